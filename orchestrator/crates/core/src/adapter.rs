@@ -4,9 +4,13 @@
 //! implements the [`Adapter`] trait so that [`CoreLogic`] can route queries
 //! without knowing provider-specific details.
 
+use async_trait::async_trait;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::prompt::Prompt;
+
 /// Errors produced by adapter operations.
 #[derive(Debug, Error)]
 pub enum AdapterError {
@@ -18,6 +22,90 @@ pub enum AdapterError {
     Request(String),
     #[error("provider returned invalid response: {0}")]
     InvalidResponse(String),
+    #[error("server error {status}: {message}")]
+    ServerError { status: u16, message: String },
+    #[error("client error {status}: {message}")]
+    ClientError { status: u16, message: String },
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("request timed out")]
+    Timeout,
+}
+
+impl AdapterError {
+    /// Classify a non-success HTTP response.
+    ///
+    /// `retry_after_ms` is the provider's `Retry-After` hint, used for 429s.
+    pub fn from_status(
+        status: u16,
+        message: impl Into<String>,
+        retry_after_ms: Option<u64>,
+    ) -> Self {
+        let message = message.into();
+        match status {
+            401 | 403 => Self::Auth(message),
+            408 => Self::Timeout,
+            429 => Self::RateLimited { retry_after_ms: retry_after_ms.unwrap_or(1000) },
+            500..=599 => Self::ServerError { status, message },
+            _ => Self::ClientError { status, message },
+        }
+    }
+
+    /// Whether the same request may succeed if sent again.
+    ///
+    /// Server, network and timeout failures are transient, as is rate
+    /// limiting; client, auth and unclassified failures are not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::ServerError { .. } | Self::Network(_) | Self::Timeout | Self::RateLimited { .. }
+        )
+    }
+}
+
+/// What a provider's [health check](Adapter::health_check) says about it,
+/// coarse enough to act on: fix credentials, wait out a rate limit, or look
+/// at the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    /// The provider rejected the credentials.
+    AuthFailed,
+    /// The provider could not be reached or did not answer sensibly.
+    Unreachable,
+    RateLimited,
+}
+
+impl HealthStatus {
+    /// Classify the outcome of a health check.
+    pub fn from_check(result: &Result<(), AdapterError>) -> Self {
+        match result {
+            Ok(()) => Self::Healthy,
+            Err(e) => Self::from(e),
+        }
+    }
+}
+
+impl From<&AdapterError> for HealthStatus {
+    fn from(e: &AdapterError) -> Self {
+        match e {
+            AdapterError::Auth(_) => Self::AuthFailed,
+            AdapterError::RateLimited { .. } => Self::RateLimited,
+            _ => Self::Unreachable,
+        }
+    }
+}
+
+/// Transport failures: timeouts map to [`AdapterError::Timeout`], everything
+/// else to [`AdapterError::Network`].
+impl From<std::io::Error> for AdapterError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::TimedOut => Self::Timeout,
+            _ => Self::Network(e.to_string()),
+        }
+    }
 }
 
 /// Supported first-class providers.
@@ -31,18 +119,117 @@ pub enum Provider {
     OpenWeight,
 }
 
-impl std::fmt::Display for Provider {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Alternative names accepted when resolving a [`Provider`] from user input.
+const PROVIDER_ALIASES: &[(&str, Provider)] = &[
+    ("anthropic", Provider::Claude),
+    ("google", Provider::Gemini),
+    ("bard", Provider::Gemini),
+    ("xai", Provider::Grok),
+    ("x.ai", Provider::Grok),
+    ("open-weight", Provider::OpenWeight),
+    ("local", Provider::OpenWeight),
+    ("ollama", Provider::OpenWeight),
+    ("llama", Provider::OpenWeight),
+    ("mistral", Provider::OpenWeight),
+];
+
+impl Provider {
+    /// Every provider, in declaration order.
+    pub const ALL: [Provider; 5] = [
+        Self::Claude,
+        Self::Gemini,
+        Self::Grok,
+        Self::Manus,
+        Self::OpenWeight,
+    ];
+
+    /// Resolve a user-supplied provider or model name.
+    ///
+    /// Matching ignores case and surrounding whitespace and accepts vendor
+    /// aliases (`"anthropic"` → `Claude`). Failing that, the leading word of a
+    /// model name is tried, so `"claude-3-opus"` and `"gemini-2.0-flash"`
+    /// route to their providers.
+    pub fn resolve(name: &str) -> Result<Self, UnknownProvider> {
+        let normalized = name.trim().to_ascii_lowercase().replace(['_', ' '], "-");
+        let lookup = |s: &str| {
+            Self::ALL
+                .into_iter()
+                .find(|p| p.as_str() == s)
+                .or_else(|| PROVIDER_ALIASES.iter().find(|(a, _)| *a == s).map(|(_, p)| *p))
+        };
+        let leading: String = normalized.chars().take_while(char::is_ascii_alphabetic).collect();
+        lookup(&normalized)
+            .or_else(|| lookup(&leading))
+            .ok_or_else(|| UnknownProvider(name.into()))
+    }
+
+    /// Canonical lowercase name, as used on the wire.
+    pub fn as_str(&self) -> &'static str {
         match self {
-            Self::Claude => write!(f, "claude"),
-            Self::Gemini => write!(f, "gemini"),
-            Self::Grok => write!(f, "grok"),
-            Self::Manus => write!(f, "manus"),
-            Self::OpenWeight => write!(f, "openweight"),
+            Self::Claude => "claude",
+            Self::Gemini => "gemini",
+            Self::Grok => "grok",
+            Self::Manus => "manus",
+            Self::OpenWeight => "openweight",
         }
     }
 }
 
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returned when a string does not name a known [`Provider`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("unknown provider: {0:?} (expected one of: claude, gemini, grok, manus, openweight)")]
+pub struct UnknownProvider(pub String);
+
+/// Parses leniently; see [`Provider::resolve`].
+impl std::str::FromStr for Provider {
+    type Err = UnknownProvider;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::resolve(s)
+    }
+}
+
+/// Size class of a provider's model, used to route queries by difficulty.
+///
+/// Tiers are ordered from smallest to largest, so routing can fall back to
+/// a larger model when no adapter of the requested tier is available.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelTier {
+    /// Small, cheap, low-latency models for simple queries.
+    Fast,
+    #[default]
+    Balanced,
+    /// The largest models, for hard queries.
+    Powerful,
+}
+
+impl ModelTier {
+    /// Every tier, smallest first.
+    pub const ALL: [ModelTier; 3] = [Self::Fast, Self::Balanced, Self::Powerful];
+}
+
+/// Optional capabilities an adapter's model supports; see
+/// [`Adapter::features`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Replies arrive incrementally from [`Adapter::chat_stream`].
+    Streaming,
+    /// [`Adapter::chat_batch`] uses a provider batch endpoint.
+    NativeBatch,
+    /// The model can call tools.
+    ToolUse,
+    /// The model accepts images.
+    Vision,
+}
+
 /// Per-provider configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdapterConfig {
@@ -53,10 +240,121 @@ pub struct AdapterConfig {
     pub model: String,
     /// Maximum tokens to generate per request.
     pub max_tokens: u32,
+    /// Size class of `model`; see [`Adapter::tier`].
+    #[serde(default)]
+    pub tier: ModelTier,
+    /// Deadline for queries sent to this provider, in milliseconds; see
+    /// [`resolve_timeout`](crate::logic::resolve_timeout).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl AdapterConfig {
+    /// Default generation limit used by [`AdapterConfigBuilder`].
+    pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+    /// Start building a validated configuration for `provider`.
+    pub fn builder(provider: Provider) -> AdapterConfigBuilder {
+        AdapterConfigBuilder::new(provider)
+    }
+
+    /// [`timeout_ms`](Self::timeout_ms) as a duration.
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_ms.map(std::time::Duration::from_millis)
+    }
+}
+
+/// Errors produced when building an [`AdapterConfig`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("missing required field: {0}")]
+    Missing(&'static str),
+    #[error("invalid base_url {url:?}: {reason}")]
+    InvalidUrl { url: String, reason: String },
+    #[error("max_tokens must be greater than zero")]
+    ZeroMaxTokens,
+    #[error("adapter pool needs at least one backend")]
+    EmptyPool,
+    #[error("pool backend serves {found}, expected {expected}")]
+    MixedPool { expected: Provider, found: Provider },
+}
+
+/// Fluent builder for [`AdapterConfig`] that validates on [`build`](Self::build).
+#[derive(Debug, Clone)]
+pub struct AdapterConfigBuilder {
+    provider: Provider,
+    base_url: Option<String>,
+    model: Option<String>,
+    max_tokens: u32,
+    tier: ModelTier,
+    timeout_ms: Option<u64>,
+}
+
+impl AdapterConfigBuilder {
+    pub fn new(provider: Provider) -> Self {
+        Self {
+            provider,
+            base_url: None,
+            model: None,
+            max_tokens: AdapterConfig::DEFAULT_MAX_TOKENS,
+            tier: ModelTier::default(),
+            timeout_ms: None,
+        }
+    }
+
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn tier(mut self, tier: ModelTier) -> Self {
+        self.tier = tier;
+        self
+    }
+
+    pub fn timeout_ms(mut self, ms: u64) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+
+    /// Validate and produce the configuration.
+    ///
+    /// `base_url` must be an absolute `http`/`https` URL and `max_tokens`
+    /// must be non-zero.
+    pub fn build(self) -> Result<AdapterConfig, ConfigError> {
+        let base_url = self.base_url.ok_or(ConfigError::Missing("base_url"))?;
+        let model = self.model.ok_or(ConfigError::Missing("model"))?;
+        let invalid = |reason: String| ConfigError::InvalidUrl { url: base_url.clone(), reason };
+        let parsed = url::Url::parse(&base_url).map_err(|e| invalid(e.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(invalid(format!("unsupported scheme `{}`", parsed.scheme())));
+        }
+        if self.max_tokens == 0 {
+            return Err(ConfigError::ZeroMaxTokens);
+        }
+        Ok(AdapterConfig {
+            provider: self.provider,
+            base_url,
+            model,
+            max_tokens: self.max_tokens,
+            tier: self.tier,
+            timeout_ms: self.timeout_ms,
+        })
+    }
 }
 
 /// A single message in a conversation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Message {
     pub role: Role,
     pub content: String,
@@ -71,39 +369,300 @@ pub enum Role {
     Assistant,
 }
 
+/// Why the model stopped generating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// Natural end of the answer.
+    #[default]
+    Stop,
+    /// Output was cut off at `max_tokens`.
+    Length,
+    /// Output was withheld by the provider's safety filter.
+    ContentFilter,
+}
+
+/// One piece of a model's output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text(String),
+    /// An image, with `data` base64-encoded as the provider returned it.
+    Image { mime: String, data: String },
+    /// A structured block, e.g. a tool call or a JSON-mode answer.
+    Json(serde_json::Value),
+}
+
+impl ContentBlock {
+    /// The text blocks of `blocks`, concatenated in order.
+    pub fn text(blocks: &[ContentBlock]) -> String {
+        blocks
+            .iter()
+            .filter_map(|b| match b {
+                Self::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 /// Provider response after model generation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelResponse {
     pub provider: Provider,
     pub model: String,
+    /// The text of the answer: for a multimodal answer, its text blocks
+    /// concatenated.
     pub content: String,
+    /// Every block of a multimodal answer, in order. Empty when the
+    /// provider answered with plain text only.
+    #[serde(default)]
+    pub blocks: Vec<ContentBlock>,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// End-to-end time of the call, including local request building and
+    /// response parsing. Same as [`total_ms`](Self::total_ms).
     pub latency_ms: u64,
+    /// Time spent waiting on the provider over the network.
+    #[serde(default)]
+    pub network_ms: u64,
+    #[serde(default)]
+    pub finish_reason: FinishReason,
+}
+
+impl ModelResponse {
+    /// End-to-end time of the call; `latency_ms` under its split name.
+    pub fn total_ms(&self) -> u64 {
+        self.latency_ms
+    }
 }
 
+/// Content chunks of a streamed reply, as produced by
+/// [`Adapter::chat_stream`].
+pub type ChunkStream<'a> = futures::stream::BoxStream<'a, Result<String, AdapterError>>;
+
 /// Unified Integration Interface — the single abstraction that every AI
 /// provider must implement to participate in the orchestrator.
+///
+/// The trait is object-safe so heterogeneous adapters can be held together in
+/// an [`AdapterRegistry`](crate::registry::AdapterRegistry).
+#[async_trait]
 pub trait Adapter: Send + Sync {
     /// Return the provider this adapter serves.
     fn provider(&self) -> Provider;
 
+    /// Size class of the model behind this adapter, matched against
+    /// [`Query::tier`](crate::logic::Query::tier) when routing.
+    fn tier(&self) -> ModelTier {
+        ModelTier::Balanced
+    }
+
+    /// Identifier of the model behind this adapter, if known.
+    fn model(&self) -> Option<String> {
+        None
+    }
+
+    /// Capabilities of the model behind this adapter. Default: none.
+    fn features(&self) -> Vec<Feature> {
+        Vec::new()
+    }
+
     /// Send a conversation and receive a model response.
-    fn chat(
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError>;
+
+    /// Send a [`Prompt`] assembled for this provider, keeping a top-level
+    /// system prompt separate from the messages.
+    ///
+    /// The default flattens the prompt with [`Prompt::into_messages`] and
+    /// calls [`chat`](Self::chat); providers whose API takes the system
+    /// prompt as its own field override it, and decorators forward it.
+    async fn chat_prompt(&self, prompt: &Prompt) -> Result<ModelResponse, AdapterError> {
+        self.chat(&prompt.clone().into_messages()).await
+    }
+
+    /// Send several independent conversations, returning one result per
+    /// conversation in the same order.
+    ///
+    /// The default runs every `chat` concurrently; providers with a native
+    /// batch endpoint can override it.
+    async fn chat_batch(
         &self,
-        messages: &[Message],
-    ) -> impl std::future::Future<Output = Result<ModelResponse, AdapterError>> + Send;
+        conversations: &[Vec<Message>],
+    ) -> Vec<Result<ModelResponse, AdapterError>> {
+        futures::future::join_all(conversations.iter().map(|c| self.chat(c))).await
+    }
+
+    /// Stream the reply to a conversation as content chunks.
+    ///
+    /// The default yields the whole [`chat`](Self::chat) reply as a single
+    /// chunk; providers with a streaming endpoint override it. See
+    /// [`stream`](crate::stream) for consuming the chunks without buffering
+    /// the full reply.
+    fn chat_stream<'a>(&'a self, messages: &'a [Message]) -> ChunkStream<'a> {
+        Box::pin(futures::stream::once(async move {
+            self.chat(messages).await.map(|response| response.content)
+        }))
+    }
+
+    /// [`chat_stream`](Self::chat_stream) for a [`Prompt`], as
+    /// [`chat_prompt`](Self::chat_prompt) is to [`chat`](Self::chat).
+    fn chat_prompt_stream<'a>(&'a self, prompt: &'a Prompt) -> ChunkStream<'a> {
+        if prompt.system.is_none() {
+            return self.chat_stream(&prompt.messages);
+        }
+        // The stream borrows the flattened messages, so it is driven here
+        // and its chunks forwarded through a channel.
+        let messages = prompt.clone().into_messages();
+        let (chunks, received) = futures::channel::mpsc::unbounded();
+        let pump = async move {
+            let mut stream = self.chat_stream(&messages);
+            while let Some(chunk) = stream.next().await {
+                if chunks.unbounded_send(chunk).is_err() {
+                    return;
+                }
+            }
+        };
+        let pump = futures::stream::once(pump).filter_map(|()| async { None });
+        Box::pin(futures::stream::select(received, pump))
+    }
 
     /// Lightweight connectivity / auth check.
-    fn health_check(
+    ///
+    /// Implementations should report rejected credentials as
+    /// [`AdapterError::Auth`] and transport failures as
+    /// [`AdapterError::Network`], so [`health_status`](Self::health_status)
+    /// can tell them apart.
+    async fn health_check(&self) -> Result<(), AdapterError>;
+
+    /// [`health_check`](Self::health_check), classified.
+    async fn health_status(&self) -> HealthStatus {
+        HealthStatus::from_check(&self.health_check().await)
+    }
+
+    /// Prepare for traffic ahead of the first request, e.g. resolve DNS,
+    /// open a pooled TLS connection and validate credentials, so the first
+    /// real `chat` does not pay the cold-start cost.
+    ///
+    /// Called once at startup by
+    /// [`AdapterRegistry::warm_up`](crate::registry::AdapterRegistry::warm_up).
+    /// The default does nothing.
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        Ok(())
+    }
+}
+
+/// Prompt sent to ask the model to pick up where a truncated answer stopped.
+pub const CONTINUE_PROMPT: &str = "continue";
+
+/// Adapter decorator that transparently continues truncated generations.
+///
+/// When the inner adapter finishes with [`FinishReason::Length`], the partial
+/// answer is appended to the conversation as an assistant message followed by
+/// a [`CONTINUE_PROMPT`] user message, and the request is repeated. Content
+/// and blocks are concatenated and token counts / latency are summed across
+/// rounds.
+pub struct ContinuingAdapter<A> {
+    inner: A,
+    max_rounds: usize,
+}
+
+impl<A: Adapter> ContinuingAdapter<A> {
+    /// Wrap `inner`, issuing at most `max_rounds` requests per `chat`.
+    pub fn new(inner: A, max_rounds: usize) -> Self {
+        Self { inner, max_rounds }
+    }
+
+    /// Chat until the model stops on its own or `max_rounds` requests have
+    /// been made. The final `finish_reason` is that of the last round.
+    pub async fn chat_until_complete(
+        &self,
+        messages: &[Message],
+        max_rounds: usize,
+    ) -> Result<ModelResponse, AdapterError> {
+        let prompt = Prompt { system: None, messages: messages.to_vec() };
+        self.prompt_until_complete(prompt, max_rounds).await
+    }
+
+    /// [`chat_until_complete`](Self::chat_until_complete) for a [`Prompt`],
+    /// whose system prompt is kept for every round.
+    async fn prompt_until_complete(
         &self,
-        ) -> impl std::future::Future<Output = Result<(), AdapterError>> + Send;
+        mut prompt: Prompt,
+        max_rounds: usize,
+    ) -> Result<ModelResponse, AdapterError> {
+        let mut combined = self.inner.chat_prompt(&prompt).await?;
+        let mut rounds = 1;
+        while combined.finish_reason == FinishReason::Length && rounds < max_rounds {
+            let conversation = &mut prompt.messages;
+            conversation.push(Message { role: Role::Assistant, content: combined.content.clone() });
+            conversation.push(Message { role: Role::User, content: CONTINUE_PROMPT.into() });
+            let next = self.inner.chat_prompt(&prompt).await?;
+            combined.content.push_str(&next.content);
+            combined.blocks.extend(next.blocks);
+            combined.input_tokens += next.input_tokens;
+            combined.output_tokens += next.output_tokens;
+            combined.latency_ms += next.latency_ms;
+            combined.network_ms += next.network_ms;
+            combined.finish_reason = next.finish_reason;
+            rounds += 1;
+        }
+        Ok(combined)
+    }
+}
+
+#[async_trait]
+impl<A: Adapter> Adapter for ContinuingAdapter<A> {
+    fn provider(&self) -> Provider {
+        self.inner.provider()
+    }
+
+    fn tier(&self) -> ModelTier {
+        self.inner.tier()
+    }
+
+    fn model(&self) -> Option<String> {
+        self.inner.model()
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.inner.features()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        self.chat_until_complete(messages, self.max_rounds).await
+    }
+
+    async fn chat_prompt(&self, prompt: &Prompt) -> Result<ModelResponse, AdapterError> {
+        self.prompt_until_complete(prompt.clone(), self.max_rounds).await
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        self.inner.health_check().await
+    }
+
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        self.inner.warm_up().await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn health_status_tells_auth_from_connectivity() {
+        let unauthorized = AdapterError::from_status(401, "invalid x-api-key", None);
+        assert_eq!(HealthStatus::from_check(&Err(unauthorized)), HealthStatus::AuthFailed);
+
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let refused = AdapterError::from(refused);
+        assert_eq!(HealthStatus::from_check(&Err(refused)), HealthStatus::Unreachable);
+
+        let limited = AdapterError::from_status(429, "slow down", Some(500));
+        assert_eq!(HealthStatus::from(&limited), HealthStatus::RateLimited);
+        assert_eq!(HealthStatus::from_check(&Ok(())), HealthStatus::Healthy);
+    }
+
     #[test]
     fn provider_display() {
         assert_eq!(Provider::Claude.to_string(), "claude");
@@ -111,6 +670,70 @@ mod tests {
         assert_eq!(Provider::OpenWeight.to_string(), "openweight");
     }
 
+    #[test]
+    fn provider_parses_its_display_name() {
+        for p in Provider::ALL {
+            assert_eq!(p.to_string().parse::<Provider>(), Ok(p));
+        }
+        assert_eq!("cluade".parse::<Provider>(), Err(UnknownProvider("cluade".into())));
+    }
+
+    #[test]
+    fn provider_aliases_and_model_names_resolve() {
+        let cases = [
+            ("Claude", Provider::Claude),
+            (" anthropic ", Provider::Claude),
+            ("claude-3-opus", Provider::Claude),
+            ("claude-sonnet-4-20250514", Provider::Claude),
+            ("Google", Provider::Gemini),
+            ("gemini-2.0-flash", Provider::Gemini),
+            ("xAI", Provider::Grok),
+            ("grok-2", Provider::Grok),
+            ("Open Weight", Provider::OpenWeight),
+            ("llama3:8b", Provider::OpenWeight),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(Provider::resolve(name), Ok(expected), "{name}");
+        }
+    }
+
+    #[test]
+    fn unknown_provider_lists_valid_names() {
+        let err = Provider::resolve("gpt-4").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown provider: \"gpt-4\" (expected one of: claude, gemini, grok, manus, openweight)"
+        );
+        assert!(Provider::resolve("").is_err());
+    }
+
+    #[test]
+    fn http_statuses_are_classified() {
+        let class = |status| AdapterError::from_status(status, "body", Some(250));
+        assert!(matches!(class(500), AdapterError::ServerError { status: 500, .. }));
+        assert!(matches!(class(503), AdapterError::ServerError { status: 503, .. }));
+        assert!(matches!(class(400), AdapterError::ClientError { status: 400, .. }));
+        assert!(matches!(class(404), AdapterError::ClientError { status: 404, .. }));
+        assert!(matches!(class(401), AdapterError::Auth(_)));
+        assert!(matches!(class(408), AdapterError::Timeout));
+        assert!(matches!(class(429), AdapterError::RateLimited { retry_after_ms: 250 }));
+
+        assert!(class(502).is_retryable());
+        assert!(class(429).is_retryable());
+        assert!(!class(422).is_retryable());
+        assert!(!class(403).is_retryable());
+    }
+
+    #[test]
+    fn transport_errors_are_classified() {
+        use std::io::{Error, ErrorKind};
+        let timeout = AdapterError::from(Error::new(ErrorKind::TimedOut, "read timed out"));
+        assert!(matches!(timeout, AdapterError::Timeout));
+        let refused = AdapterError::from(Error::new(ErrorKind::ConnectionRefused, "refused"));
+        assert!(matches!(refused, AdapterError::Network(ref m) if m == "refused"));
+        assert!(timeout.is_retryable() && refused.is_retryable());
+    }
+
     #[test]
     fn provider_serde_roundtrip() {
         let json = serde_json::to_string(&Provider::Gemini).unwrap();
@@ -126,10 +749,156 @@ mod tests {
             base_url: "https://api.anthropic.com".into(),
             model: "claude-sonnet-4-20250514".into(),
             max_tokens: 4096,
+            tier: ModelTier::Fast,
+            timeout_ms: Some(30_000),
         };
         let json = serde_json::to_string(&cfg).unwrap();
         let back: AdapterConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(back.provider, Provider::Claude);
         assert_eq!(back.max_tokens, 4096);
+        assert_eq!(back.tier, ModelTier::Fast);
+        assert_eq!(back.timeout(), Some(std::time::Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn builder_applies_defaults() {
+        let cfg = AdapterConfig::builder(Provider::Gemini)
+            .base_url("https://generativelanguage.googleapis.com")
+            .model("gemini-2.0-flash")
+            .build()
+            .unwrap();
+        assert_eq!(cfg.provider, Provider::Gemini);
+        assert_eq!(cfg.model, "gemini-2.0-flash");
+        assert_eq!(cfg.max_tokens, AdapterConfig::DEFAULT_MAX_TOKENS);
+        assert_eq!(cfg.tier, ModelTier::Balanced);
+    }
+
+    #[test]
+    fn builder_rejects_bad_url() {
+        let err = AdapterConfig::builder(Provider::Claude)
+            .base_url("not a url")
+            .model("claude-sonnet-4-20250514")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidUrl { .. }));
+
+        let err = AdapterConfig::builder(Provider::Claude)
+            .base_url("ftp://api.anthropic.com")
+            .model("claude-sonnet-4-20250514")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported scheme `ftp`"));
+    }
+
+    #[test]
+    fn builder_rejects_zero_max_tokens() {
+        let err = AdapterConfig::builder(Provider::Grok)
+            .base_url("https://api.x.ai")
+            .model("grok-2")
+            .max_tokens(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::ZeroMaxTokens);
+    }
+
+    #[test]
+    fn builder_requires_model() {
+        let err = AdapterConfig::builder(Provider::Grok)
+            .base_url("https://api.x.ai")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::Missing("model"));
+    }
+
+    #[test]
+    fn finish_reason_defaults_to_stop() {
+        let json = r#"{"provider":"claude","model":"m","content":"hi","input_tokens":1,"output_tokens":1,"latency_ms":5}"#;
+        let resp: ModelResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.finish_reason, FinishReason::Stop);
+    }
+
+    #[tokio::test]
+    async fn continues_truncated_response() {
+        use crate::mock::MockAdapter;
+
+        let mock = MockAdapter::new(Provider::Claude).with_script([
+            ("The quick brown ", FinishReason::Length),
+            ("fox jumps.", FinishReason::Stop),
+        ]);
+        let adapter = ContinuingAdapter::new(mock, 4);
+        let msgs = vec![Message { role: Role::User, content: "finish the sentence".into() }];
+
+        let resp = adapter.chat(&msgs).await.unwrap();
+        assert_eq!(resp.content, "The quick brown fox jumps.");
+        assert_eq!(resp.finish_reason, FinishReason::Stop);
+        assert_eq!(resp.output_tokens, 5);
+
+        let requests = adapter.inner.requests();
+        assert_eq!(requests.len(), 2);
+        let followup = &requests[1];
+        assert_eq!(followup.len(), 3);
+        assert_eq!(followup[1].role, Role::Assistant);
+        assert_eq!(followup[1].content, "The quick brown ");
+        assert_eq!(followup[2].content, CONTINUE_PROMPT);
+    }
+
+    #[tokio::test]
+    async fn continuation_keeps_the_top_level_system_prompt() {
+        use crate::mock::MockAdapter;
+
+        let mock = MockAdapter::new(Provider::Claude)
+            .with_script([("half", FinishReason::Length), (" done", FinishReason::Stop)]);
+        let adapter = ContinuingAdapter::new(mock, 4);
+        let prompt = Prompt {
+            system: Some("be brief".into()),
+            messages: vec![Message { role: Role::User, content: "go".into() }],
+        };
+        assert_eq!(adapter.chat_prompt(&prompt).await.unwrap().content, "half done");
+        let prompts = adapter.inner.prompts();
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts.iter().all(|p| p.system.as_deref() == Some("be brief")));
+        assert_eq!(prompts[1].messages.len(), 3);
+
+        // Without a `chat_prompt_stream` override, the prompt is flattened.
+        let streamed: Vec<_> = adapter.chat_prompt_stream(&prompt).collect().await;
+        assert_eq!(streamed.len(), 1);
+        assert_eq!(adapter.inner.requests()[2][0].role, Role::System);
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_rounds() {
+        use crate::mock::MockAdapter;
+
+        let mock = MockAdapter::new(Provider::Claude).with_script([
+            ("a", FinishReason::Length),
+            ("b", FinishReason::Length),
+            ("c", FinishReason::Length),
+        ]);
+        let adapter = ContinuingAdapter::new(mock, 2);
+        let resp = adapter.chat(&[]).await.unwrap();
+        assert_eq!(resp.content, "ab");
+        assert_eq!(resp.finish_reason, FinishReason::Length);
+        assert_eq!(adapter.inner.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn chat_batch_runs_concurrently_in_order() {
+        use crate::mock::MockAdapter;
+
+        let mock =
+            MockAdapter::new(Provider::Claude).with_delay(std::time::Duration::from_millis(50));
+        let conversations: Vec<Vec<Message>> = ["a", "b c", "d e f"]
+            .into_iter()
+            .map(|text| vec![Message { role: Role::User, content: text.into() }])
+            .collect();
+
+        let started = tokio::time::Instant::now();
+        let results = mock.chat_batch(&conversations).await;
+        assert_eq!(started.elapsed(), std::time::Duration::from_millis(50));
+
+        let inputs: Vec<u32> = results.iter().map(|r| r.as_ref().unwrap().input_tokens).collect();
+        assert_eq!(inputs, [1, 2, 3]);
+        assert_eq!(mock.calls(), 3);
+        assert!(mock.chat_batch(&[]).await.is_empty());
     }
 }
@@ -1,7 +1,6 @@
 use async_trait::async_trait;
-use crate::protocol::Message;
+use crate::protocol::{Message, SystemEvent};
 use crate::capability::CapabilityInfo;
-use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct AgentMetadata {
@@ -23,6 +22,33 @@ pub trait Agent: Send + Sync {
     /// Called on every tick of the orchestrator loop.
     async fn tick(&mut self) -> anyhow::Result<Vec<Message>>;
 
+    /// Message kinds (e.g. `"command"`) or topics this agent wants delivered
+    /// to [`on_message`](Self::on_message); see [`Message::matches_interest`].
+    ///
+    /// Read once when the agent is registered. Defaults to
+    /// [`ALL_MESSAGES`], i.e. every message.
+    fn interests(&self) -> Vec<String> {
+        vec![ALL_MESSAGES.into()]
+    }
+
     /// Called when a message is received from the bus.
     async fn on_message(&mut self, message: Message) -> anyhow::Result<()>;
-}
\ No newline at end of file
+
+    /// Called with each [`SystemEvent`] broadcast by the orchestrator, before
+    /// any message of the same round and regardless of
+    /// [`interests`](Self::interests). The default ignores it.
+    async fn on_system(&mut self, _event: SystemEvent) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Whether the agent has work in progress that returns no messages,
+    /// e.g. tasks running or queued. A busy agent keeps an orchestrator with
+    /// an [idle timeout](crate::orchestrator::Orchestrator::with_idle_timeout)
+    /// running. The default is never busy.
+    fn is_busy(&self) -> bool {
+        false
+    }
+}
+
+/// Interest matching every message.
+pub const ALL_MESSAGES: &str = "*";
\ No newline at end of file
@@ -1,21 +1,758 @@
-use tokio::sync::broadcast;
-use crate::protocol::Message;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::memory::{MemoryError, MemorySystem};
+use crate::metrics::Metrics;
+use crate::protocol::{LogEntry, LogLevel, LogSink, Message, MessageKind};
+
+/// Errors produced by [`MessageBus`] operations.
+#[derive(Debug, Error)]
+pub enum MessageBusError {
+    #[error("publish failed: no active subscribers")]
+    NoSubscribers,
+    #[error("journal error: {0}")]
+    Journal(#[from] MemoryError),
+}
+
+/// Durable log of published messages, so that messages not yet consumed
+/// survive a restart.
+#[async_trait]
+pub trait BusJournal: Send + Sync {
+    /// Record `message` before it is broadcast.
+    async fn append(&self, message: &Message) -> Result<(), MemoryError>;
+
+    /// Mark `message_id` as consumed; it will no longer be replayed.
+    async fn ack(&self, message_id: Uuid) -> Result<(), MemoryError>;
+
+    /// Messages appended but not yet acknowledged, in publish order.
+    async fn pending(&self) -> Result<Vec<Message>, MemoryError>;
+}
+
+/// [`BusJournal`] storing each message under `bus:<id>` in a [`MemorySystem`].
+pub struct MemoryJournal<M> {
+    memory: Arc<M>,
+}
+
+impl<M: MemorySystem> MemoryJournal<M> {
+    pub fn new(memory: Arc<M>) -> Self {
+        Self { memory }
+    }
+}
+
+const JOURNAL_PREFIX: &str = "bus:";
+
+#[async_trait]
+impl<M: MemorySystem + 'static> BusJournal for MemoryJournal<M> {
+    async fn append(&self, message: &Message) -> Result<(), MemoryError> {
+        let at = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let entry = serde_json::json!({ "at": at, "message": message });
+        self.memory.store(&format!("{JOURNAL_PREFIX}{}", message.id), entry).await?;
+        Ok(())
+    }
+
+    async fn ack(&self, message_id: Uuid) -> Result<(), MemoryError> {
+        match self.memory.remove(&format!("{JOURNAL_PREFIX}{message_id}")).await {
+            Ok(()) | Err(MemoryError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn pending(&self) -> Result<Vec<Message>, MemoryError> {
+        let mut entries = Vec::new();
+        for key in self.memory.keys().await? {
+            if !key.starts_with(JOURNAL_PREFIX) {
+                continue;
+            }
+            let value = self.memory.load(&key).await?.value;
+            let at = value["at"].as_i64().unwrap_or_default();
+            let message: Message = serde_json::from_value(value["message"].clone())
+                .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+            entries.push((at, message));
+        }
+        entries.sort_by_key(|(at, _)| *at);
+        Ok(entries.into_iter().map(|(_, m)| m).collect())
+    }
+}
 
 pub struct MessageBus {
     sender: broadcast::Sender<Message>,
+    capacity: usize,
+    journal: Option<Arc<dyn BusJournal>>,
+    reliable: ReliableChannel,
+    reliable_topics: HashSet<String>,
+    last_activity: Mutex<Instant>,
 }
 
 impl MessageBus {
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(capacity);
-        Self { sender }
+        Self {
+            sender,
+            capacity,
+            journal: None,
+            reliable: ReliableChannel::new(capacity),
+            reliable_topics: HashSet::new(),
+            last_activity: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Deliver messages with payload [topic](Message::topic) `topic` through
+    /// the bus's [`ReliableChannel`] instead of the broadcast, so they reach
+    /// only [reliable subscribers](Self::subscribe_reliable).
+    pub fn with_reliable_topic(mut self, topic: impl Into<String>) -> Self {
+        self.reliable_topics.insert(topic.into());
+        self
+    }
+
+    /// How long a reliable subscriber waits for an ack before redelivering;
+    /// see [`ReliableChannel::with_redelivery_after`].
+    pub fn with_redelivery_after(mut self, timeout: Duration) -> Self {
+        self.reliable = self.reliable.with_redelivery_after(timeout);
+        self
+    }
+
+    /// How many acknowledged ids a reliable subscriber remembers; see
+    /// [`ReliableChannel::with_dedup_window`].
+    pub fn with_dedup_window(mut self, window: usize) -> Self {
+        self.reliable = self.reliable.with_dedup_window(window);
+        self
+    }
+
+    /// How long publishing waits on a full reliable subscriber queue; see
+    /// [`ReliableChannel::with_send_timeout`].
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.reliable = self.reliable.with_send_timeout(timeout);
+        self
+    }
+
+    /// Journal every published message so it can be [replayed](Self::replay)
+    /// after a restart until it is [acknowledged](Self::ack).
+    pub fn with_journal(mut self, journal: Arc<dyn BusJournal>) -> Self {
+        self.journal = Some(journal);
+        self
     }
 
     pub fn subscribe(&self) -> broadcast::Receiver<Message> {
         self.sender.subscribe()
     }
 
-    pub fn publish(&self, message: Message) -> anyhow::Result<usize> {
-        self.sender.send(message).map_err(|e| anyhow::anyhow!("Publish failed: {}", e))
+    /// Subscribe on behalf of `agent_id`, logging to `log` whenever the
+    /// agent falls behind; see [`BusSubscriber`].
+    pub fn subscribe_agent<S: LogSink>(
+        &self,
+        agent_id: impl Into<String>,
+        log: S,
+    ) -> BusSubscriber<S> {
+        BusSubscriber {
+            agent_id: agent_id.into(),
+            receiver: self.sender.subscribe(),
+            sender: self.sender.clone(),
+            log,
+            resync: false,
+            resync_pending: false,
+            dropped: 0,
+        }
+    }
+
+    /// Subscribe to the [reliable topics](Self::with_reliable_topic).
+    pub fn subscribe_reliable(&self) -> ReliableSubscriber {
+        self.reliable.subscribe()
+    }
+
+    /// Broadcast `message`, journaling it first when a journal is configured.
+    /// Messages on a [reliable topic](Self::with_reliable_topic) are queued
+    /// for every reliable subscriber instead, waiting while a queue is full.
+    ///
+    /// A journaled message is kept even if nobody is subscribed yet.
+    pub async fn publish(&self, message: Message) -> Result<usize, MessageBusError> {
+        if let Some(journal) = &self.journal {
+            journal.append(&message).await?;
+        }
+        self.route(message).await
     }
-}
\ No newline at end of file
+
+    /// When a message was last published or replayed, or when the bus was
+    /// created if none has been.
+    pub fn last_activity(&self) -> Instant {
+        *self.last_activity.lock().unwrap()
+    }
+
+    async fn route(&self, message: Message) -> Result<usize, MessageBusError> {
+        *self.last_activity.lock().unwrap() = Instant::now();
+        match message.topic() {
+            Some(topic) if self.reliable_topics.contains(topic) => {
+                self.reliable.send(message).await
+            }
+            _ => self.sender.send(message).map_err(|_| MessageBusError::NoSubscribers),
+        }
+    }
+
+    /// Acknowledge that `message_id` has been consumed. No-op without a journal.
+    pub async fn ack(&self, message_id: Uuid) -> Result<(), MessageBusError> {
+        if let Some(journal) = &self.journal {
+            journal.ack(message_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Re-broadcast every journaled message not yet acknowledged, in publish
+    /// order, returning how many were re-sent.
+    pub async fn replay(&self) -> Result<usize, MessageBusError> {
+        let Some(journal) = &self.journal else { return Ok(0) };
+        let pending = journal.pending().await?;
+        let count = pending.len();
+        for message in pending {
+            self.route(message).await?;
+        }
+        Ok(count)
+    }
+}
+
+/// Ordered, at-least-once delivery, for control messages that must not be
+/// lost to lag.
+///
+/// Every [subscriber](Self::subscribe) gets its own bounded queue, and
+/// [`send`](Self::send) waits for room in each rather than overwriting, so a
+/// slow subscriber slows the sender down instead of missing messages. A
+/// subscriber whose queue stays full for the [send
+/// timeout](Self::with_send_timeout) is taken for stalled and evicted, so it
+/// cannot hold the sender up forever. A message handed out by
+/// [`ReliableSubscriber::recv`] is redelivered until it is
+/// [acknowledged](ReliableSubscriber::ack).
+pub struct ReliableChannel {
+    capacity: usize,
+    redeliver_after: Duration,
+    dedup_window: usize,
+    send_timeout: Duration,
+    subscribers: Mutex<Vec<mpsc::Sender<Message>>>,
+}
+
+impl ReliableChannel {
+    /// Queues hold up to `capacity` messages; unacknowledged messages are
+    /// redelivered after 5 seconds, and a subscriber is evicted after its
+    /// queue has been full for 30.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            redeliver_after: Duration::from_secs(5),
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            send_timeout: Duration::from_secs(30),
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Applies to subscribers created afterwards.
+    pub fn with_redelivery_after(mut self, timeout: Duration) -> Self {
+        self.redeliver_after = timeout;
+        self
+    }
+
+    /// Remember the ids of the last `window` acknowledged messages, instead
+    /// of [`DEFAULT_DEDUP_WINDOW`], to drop copies of them. A copy of an
+    /// older message is delivered again, so size the window to cover a
+    /// [journal replay](MessageBus::replay): every reliable message
+    /// published since the journal was last acknowledged. Applies to
+    /// subscribers created afterwards.
+    pub fn with_dedup_window(mut self, window: usize) -> Self {
+        self.dedup_window = window;
+        self
+    }
+
+    /// Wait at most `timeout` for room in a subscriber's queue. A subscriber
+    /// still full after that is dropped from the channel: it receives what
+    /// is already queued, then [`recv`](ReliableSubscriber::recv) winds down
+    /// as if the channel were gone, and it must subscribe again.
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = timeout;
+        self
+    }
+
+    pub fn subscribe(&self) -> ReliableSubscriber {
+        let (sender, receiver) = mpsc::channel(self.capacity);
+        self.subscribers.lock().unwrap().push(sender);
+        ReliableSubscriber {
+            receiver,
+            redeliver_after: self.redeliver_after,
+            unacked: BTreeMap::new(),
+            next_seq: 0,
+            acked: RecentIds::new(self.dedup_window),
+        }
+    }
+
+    /// Subscribers still listening.
+    pub fn subscribers(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|s| !s.is_closed());
+        subscribers.len()
+    }
+
+    /// Queue `message` for every subscriber at once, returning how many it
+    /// reached. Subscribers that stay full for the send timeout are evicted.
+    pub async fn send(&self, message: Message) -> Result<usize, MessageBusError> {
+        let subscribers = self.subscribers.lock().unwrap().clone();
+        let sends = subscribers.iter().map(|subscriber| {
+            tokio::time::timeout(self.send_timeout, subscriber.send(message.clone()))
+        });
+        let outcomes = futures::future::join_all(sends).await;
+        let mut reached = 0;
+        let mut stalled = Vec::new();
+        for (subscriber, outcome) in subscribers.into_iter().zip(outcomes) {
+            match outcome {
+                Ok(Ok(())) => reached += 1,
+                Ok(Err(_)) => {}
+                Err(_) => stalled.push(subscriber),
+            }
+        }
+        if !stalled.is_empty() {
+            self.subscribers
+                .lock()
+                .unwrap()
+                .retain(|s| !stalled.iter().any(|gone| gone.same_channel(s)));
+        }
+        if reached == 0 {
+            return Err(MessageBusError::NoSubscribers);
+        }
+        Ok(reached)
+    }
+}
+
+/// Acknowledged ids a [`ReliableSubscriber`] remembers by default.
+pub const DEFAULT_DEDUP_WINDOW: usize = 4096;
+
+/// The most recent `capacity` ids inserted, forgetting the oldest first.
+struct RecentIds {
+    ids: HashSet<Uuid>,
+    order: VecDeque<Uuid>,
+    capacity: usize,
+}
+
+impl RecentIds {
+    fn new(capacity: usize) -> Self {
+        Self { ids: HashSet::new(), order: VecDeque::new(), capacity }
+    }
+
+    fn contains(&self, id: &Uuid) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, id: Uuid) {
+        if self.capacity == 0 || !self.ids.insert(id) {
+            return;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+}
+
+/// A subscription to a [`ReliableChannel`].
+///
+/// Messages arrive in send order. One not [acked](Self::ack) within the
+/// channel's redelivery timeout is handed out again, ahead of newer ones.
+/// Copies of a recently acknowledged message, such as a journal replay,
+/// are dropped, so each message is acked exactly once as long as its
+/// copies arrive within the channel's
+/// [dedup window](ReliableChannel::with_dedup_window).
+pub struct ReliableSubscriber {
+    receiver: mpsc::Receiver<Message>,
+    redeliver_after: Duration,
+    /// Delivered, awaiting an ack, by delivery order: the message and when
+    /// it is due again.
+    unacked: BTreeMap<u64, (Message, Instant)>,
+    next_seq: u64,
+    acked: RecentIds,
+}
+
+impl ReliableSubscriber {
+    /// The next message: an overdue redelivery if there is one, otherwise
+    /// the next queued message. `None` once the channel is gone and every
+    /// delivered message has been acknowledged.
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            let due = self.unacked.values().map(|(_, at)| *at).min();
+            if let Some(at) = due
+                && at <= Instant::now()
+            {
+                return Some(self.redeliver(at));
+            }
+            let overdue = async {
+                match due {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                received = self.receiver.recv() => match received {
+                    Some(message) => {
+                        if self.acked.contains(&message.id)
+                            || self.unacked.values().any(|(m, _)| m.id == message.id)
+                        {
+                            continue;
+                        }
+                        let at = Instant::now() + self.redeliver_after;
+                        self.unacked.insert(self.next_seq, (message.clone(), at));
+                        self.next_seq += 1;
+                        return Some(message);
+                    }
+                    // Nothing more will be sent; finish redelivering.
+                    None => {
+                        tokio::time::sleep_until(due?).await;
+                    }
+                },
+                () = overdue => {}
+            }
+        }
+    }
+
+    /// Hand out again the oldest message due at `at`.
+    fn redeliver(&mut self, at: Instant) -> Message {
+        let (message, due) = self
+            .unacked
+            .values_mut()
+            .find(|(_, due)| *due == at)
+            .expect("a message is due at `at`");
+        *due = Instant::now() + self.redeliver_after;
+        message.clone()
+    }
+
+    /// Acknowledge `message_id`, stopping its redelivery. Returns `false` if
+    /// it was not awaiting an ack.
+    pub fn ack(&mut self, message_id: Uuid) -> bool {
+        let Some(seq) = self.unacked.iter().find(|(_, (m, _))| m.id == message_id).map(|(s, _)| *s)
+        else {
+            return false;
+        };
+        self.unacked.remove(&seq);
+        self.acked.insert(message_id);
+        true
+    }
+
+    /// Messages delivered but not yet acknowledged.
+    pub fn unacked(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Acknowledged ids remembered for deduplication; at most the channel's
+    /// dedup window.
+    pub fn remembered(&self) -> usize {
+        self.acked.len()
+    }
+}
+
+/// Topic of the `Command` a lagging [`BusSubscriber`] publishes to ask for
+/// its agent's state to be resent.
+pub const RESYNC_TOPIC: &str = "resync";
+
+/// An agent's subscription to a [`MessageBus`] that tolerates lag.
+///
+/// When the agent reads too slowly, the bus overwrites messages it has not
+/// seen yet. Instead of failing, [`recv`](Self::recv) logs a `Warn` with the
+/// number of messages dropped and carries on from the oldest one still
+/// queued. With [`with_resync`](Self::with_resync) it also publishes a
+/// [`RESYNC_TOPIC`] command naming the agent, so a state owner can bring it
+/// up to date.
+pub struct BusSubscriber<S> {
+    agent_id: String,
+    receiver: broadcast::Receiver<Message>,
+    sender: broadcast::Sender<Message>,
+    log: S,
+    resync: bool,
+    resync_pending: bool,
+    dropped: u64,
+}
+
+impl<S: LogSink> BusSubscriber<S> {
+    /// Request a resync each time messages are dropped.
+    pub fn with_resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// Messages dropped over the subscription's lifetime.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// The next message, or `None` once the bus is gone.
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(message) => {
+                    if self.resync_pending {
+                        self.request_resync();
+                    }
+                    return Some(message);
+                }
+                Err(RecvError::Lagged(n)) => self.lagged(n),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn lagged(&mut self, n: u64) {
+        self.dropped += n;
+        let data = serde_json::json!({ "agent_id": self.agent_id, "dropped": n });
+        self.log.emit(
+            &LogEntry::new(
+                LogLevel::Warn,
+                "bus",
+                format!("agent {} lagged behind the bus; {n} messages dropped", self.agent_id),
+            )
+            .with_data(data),
+        );
+        // Sending now would overwrite the oldest message we have yet to
+        // read and lag us again, so wait until that one is consumed.
+        self.resync_pending = self.resync;
+    }
+
+    fn request_resync(&mut self) {
+        self.resync_pending = false;
+        let payload = serde_json::json!({
+            "topic": RESYNC_TOPIC,
+            "agent_id": self.agent_id,
+            "dropped": self.dropped,
+        });
+        // Nobody listening for resyncs is not an error for the subscriber.
+        let _ = self.sender.send(Message {
+            id: Uuid::new_v4(),
+            source: Uuid::nil(),
+            target: None,
+            kind: MessageKind::Command,
+            payload,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        });
+    }
+}
+
+impl Metrics for MessageBus {
+    fn subsystem(&self) -> &'static str {
+        "bus"
+    }
+
+    /// Live subscribers, and `lag`: messages still queued for at least one of
+    /// them, out of `capacity` before the slowest starts missing messages.
+    fn snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "subscribers": self.sender.receiver_count(),
+            "lag": self.sender.len(),
+            "capacity": self.capacity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use crate::protocol::MemoryLogSink;
+    use serde_json::json;
+
+    fn command(n: u32) -> Message {
+        Message {
+            id: Uuid::new_v4(),
+            source: Uuid::nil(),
+            target: None,
+            kind: MessageKind::Command,
+            payload: json!({ "n": n }),
+            timestamp: 0,
+        }
+    }
+
+    fn journaled(store: &Arc<InMemoryStore>) -> MessageBus {
+        MessageBus::new(16).with_journal(Arc::new(MemoryJournal::new(store.clone())))
+    }
+
+    #[tokio::test]
+    async fn replay_redelivers_unacknowledged_messages_after_restart() {
+        let store = Arc::new(InMemoryStore::new());
+        let sent: Vec<Message> = (1..=3).map(command).collect();
+        {
+            let bus = journaled(&store);
+            let mut rx = bus.subscribe();
+            for m in &sent {
+                bus.publish(m.clone()).await.unwrap();
+            }
+            // Only the first message is consumed before the "crash".
+            let first = rx.recv().await.unwrap();
+            bus.ack(first.id).await.unwrap();
+        }
+
+        let bus = journaled(&store);
+        let mut rx = bus.subscribe();
+        assert_eq!(bus.replay().await.unwrap(), 2);
+        assert_eq!(rx.recv().await.unwrap().id, sent[1].id);
+        assert_eq!(rx.recv().await.unwrap().id, sent[2].id);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_logs_drops_and_keeps_receiving() {
+        let bus = MessageBus::new(4);
+        let sink = MemoryLogSink::new();
+        let mut slow = bus.subscribe_agent("slow", sink.clone()).with_resync();
+        for n in 1..=10 {
+            bus.publish(command(n)).await.unwrap();
+        }
+        let mut watcher = bus.subscribe();
+
+        // The six oldest messages were overwritten before `slow` read any.
+        assert_eq!(slow.recv().await.unwrap().payload["n"], 7);
+        assert_eq!(slow.dropped(), 6);
+        let warnings = sink.entries();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].level, LogLevel::Warn);
+        assert_eq!(warnings[0].data, Some(json!({ "agent_id": "slow", "dropped": 6 })));
+
+        let resync = watcher.recv().await.unwrap();
+        assert_eq!(resync.payload["topic"], RESYNC_TOPIC);
+        assert_eq!(resync.payload["agent_id"], "slow");
+
+        for n in 8..=10 {
+            assert_eq!(slow.recv().await.unwrap().payload["n"], n);
+        }
+        assert_eq!(slow.recv().await.unwrap().id, resync.id);
+        bus.publish(command(11)).await.unwrap();
+        assert_eq!(slow.recv().await.unwrap().payload["n"], 11);
+        assert_eq!(sink.entries().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reliable_topic_delivers_everything_to_a_slow_consumer_once() {
+        let bus = Arc::new(
+            MessageBus::new(2)
+                .with_reliable_topic("control")
+                .with_redelivery_after(Duration::from_secs(1)),
+        );
+        let mut consumer = bus.subscribe_reliable();
+        let mut broadcast = bus.subscribe();
+        let sent: Vec<Message> = (1..=20)
+            .map(|n| Message { payload: json!({ "topic": "control", "n": n }), ..command(n) })
+            .collect();
+
+        // The queue holds two messages, so the publisher keeps waiting on
+        // the consumer instead of dropping anything.
+        let publisher = tokio::spawn({
+            let bus = bus.clone();
+            let sent = sent.clone();
+            async move {
+                for m in sent {
+                    assert_eq!(bus.publish(m).await.unwrap(), 1);
+                }
+            }
+        });
+
+        let mut acked = Vec::new();
+        let mut dropped_once = HashSet::new();
+        while acked.len() < sent.len() {
+            let m = consumer.recv().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            // Every third message is "lost" the first time it is handled.
+            let n = m.payload["n"].as_u64().unwrap();
+            if n % 3 == 0 && dropped_once.insert(m.id) {
+                continue;
+            }
+            assert!(consumer.ack(m.id));
+            acked.push(m.id);
+        }
+        publisher.await.unwrap();
+
+        let mut expected: Vec<Uuid> = sent.iter().map(|m| m.id).collect();
+        assert_eq!(acked.len(), expected.len());
+        acked.sort();
+        expected.sort();
+        assert_eq!(acked, expected);
+        assert_eq!(dropped_once.len(), 6);
+        assert_eq!(consumer.unacked(), 0);
+        assert!(!consumer.ack(sent[0].id));
+
+        // A second copy of an acknowledged message is not handed out again.
+        bus.publish(sent[0].clone()).await.unwrap();
+        bus.publish(command(21)).await.unwrap();
+        let next = Message { payload: json!({ "topic": "control", "n": 22 }), ..command(22) };
+        bus.publish(next.clone()).await.unwrap();
+        assert_eq!(consumer.recv().await.unwrap().id, next.id);
+        // Reliable topics bypass the broadcast.
+        assert_eq!(broadcast.recv().await.unwrap().payload["n"], 21);
+        assert!(broadcast.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn reliable_subscriber_only_remembers_its_dedup_window() {
+        let bus = MessageBus::new(8).with_reliable_topic("control").with_dedup_window(3);
+        let mut consumer = bus.subscribe_reliable();
+        let sent: Vec<Message> = (1..=5)
+            .map(|n| Message { payload: json!({ "topic": "control", "n": n }), ..command(n) })
+            .collect();
+        for m in &sent {
+            bus.publish(m.clone()).await.unwrap();
+            let id = consumer.recv().await.unwrap().id;
+            assert!(consumer.ack(id));
+        }
+        assert_eq!(consumer.remembered(), 3);
+
+        // The oldest acknowledged id fell out of the window, the newest did not.
+        bus.publish(sent[4].clone()).await.unwrap();
+        bus.publish(sent[0].clone()).await.unwrap();
+        assert_eq!(consumer.recv().await.unwrap().id, sent[0].id);
+        assert!(consumer.ack(sent[0].id));
+        assert_eq!(consumer.remembered(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_reliable_subscriber_is_evicted_instead_of_blocking_publish() {
+        let bus = MessageBus::new(1)
+            .with_reliable_topic("control")
+            .with_send_timeout(Duration::from_secs(2));
+        let mut active = bus.subscribe_reliable();
+        let mut stalled = bus.subscribe_reliable();
+        let control = |n| Message { payload: json!({ "topic": "control", "n": n }), ..command(n) };
+
+        assert_eq!(bus.publish(control(1)).await.unwrap(), 2);
+        let first = active.recv().await.unwrap();
+        assert!(active.ack(first.id));
+        // `stalled` never receives, so its queue of one stays full.
+        let start = Instant::now();
+        assert_eq!(bus.publish(control(2)).await.unwrap(), 1);
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(bus.reliable.subscribers(), 1);
+
+        // Later publishes no longer wait on it.
+        let id = active.recv().await.unwrap().id;
+        assert!(active.ack(id));
+        let start = Instant::now();
+        assert_eq!(bus.publish(control(3)).await.unwrap(), 1);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The evicted subscriber drains what it was sent, then winds down.
+        let queued = stalled.recv().await.unwrap();
+        assert_eq!(queued.payload["n"], 1);
+        assert!(stalled.ack(queued.id));
+        assert!(stalled.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn unjournaled_bus_replays_nothing() {
+        let bus = MessageBus::new(4);
+        assert!(matches!(bus.publish(command(1)).await, Err(MessageBusError::NoSubscribers)));
+        assert_eq!(bus.replay().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,137 @@
+//! Cache keys — how [`CachingLogic`] and [`CachedCapability`] decide that two
+//! requests are the same.
+//!
+//! A [`CacheKeyStrategy`] rewrites every piece of text that goes into a key.
+//! [`Exact`] keeps text as written, so only byte-identical requests share an
+//! entry. [`Normalized`] ignores leading, trailing and repeated whitespace,
+//! raising the hit rate for prompts that differ only in layout. [`Custom`]
+//! takes any function, e.g. to fold case as well.
+//!
+//! [`CachingLogic`]: crate::logic::CachingLogic
+//! [`CachedCapability`]: crate::capability::CachedCapability
+
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::memory::{MemoryError, MemorySystem};
+
+/// Rewrites the text of a request before it is hashed into a cache key.
+pub trait CacheKeyStrategy: Send + Sync {
+    /// The form of `text` that goes into the key.
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str>;
+}
+
+/// Key on text exactly as written. The default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Exact;
+
+impl CacheKeyStrategy for Exact {
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Key on text with surrounding whitespace trimmed and every inner run of
+/// whitespace collapsed to one space.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Normalized;
+
+impl CacheKeyStrategy for Normalized {
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut words = text.split_whitespace();
+        let collapsed = match words.next() {
+            Some(first) => words.fold(first.to_owned(), |mut out, word| {
+                out.push(' ');
+                out.push_str(word);
+                out
+            }),
+            None => String::new(),
+        };
+        if collapsed == text { Cow::Borrowed(text) } else { Cow::Owned(collapsed) }
+    }
+}
+
+/// Key on whatever `F` makes of the text.
+pub struct Custom<F>(pub F);
+
+impl<F> CacheKeyStrategy for Custom<F>
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        Cow::Owned((self.0)(text))
+    }
+}
+
+/// Hash `parts` as rewritten by `strategy`. An absent part hashes apart from
+/// an empty one.
+pub fn hash_parts(strategy: &dyn CacheKeyStrategy, parts: &[Option<&str>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for part in parts {
+        part.map(|text| strategy.normalize(text)).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// `value` with every string in it, keys aside, rewritten by `strategy`.
+pub fn normalize_value(strategy: &dyn CacheKeyStrategy, value: &Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(strategy.normalize(text).into_owned()),
+        Value::Array(items) => items.iter().map(|v| normalize_value(strategy, v)).collect(),
+        Value::Object(members) => members
+            .iter()
+            .map(|(k, v)| (k.clone(), normalize_value(strategy, v)))
+            .collect(),
+        other => other.clone(),
+    }
+}
+
+/// The value stored under `key`, unless it was written `ttl` or more ago.
+pub(crate) async fn load_fresh<M: MemorySystem>(
+    memory: &M,
+    key: &str,
+    ttl: Duration,
+) -> Result<Option<Value>, MemoryError> {
+    match memory.load(key).await {
+        Ok(record) => {
+            let age = (chrono::Utc::now() - record.updated_at).to_std().unwrap_or_default();
+            Ok((age < ttl).then_some(record.value))
+        }
+        Err(MemoryError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn normalized_collapses_whitespace() {
+        assert_eq!(Normalized.normalize("  What is\t a   braid?\n"), "What is a braid?");
+        assert!(matches!(Normalized.normalize("already tidy"), Cow::Borrowed(_)));
+        assert_eq!(Normalized.normalize(" \n "), "");
+    }
+
+    #[test]
+    fn absent_and_empty_parts_differ() {
+        assert_ne!(
+            hash_parts(&Exact, &[Some("q"), None]),
+            hash_parts(&Exact, &[Some("q"), Some("")])
+        );
+    }
+
+    #[test]
+    fn custom_strategy_can_fold_case() {
+        let folded = Custom(|text: &str| text.to_lowercase());
+        assert_eq!(
+            normalize_value(&folded, &json!({"Prompt": ["HeLLo", 1]})),
+            json!({"Prompt": ["hello", 1]})
+        );
+    }
+}
@@ -1,7 +1,26 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
 use serde_json::Value;
-use crate::protocol::Message;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::adapter::Provider;
+use crate::cache::{CacheKeyStrategy, Exact, load_fresh, normalize_value};
+use crate::id::{IdGenerator, RandomIdGenerator};
+use crate::logic::{Query, query_messages};
+use crate::memory::{MemoryError, MemorySystem};
+use crate::registry::AdapterRegistry;
+
+/// Typed failures a capability call can report through its `anyhow` error.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CapabilityError {
+    /// The call was stopped by its [`CancellationToken`] before finishing.
+    #[error("capability cancelled")]
+    Cancelled,
+}
 
 /// A Capability represents a specific tool or action an agent can perform.
 /// This is the "hands" of the agent, allowing it to interact with the substrate.
@@ -18,9 +37,28 @@ pub trait Capability: Send + Sync {
 
     /// Execute the capability with the provided arguments.
     async fn execute(&self, args: Value) -> anyhow::Result<Value>;
+
+    /// Execute the capability, giving up with [`CapabilityError::Cancelled`]
+    /// once `cancel` is tripped.
+    ///
+    /// The default drops the [`execute`](Self::execute) future at its next
+    /// await point. Override it when a call holds something that needs
+    /// orderly teardown, such as an in-flight request.
+    async fn execute_cancellable(
+        &self,
+        args: Value,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<Value> {
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(CapabilityError::Cancelled.into()),
+            result = self.execute(args) => result,
+        }
+    }
 }
 
 /// The Registry manages all available capabilities in the system.
+#[derive(Default)]
 pub struct CapabilityRegistry {
     capabilities: Vec<Arc<dyn Capability>>,
 }
@@ -47,14 +85,62 @@ impl CapabilityRegistry {
             .collect()
     }
 
+    /// Every capability as a tool declaration in `provider`'s tool-calling
+    /// format, ready to pass in a request:
+    ///
+    /// - Claude: an Anthropic `tools` entry (`name`, `description`,
+    ///   `input_schema`).
+    /// - Gemini: a `function_declarations` entry (`name`, `description`,
+    ///   `parameters`).
+    /// - Grok, Manus and open-weight models, which serve OpenAI-compatible
+    ///   APIs: a `tools` entry of type `function`.
+    ///
+    /// Tools are named with [`tool_name`], since providers reject names like
+    /// `llm.query`; [`call`](Self::call) accepts either form.
+    pub fn as_tools(&self, provider: Provider) -> Vec<Value> {
+        self.list().into_iter().map(|info| info.as_tool(provider)).collect()
+    }
+
+    /// The name of the capability a model called as tool `tool`.
+    pub fn capability_for_tool(&self, tool: &str) -> Option<&str> {
+        self.find(tool).map(|c| c.name())
+    }
+
+    /// The capability named `name`, or else the one whose [`tool_name`] it is.
+    fn find(&self, name: &str) -> Option<&Arc<dyn Capability>> {
+        let mut all = self.capabilities.iter();
+        all.clone().find(|c| c.name() == name).or_else(|| all.find(|c| tool_name(c.name()) == name))
+    }
+
+    /// Run the capability `name`, which may also be its [`tool_name`].
     pub async fn call(&self, name: &str, args: Value) -> anyhow::Result<Value> {
-        let cap = self.capabilities
-            .iter()
-            .find(|c| c.name() == name)
+        let cap = self.find(name)
             .ok_or_else(|| anyhow::anyhow!("Capability not found: {}", name))?;
         
         cap.execute(args).await
     }
+
+    /// Like [`call`](Self::call), but aborts when `cancel` is tripped.
+    pub async fn call_cancellable(
+        &self,
+        name: &str,
+        args: Value,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<Value> {
+        let cap = self.find(name)
+            .ok_or_else(|| anyhow::anyhow!("Capability not found: {}", name))?;
+
+        cap.execute_cancellable(args, cancel).await
+    }
+}
+
+/// `name` as a tool name providers accept, which must match
+/// `^[a-zA-Z0-9_-]+$`: every other character becomes `_`, e.g.
+/// `llm.query` becomes `llm_query`.
+pub fn tool_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -62,4 +148,615 @@ pub struct CapabilityInfo {
     pub name: String,
     pub description: String,
     pub input_schema: Value,
-}
\ No newline at end of file
+}
+
+impl CapabilityInfo {
+    /// This capability as a tool declaration for `provider`; see
+    /// [`CapabilityRegistry::as_tools`].
+    pub fn as_tool(&self, provider: Provider) -> Value {
+        let name = tool_name(&self.name);
+        match provider {
+            Provider::Claude => serde_json::json!({
+                "name": name,
+                "description": self.description,
+                "input_schema": self.input_schema,
+            }),
+            Provider::Gemini => serde_json::json!({
+                "name": name,
+                "description": self.description,
+                "parameters": self.input_schema,
+            }),
+            Provider::Grok | Provider::Manus | Provider::OpenWeight => serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": name,
+                    "description": self.description,
+                    "parameters": self.input_schema,
+                },
+            }),
+        }
+    }
+}
+
+/// Decorator that caches successful results of an idempotent capability.
+///
+/// Results are stored in a [`MemorySystem`] under a key derived from the
+/// capability name and a hash of the JSON arguments, and are served until
+/// `ttl` has elapsed since they were written. Errors are never cached.
+///
+/// String arguments are hashed as rewritten by the
+/// [key strategy](Self::with_key_strategy), [`Exact`] by default.
+pub struct CachedCapability<C, M> {
+    inner: C,
+    memory: Arc<M>,
+    ttl: Duration,
+    keys: Box<dyn CacheKeyStrategy>,
+}
+
+impl<C: Capability, M: MemorySystem> CachedCapability<C, M> {
+    pub fn new(inner: C, memory: Arc<M>, ttl: Duration) -> Self {
+        Self { inner, memory, ttl, keys: Box::new(Exact) }
+    }
+
+    /// Decide which calls share a cache entry with `strategy`.
+    pub fn with_key_strategy(mut self, strategy: impl CacheKeyStrategy + 'static) -> Self {
+        self.keys = Box::new(strategy);
+        self
+    }
+
+    /// Memory key for a call of this capability with `args`.
+    ///
+    /// `serde_json` objects serialize with sorted keys, so argument order
+    /// does not affect the key.
+    pub fn cache_key(&self, args: &Value) -> String {
+        let mut hasher = DefaultHasher::new();
+        normalize_value(self.keys.as_ref(), args).to_string().hash(&mut hasher);
+        format!("cap:{}:{:016x}", self.inner.name(), hasher.finish())
+    }
+
+    async fn lookup(&self, key: &str) -> anyhow::Result<Option<Value>> {
+        Ok(load_fresh(self.memory.as_ref(), key, self.ttl).await?)
+    }
+}
+
+#[async_trait]
+impl<C: Capability, M: MemorySystem> Capability for CachedCapability<C, M> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn input_schema(&self) -> Value {
+        self.inner.input_schema()
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+        let key = self.cache_key(&args);
+        if let Some(hit) = self.lookup(&key).await? {
+            return Ok(hit);
+        }
+        let result = self.inner.execute(args).await?;
+        self.memory.store(&key, result.clone()).await?;
+        Ok(result)
+    }
+
+    async fn execute_cancellable(
+        &self,
+        args: Value,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<Value> {
+        let key = self.cache_key(&args);
+        if let Some(hit) = self.lookup(&key).await? {
+            return Ok(hit);
+        }
+        let result = self.inner.execute_cancellable(args, cancel).await?;
+        self.memory.store(&key, result.clone()).await?;
+        Ok(result)
+    }
+}
+
+/// `llm.query` — delegate a prompt to a named provider through an
+/// [`AdapterRegistry`], so capability-using agents can make model calls the
+/// same way as any other tool call.
+pub struct LlmQueryCapability {
+    registry: Arc<AdapterRegistry>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl LlmQueryCapability {
+    pub fn new(registry: Arc<AdapterRegistry>) -> Self {
+        Self { registry, ids: Arc::new(RandomIdGenerator) }
+    }
+
+    /// Draw query ids from `ids` instead of at random.
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+}
+
+#[async_trait]
+impl Capability for LlmQueryCapability {
+    fn name(&self) -> &str {
+        "llm.query"
+    }
+
+    fn description(&self) -> &str {
+        "Send a prompt to a specific AI provider and return its reply and token usage."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "provider": {
+                    "type": "string",
+                    "enum": ["claude", "gemini", "grok", "manus", "openweight"]
+                },
+                "prompt": { "type": "string" },
+                "system": { "type": "string" }
+            },
+            "required": ["provider", "prompt"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+        let field = |name: &str| args.get(name).and_then(Value::as_str);
+        let required = |name: &str| {
+            field(name).ok_or_else(|| anyhow::anyhow!("missing string `{name}`"))
+        };
+        let provider = required("provider")?;
+        let prompt = required("prompt")?;
+
+        let mut query = Query::new_with_ids(prompt, self.ids.as_ref()).with_provider(provider);
+        if let Some(system) = field("system") {
+            query = query.with_system(system);
+        }
+        let adapter = self.registry.route(&query)?;
+        let response = adapter.chat(&query_messages(&query)).await?;
+        self.registry.latency().observe(&response);
+        Ok(serde_json::json!({
+            "provider": response.provider,
+            "model": response.model,
+            "content": response.content,
+            "usage": {
+                "input_tokens": response.input_tokens,
+                "output_tokens": response.output_tokens,
+            },
+        }))
+    }
+}
+
+/// Key prefixes of the core's own records, which no memory capability
+/// namespace may take.
+pub const RESERVED_NAMESPACES: &[&str] = &["task", "bus", "query", "cap"];
+
+/// A namespace of memory keys handed to agents: key `k` is stored as
+/// `{namespace}:k`, so agents cannot read or overwrite keys outside it,
+/// such as the core's own `task:` and `bus:` records.
+///
+/// The namespace itself may not contain `:`, so the first `:` of a stored
+/// key always ends the namespace: no namespace's keys fall inside another's.
+struct MemNamespace<M> {
+    memory: Arc<M>,
+    namespace: String,
+}
+
+impl<M: MemorySystem> MemNamespace<M> {
+    fn new(memory: Arc<M>, namespace: impl Into<String>) -> Self {
+        let namespace = namespace.into();
+        assert!(!namespace.is_empty(), "memory capabilities need a namespace");
+        assert!(!namespace.contains(':'), "memory namespace `{namespace}` contains `:`");
+        assert!(
+            !RESERVED_NAMESPACES.contains(&namespace.as_str()),
+            "memory namespace `{namespace}` is reserved for the core"
+        );
+        Self { memory, namespace }
+    }
+
+    /// The agent's `key` argument and the memory key it maps to.
+    fn key<'a>(&self, args: &'a Value) -> anyhow::Result<(&'a str, String)> {
+        let key = args
+            .get("key")
+            .and_then(Value::as_str)
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("missing string `key`"))?;
+        Ok((key, format!("{}:{key}", self.namespace)))
+    }
+}
+
+/// `mem.get` — read a key from a namespace of a [`MemorySystem`].
+///
+/// Returns `{"found": true, "key", "value", "version", "updated_at"}`, or
+/// `{"found": false, "key"}` for a missing key rather than an error.
+pub struct MemGetCapability<M> {
+    space: MemNamespace<M>,
+}
+
+impl<M: MemorySystem> MemGetCapability<M> {
+    /// Read keys under `{namespace}:` in `memory`.
+    ///
+    /// # Panics
+    ///
+    /// If `namespace` is empty, contains `:`, or is one of the
+    /// [`RESERVED_NAMESPACES`].
+    pub fn new(memory: Arc<M>, namespace: impl Into<String>) -> Self {
+        Self { space: MemNamespace::new(memory, namespace) }
+    }
+}
+
+#[async_trait]
+impl<M: MemorySystem + 'static> Capability for MemGetCapability<M> {
+    fn name(&self) -> &str {
+        "mem.get"
+    }
+
+    fn description(&self) -> &str {
+        "Read a value from shared memory by key."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": { "key": { "type": "string" } },
+            "required": ["key"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+        let (key, stored) = self.space.key(&args)?;
+        match self.space.memory.load(&stored).await {
+            Ok(record) => Ok(serde_json::json!({
+                "found": true,
+                "key": key,
+                "value": record.value,
+                "version": record.version,
+                "updated_at": record.updated_at,
+            })),
+            Err(MemoryError::NotFound(_)) => Ok(serde_json::json!({ "found": false, "key": key })),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// `mem.set` — write a key in a namespace of a [`MemorySystem`].
+///
+/// Takes `{"key", "value"}` and returns `{"key", "version"}`.
+pub struct MemSetCapability<M> {
+    space: MemNamespace<M>,
+}
+
+impl<M: MemorySystem> MemSetCapability<M> {
+    /// Write keys under `{namespace}:` in `memory`.
+    ///
+    /// # Panics
+    ///
+    /// If `namespace` is empty, contains `:`, or is one of the
+    /// [`RESERVED_NAMESPACES`].
+    pub fn new(memory: Arc<M>, namespace: impl Into<String>) -> Self {
+        Self { space: MemNamespace::new(memory, namespace) }
+    }
+}
+
+#[async_trait]
+impl<M: MemorySystem + 'static> Capability for MemSetCapability<M> {
+    fn name(&self) -> &str {
+        "mem.set"
+    }
+
+    fn description(&self) -> &str {
+        "Write a JSON value to shared memory under a key and return its new version."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": { "key": { "type": "string" }, "value": {} },
+            "required": ["key", "value"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+        let (key, stored) = self.space.key(&args)?;
+        let value = args.get("value").cloned().ok_or_else(|| anyhow::anyhow!("missing `value`"))?;
+        let version = self.space.memory.store(&stored, value).await?;
+        Ok(serde_json::json!({ "key": key, "version": version }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::Normalized;
+    use crate::memory::InMemoryStore;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Echoes its args and counts executions; fails when asked to.
+    #[derive(Default)]
+    struct Counting {
+        runs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Capability for Counting {
+        fn name(&self) -> &str {
+            "test.echo"
+        }
+
+        fn description(&self) -> &str {
+            "echo args"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            if args["fail"] == json!(true) {
+                anyhow::bail!("asked to fail");
+            }
+            Ok(json!({"echo": args}))
+        }
+    }
+
+    /// Sleeps for `args["ms"]` milliseconds before answering.
+    struct Sleeping;
+
+    #[async_trait]
+    impl Capability for Sleeping {
+        fn name(&self) -> &str {
+            "test.sleep"
+        }
+
+        fn description(&self) -> &str {
+            "sleep, then answer"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object", "properties": {"ms": {"type": "integer"}}})
+        }
+
+        async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+            let ms = args["ms"].as_u64().unwrap_or(0);
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(json!("done"))
+        }
+    }
+
+    fn cached(ttl: Duration) -> CachedCapability<Counting, InMemoryStore> {
+        CachedCapability::new(Counting::default(), Arc::new(InMemoryStore::new()), ttl)
+    }
+
+    #[tokio::test]
+    async fn llm_query_routes_to_named_provider() {
+        use crate::adapter::Provider;
+        use crate::mock::MockAdapter;
+
+        let claude = Arc::new(MockAdapter::new(Provider::Claude).with_reply("from claude"));
+        let gemini = Arc::new(MockAdapter::new(Provider::Gemini).with_reply("from gemini side"));
+        let mut registry = AdapterRegistry::new();
+        registry.register(claude.clone());
+        registry.register(gemini.clone());
+        let mut caps = CapabilityRegistry::new();
+        caps.register(Arc::new(LlmQueryCapability::new(Arc::new(registry))));
+
+        let args = json!({"provider": "gemini", "prompt": "hi there", "system": "be brief"});
+        let out = caps.call("llm.query", args).await.unwrap();
+        assert_eq!(out["provider"], "gemini");
+        assert_eq!(out["content"], "from gemini side");
+        assert_eq!(out["usage"], json!({"input_tokens": 4, "output_tokens": 3}));
+        assert_eq!((claude.calls(), gemini.calls()), (0, 1));
+
+        let err = caps.call("llm.query", json!({"provider": "nope", "prompt": "x"})).await;
+        assert!(err.unwrap_err().to_string().contains("unknown provider"));
+        assert!(caps.call("llm.query", json!({"provider": "claude"})).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_capability_returns_promptly() {
+        let mut caps = CapabilityRegistry::new();
+        caps.register(Arc::new(Sleeping));
+        let cancel = CancellationToken::new();
+        let trip = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            trip.cancel();
+        });
+
+        let started = tokio::time::Instant::now();
+        let err = caps
+            .call_cancellable("test.sleep", json!({"ms": 60_000}), &cancel)
+            .await
+            .unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&CapabilityError::Cancelled));
+        assert_eq!(started.elapsed(), Duration::from_millis(50));
+
+        // An untripped token lets the call finish.
+        let out = caps
+            .call_cancellable("test.sleep", json!({"ms": 10}), &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(out, json!("done"));
+    }
+
+    #[test]
+    fn tools_use_each_providers_envelope() {
+        let mut caps = CapabilityRegistry::new();
+        caps.register(Arc::new(Counting::default()));
+
+        assert_eq!(
+            caps.as_tools(Provider::Claude),
+            [json!({
+                "name": "test_echo",
+                "description": "echo args",
+                "input_schema": {"type": "object"},
+            })]
+        );
+        assert_eq!(
+            caps.as_tools(Provider::Grok),
+            [json!({
+                "type": "function",
+                "function": {
+                    "name": "test_echo",
+                    "description": "echo args",
+                    "parameters": {"type": "object"},
+                },
+            })]
+        );
+        assert_eq!(
+            caps.as_tools(Provider::Gemini),
+            [json!({
+                "name": "test_echo",
+                "description": "echo args",
+                "parameters": {"type": "object"},
+            })]
+        );
+        assert_eq!(caps.as_tools(Provider::OpenWeight), caps.as_tools(Provider::Grok));
+
+        // Built-in names are dotted; their tools are not, and map back.
+        let memory = Arc::new(crate::memory::InMemoryStore::new());
+        caps.register(Arc::new(LlmQueryCapability::new(Arc::new(AdapterRegistry::new()))));
+        caps.register(Arc::new(MemGetCapability::new(memory.clone(), "agent-a")));
+        caps.register(Arc::new(MemSetCapability::new(memory, "agent-a")));
+        let names: Vec<Value> =
+            caps.as_tools(Provider::Claude).into_iter().map(|t| t["name"].clone()).collect();
+        assert_eq!(names, ["test_echo", "llm_query", "mem_get", "mem_set"]);
+        let valid =
+            |name: &str| name.chars().all(|c| c.is_ascii_alphanumeric() || "_-".contains(c));
+        for provider in [Provider::Claude, Provider::Gemini, Provider::Grok] {
+            for tool in caps.as_tools(provider) {
+                let name = tool.get("function").unwrap_or(&tool)["name"].as_str().unwrap();
+                assert!(valid(name), "{provider}: {name}");
+            }
+        }
+        assert_eq!(caps.capability_for_tool("mem_set"), Some("mem.set"));
+        assert_eq!(caps.capability_for_tool("llm.query"), Some("llm.query"));
+        assert_eq!(caps.capability_for_tool("mem_delete"), None);
+    }
+
+    #[tokio::test]
+    async fn tools_are_called_by_their_tool_name() {
+        let mut caps = CapabilityRegistry::new();
+        caps.register(Arc::new(Counting::default()));
+        let args = json!({"x": 1});
+        let echo = json!({"echo": args});
+        assert_eq!(caps.call("test_echo", args.clone()).await.unwrap(), echo);
+        assert_eq!(caps.call("test.echo", args.clone()).await.unwrap(), echo);
+        assert!(caps.call("test-echo", args).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn identical_args_hit_cache() {
+        let cap = cached(Duration::from_secs(60));
+        let first = cap.execute(json!({"url": "a", "n": 1})).await.unwrap();
+        let second = cap.execute(json!({"n": 1, "url": "a"})).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cap.inner.runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn key_strategy_decides_whitespace_hits() {
+        let tidy = json!({"prompt": "What is a braid?"});
+        let messy = json!({"prompt": "  What is\n a  braid? "});
+
+        let exact = cached(Duration::from_secs(60));
+        assert_ne!(exact.cache_key(&tidy), exact.cache_key(&messy));
+
+        let normalized = cached(Duration::from_secs(60)).with_key_strategy(Normalized);
+        assert_eq!(normalized.cache_key(&tidy), normalized.cache_key(&messy));
+        normalized.execute(tidy).await.unwrap();
+        normalized.execute(messy).await.unwrap();
+        assert_eq!(normalized.inner.runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn different_args_miss() {
+        let cap = cached(Duration::from_secs(60));
+        cap.execute(json!({"url": "a"})).await.unwrap();
+        cap.execute(json!({"url": "b"})).await.unwrap();
+        assert_eq!(cap.inner.runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn errors_and_expired_entries_are_not_served() {
+        let cap = cached(Duration::from_secs(60));
+        assert!(cap.execute(json!({"fail": true})).await.is_err());
+        assert!(cap.execute(json!({"fail": true})).await.is_err());
+        assert_eq!(cap.inner.runs.load(Ordering::SeqCst), 2);
+
+        let cap = cached(Duration::ZERO);
+        cap.execute(json!({})).await.unwrap();
+        cap.execute(json!({})).await.unwrap();
+        assert_eq!(cap.inner.runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn mem_get_reports_misses_without_erroring() {
+        let get = MemGetCapability::new(Arc::new(InMemoryStore::new()), "agent-a");
+        let out = get.execute(json!({"key": "plan"})).await.unwrap();
+        assert_eq!(out, json!({"found": false, "key": "plan"}));
+        assert!(get.execute(json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mem_set_then_get_round_trips() {
+        let memory = Arc::new(InMemoryStore::new());
+        let set = MemSetCapability::new(memory.clone(), "agent-a");
+        let get = MemGetCapability::new(memory.clone(), "agent-a");
+
+        let out = set.execute(json!({"key": "plan", "value": {"step": 1}})).await.unwrap();
+        assert_eq!(out, json!({"key": "plan", "version": 1}));
+        let out = set.execute(json!({"key": "plan", "value": {"step": 2}})).await.unwrap();
+        assert_eq!(out["version"], 2);
+
+        let out = get.execute(json!({"key": "plan"})).await.unwrap();
+        assert_eq!(out["found"], true);
+        assert_eq!((&out["value"], &out["version"]), (&json!({"step": 2}), &json!(2)));
+        assert_eq!(memory.load("agent-a:plan").await.unwrap().version, 2);
+    }
+
+    #[tokio::test]
+    async fn mem_capabilities_stay_inside_their_namespace() {
+        let memory = Arc::new(InMemoryStore::new());
+        memory.store("task:1", json!("core state")).await.unwrap();
+        let set_a = MemSetCapability::new(memory.clone(), "agent-a");
+        let get_b = MemGetCapability::new(memory.clone(), "agent-b");
+
+        set_a.execute(json!({"key": "shared", "value": "from a"})).await.unwrap();
+        let out = get_b.execute(json!({"key": "shared"})).await.unwrap();
+        assert_eq!(out["found"], false);
+
+        // A key naming a core record lands inside the namespace instead.
+        set_a.execute(json!({"key": "task:1", "value": "stomped"})).await.unwrap();
+        assert_eq!(memory.load("task:1").await.unwrap().value, "core state");
+        assert_eq!(memory.load("agent-a:task:1").await.unwrap().value, "stomped");
+    }
+
+    #[tokio::test]
+    async fn mem_namespaces_cannot_nest_or_take_core_prefixes() {
+        let memory = Arc::new(InMemoryStore::new());
+        for namespace in ["agent:a", "task", "bus", ""] {
+            let memory = memory.clone();
+            let made = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                MemGetCapability::new(memory, namespace)
+            }));
+            assert!(made.is_err(), "`{namespace}` was accepted");
+        }
+
+        // `agent` cannot reach another namespace's keys through a key that
+        // looks like a nested path.
+        let set_inner = MemSetCapability::new(memory.clone(), "agent-a");
+        let get_outer = MemGetCapability::new(memory.clone(), "agent");
+        set_inner.execute(json!({"key": "plan", "value": "secret"})).await.unwrap();
+        for key in ["a:plan", "-a:plan", ":plan"] {
+            let out = get_outer.execute(json!({ "key": key })).await.unwrap();
+            assert_eq!(out["found"], false, "{key}");
+        }
+        assert_eq!(memory.load("agent-a:plan").await.unwrap().value, "secret");
+    }
+}
@@ -0,0 +1,210 @@
+//! CassetteAdapter — record a provider's replies once, replay them in tests
+//! (feature `test-util`).
+//!
+//! In record mode the adapter forwards every `chat` to a live adapter and
+//! writes each successful request/response pair to a JSON cassette file.
+//! In replay mode it answers from that file alone: a request is served the
+//! first unused response recorded for an identical conversation, and a
+//! request the cassette never saw is an error rather than a live call.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::adapter::{Adapter, AdapterError, Message, ModelResponse, ModelTier, Provider};
+
+/// One recorded exchange.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: Vec<Message>,
+    pub response: ModelResponse,
+}
+
+/// Contents of a cassette file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub provider: Provider,
+    #[serde(default)]
+    pub tier: ModelTier,
+    pub interactions: Vec<Interaction>,
+}
+
+enum Mode {
+    Record(Arc<dyn Adapter>),
+    /// Which interactions have been served.
+    Replay(Mutex<Vec<bool>>),
+}
+
+/// An [`Adapter`] that records to, or replays from, a [`Cassette`] file.
+///
+/// Only successful replies are recorded; errors from the live adapter pass
+/// through untouched.
+pub struct CassetteAdapter {
+    provider: Provider,
+    tier: ModelTier,
+    path: PathBuf,
+    mode: Mode,
+    cassette: Mutex<Cassette>,
+}
+
+impl CassetteAdapter {
+    /// Forward to `live`, writing every reply to a new cassette at `path`.
+    pub fn record(live: Arc<dyn Adapter>, path: impl AsRef<Path>) -> Self {
+        let cassette =
+            Cassette { provider: live.provider(), tier: live.tier(), interactions: Vec::new() };
+        Self {
+            provider: cassette.provider,
+            tier: cassette.tier,
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record(live),
+            cassette: Mutex::new(cassette),
+        }
+    }
+
+    /// Serve replies from the cassette at `path`, without a live adapter.
+    pub async fn replay(path: impl AsRef<Path>) -> Result<Self, AdapterError> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = tokio::fs::read(&path).await.map_err(|e| {
+            AdapterError::Request(format!("cannot read cassette {}: {e}", path.display()))
+        })?;
+        let cassette: Cassette = serde_json::from_slice(&bytes).map_err(|e| {
+            AdapterError::InvalidResponse(format!("bad cassette {}: {e}", path.display()))
+        })?;
+        let served = vec![false; cassette.interactions.len()];
+        Ok(Self {
+            provider: cassette.provider,
+            tier: cassette.tier,
+            path,
+            mode: Mode::Replay(Mutex::new(served)),
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Record(_))
+    }
+
+    /// Every interaction recorded or loaded so far.
+    pub async fn interactions(&self) -> Vec<Interaction> {
+        self.cassette.lock().await.interactions.clone()
+    }
+
+    async fn record_reply(
+        &self,
+        live: &dyn Adapter,
+        messages: &[Message],
+    ) -> Result<ModelResponse, AdapterError> {
+        let response = live.chat(messages).await?;
+        let mut cassette = self.cassette.lock().await;
+        cassette
+            .interactions
+            .push(Interaction { request: messages.to_vec(), response: response.clone() });
+        let json = serde_json::to_vec_pretty(&*cassette)
+            .map_err(|e| AdapterError::Request(format!("cannot encode cassette: {e}")))?;
+        tokio::fs::write(&self.path, json).await.map_err(|e| {
+            AdapterError::Request(format!("cannot write cassette {}: {e}", self.path.display()))
+        })?;
+        Ok(response)
+    }
+
+    async fn replay_reply(
+        &self,
+        served: &Mutex<Vec<bool>>,
+        messages: &[Message],
+    ) -> Result<ModelResponse, AdapterError> {
+        let cassette = self.cassette.lock().await;
+        let mut served = served.lock().await;
+        let found = cassette
+            .interactions
+            .iter()
+            .zip(served.iter())
+            .position(|(interaction, &used)| !used && interaction.request == messages);
+        match found {
+            Some(i) => {
+                served[i] = true;
+                Ok(cassette.interactions[i].response.clone())
+            }
+            None => Err(AdapterError::Request(format!(
+                "no unused interaction in cassette {} matches the request",
+                self.path.display()
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Adapter for CassetteAdapter {
+    fn provider(&self) -> Provider {
+        self.provider
+    }
+
+    fn tier(&self) -> ModelTier {
+        self.tier
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        match &self.mode {
+            Mode::Record(live) => self.record_reply(live.as_ref(), messages).await,
+            Mode::Replay(served) => self.replay_reply(served, messages).await,
+        }
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        match &self.mode {
+            Mode::Record(live) => live.health_check().await,
+            Mode::Replay(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::{FinishReason, Role};
+    use crate::mock::MockAdapter;
+
+    fn ask(content: &str) -> Vec<Message> {
+        vec![Message { role: Role::User, content: content.into() }]
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_replies_without_live_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gemini.json");
+        let live = Arc::new(MockAdapter::new(Provider::Gemini).with_script([
+            ("first", FinishReason::Stop),
+            ("second", FinishReason::Length),
+            ("third", FinishReason::Stop),
+        ]));
+
+        let recorder = CassetteAdapter::record(live.clone(), &path);
+        assert!(recorder.is_recording());
+        let mut recorded = Vec::new();
+        for prompt in ["a", "b", "a"] {
+            recorded.push(recorder.chat(&ask(prompt)).await.unwrap());
+        }
+        assert_eq!(live.calls(), 3);
+
+        let player = CassetteAdapter::replay(&path).await.unwrap();
+        assert_eq!(player.provider(), Provider::Gemini);
+        // Repeated requests are served their recordings in order.
+        for (prompt, expected) in ["a", "b", "a"].into_iter().zip(&recorded) {
+            let replayed = player.chat(&ask(prompt)).await.unwrap();
+            assert_eq!(
+                serde_json::to_value(replayed).unwrap(),
+                serde_json::to_value(expected).unwrap()
+            );
+        }
+        assert_eq!(live.calls(), 3);
+
+        let unmatched = player.chat(&ask("a")).await.unwrap_err();
+        assert!(matches!(unmatched, AdapterError::Request(ref m) if m.contains("no unused")));
+    }
+}
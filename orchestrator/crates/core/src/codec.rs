@@ -0,0 +1,55 @@
+//! Codec — pluggable wire encodings for values sent over a transport.
+//!
+//! [`JsonCodec`] is the default and the interop format. [`MessagePackCodec`]
+//! (feature `msgpack`) is a compact binary alternative for transports where
+//! JSON encoding is a bottleneck.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// A value could not be encoded or decoded.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("codec error: {0}")]
+pub struct CodecError(pub String);
+
+fn codec_error(e: impl std::fmt::Display) -> CodecError {
+    CodecError(e.to_string())
+}
+
+/// A wire encoding for serde types.
+pub trait Codec {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError>;
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError>;
+}
+
+/// UTF-8 JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        serde_json::to_vec(value).map_err(codec_error)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        serde_json::from_slice(bytes).map_err(codec_error)
+    }
+}
+
+/// MessagePack with named struct fields, so fields can be added compatibly.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePackCodec;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePackCodec {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        rmp_serde::to_vec_named(value).map_err(codec_error)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        rmp_serde::from_slice(bytes).map_err(codec_error)
+    }
+}
@@ -0,0 +1,264 @@
+//! CompositeTask — run a group of child tasks as one parent task.
+//!
+//! Each child runs through a [`TaskRunner`] like any other task. A child may
+//! depend on earlier ones and only starts once they have completed; children
+//! with no unmet dependencies run concurrently, up to a configurable limit.
+//! Once every child has finished, an [`Aggregator`] combines their outputs
+//! into the parent's output and the parent moves through its own lifecycle.
+
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use serde_json::Value;
+
+use crate::logic::CoreLogic;
+use crate::memory::MemorySystem;
+use crate::runner::TaskRunner;
+use crate::task::{Task, TaskError, TaskResult};
+
+/// Combines child outputs into the parent's output.
+///
+/// Closures of the form `Fn(Vec<Value>) -> Value` are aggregators too.
+pub trait Aggregator: Send + Sync {
+    /// `outputs` holds one value per child, in the order the children were
+    /// added; an optional child that failed contributes `null`.
+    fn aggregate(&self, outputs: Vec<Value>) -> Value;
+}
+
+/// The default [`Aggregator`]: an array of the child outputs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CollectOutputs;
+
+impl Aggregator for CollectOutputs {
+    fn aggregate(&self, outputs: Vec<Value>) -> Value {
+        Value::Array(outputs)
+    }
+}
+
+impl<F: Fn(Vec<Value>) -> Value + Send + Sync> Aggregator for F {
+    fn aggregate(&self, outputs: Vec<Value>) -> Value {
+        self(outputs)
+    }
+}
+
+struct Child {
+    /// Taken while the child runs.
+    task: Option<Task>,
+    required: bool,
+    depends_on: Vec<usize>,
+    outcome: Option<Result<TaskResult, TaskError>>,
+}
+
+/// A parent [`Task`] whose output aggregates those of its children.
+///
+/// The parent fails if any required child fails. An optional child's failure
+/// only leaves `null` in its place, but children that depend on a failed
+/// child are failed without running either way.
+pub struct CompositeTask {
+    pub parent: Task,
+    children: Vec<Child>,
+    aggregator: Box<dyn Aggregator>,
+    concurrency: usize,
+}
+
+impl CompositeTask {
+    pub fn new(parent: Task) -> Self {
+        Self {
+            parent,
+            children: Vec::new(),
+            aggregator: Box::new(CollectOutputs),
+            concurrency: usize::MAX,
+        }
+    }
+
+    /// Add a child the parent cannot complete without.
+    pub fn with_child(self, task: Task) -> Self {
+        self.push(task, true)
+    }
+
+    /// Add a child whose failure the parent tolerates.
+    pub fn with_optional_child(self, task: Task) -> Self {
+        self.push(task, false)
+    }
+
+    fn push(mut self, task: Task, required: bool) -> Self {
+        self.children.push(Child {
+            task: Some(task),
+            required,
+            depends_on: Vec::new(),
+            outcome: None,
+        });
+        self
+    }
+
+    /// Start child `child` only after child `prerequisite` has completed.
+    /// Children are numbered in the order they were added.
+    pub fn with_dependency(mut self, child: usize, prerequisite: usize) -> Self {
+        if let Some(c) = self.children.get_mut(child) {
+            c.depends_on.push(prerequisite);
+        }
+        self
+    }
+
+    pub fn with_aggregator(mut self, aggregator: impl Aggregator + 'static) -> Self {
+        self.aggregator = Box::new(aggregator);
+        self
+    }
+
+    /// Run at most `max` children at once. Default: every ready child.
+    pub fn with_concurrency(mut self, max: usize) -> Self {
+        self.concurrency = max.max(1);
+        self
+    }
+
+    /// The child tasks, in the order they were added.
+    pub fn children(&self) -> impl Iterator<Item = &Task> {
+        self.children.iter().filter_map(|c| c.task.as_ref())
+    }
+
+    /// How child `index` ended, once the composite has run.
+    pub fn child_result(&self, index: usize) -> Option<&Result<TaskResult, TaskError>> {
+        self.children.get(index)?.outcome.as_ref()
+    }
+
+    /// Run every child through `runner`, then complete the parent with the
+    /// aggregated output. Token and cost totals are summed over the children.
+    /// On error the parent is marked `Failed`.
+    pub async fn run<L, M>(&mut self, runner: &TaskRunner<L, M>) -> Result<TaskResult, TaskError>
+    where
+        L: CoreLogic,
+        M: MemorySystem,
+    {
+        let outcome = self.run_phases(runner).await;
+        if let Err(e) = &outcome {
+            self.parent.fail_with(e.to_string());
+        }
+        outcome
+    }
+
+    async fn run_phases<L, M>(&mut self, runner: &TaskRunner<L, M>) -> Result<TaskResult, TaskError>
+    where
+        L: CoreLogic,
+        M: MemorySystem,
+    {
+        self.parent.initialize_with(runner.schemas())?;
+        self.check_dependencies()?;
+        self.parent.begin_execution()?;
+        self.run_children(runner).await;
+
+        let mut outputs = Vec::with_capacity(self.children.len());
+        let (mut input_tokens, mut output_tokens, mut cost) = (0, 0, 0.0);
+        for (i, child) in self.children.iter().enumerate() {
+            match child.outcome.as_ref().expect("every child has finished") {
+                Ok(result) => {
+                    outputs.push(result.output.clone());
+                    input_tokens += result.total_input_tokens;
+                    output_tokens += result.total_output_tokens;
+                    cost += result.estimated_cost;
+                }
+                Err(e) if child.required => {
+                    return Err(TaskError::ExecFailed(format!("required child {i} failed: {e}")));
+                }
+                Err(_) => outputs.push(Value::Null),
+            }
+        }
+
+        self.parent.validate(self.aggregator.aggregate(outputs))?;
+        let mut result = self.parent.complete()?;
+        result.total_input_tokens = input_tokens;
+        result.total_output_tokens = output_tokens;
+        result.estimated_cost = cost;
+        Ok(result)
+    }
+
+    /// Reject dependencies on missing children and dependency cycles.
+    fn check_dependencies(&self) -> Result<(), TaskError> {
+        let n = self.children.len();
+        for (i, child) in self.children.iter().enumerate() {
+            if let Some(&d) = child.depends_on.iter().find(|&&d| d >= n || d == i) {
+                return Err(TaskError::InitFailed(format!(
+                    "child {i} cannot depend on child {d}"
+                )));
+            }
+        }
+        // Repeatedly settle children whose dependencies are all settled.
+        let mut settled = vec![false; n];
+        loop {
+            let ready: Vec<usize> = (0..n)
+                .filter(|&i| !settled[i])
+                .filter(|&i| self.children[i].depends_on.iter().all(|&d| settled[d]))
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            for i in ready {
+                settled[i] = true;
+            }
+        }
+        match settled.iter().position(|&s| !s) {
+            Some(i) => Err(TaskError::InitFailed(format!("child {i} is in a dependency cycle"))),
+            None => Ok(()),
+        }
+    }
+
+    async fn run_children<L, M>(&mut self, runner: &TaskRunner<L, M>)
+    where
+        L: CoreLogic,
+        M: MemorySystem,
+    {
+        let mut running = FuturesUnordered::new();
+        loop {
+            self.skip_blocked();
+            for i in 0..self.children.len() {
+                if running.len() >= self.concurrency {
+                    break;
+                }
+                if !self.is_ready(i) {
+                    continue;
+                }
+                let mut task = self.children[i].task.take().expect("unstarted child");
+                running.push(async move {
+                    let result = runner.run(&mut task).await;
+                    (i, task, result)
+                });
+            }
+            let Some((i, task, result)) = running.next().await else {
+                break;
+            };
+            let child = &mut self.children[i];
+            child.task = Some(task);
+            child.outcome = Some(result);
+        }
+    }
+
+    /// Whether child `i` has not started and its dependencies completed.
+    fn is_ready(&self, i: usize) -> bool {
+        let child = &self.children[i];
+        child.task.is_some()
+            && child.outcome.is_none()
+            && child.depends_on.iter().all(|&d| matches!(self.children[d].outcome, Some(Ok(_))))
+    }
+
+    /// Fail, without running them, the children that depend on a failed one.
+    fn skip_blocked(&mut self) {
+        loop {
+            let blocked = (0..self.children.len()).find_map(|i| {
+                let child = &self.children[i];
+                if child.outcome.is_some() || child.task.is_none() {
+                    return None;
+                }
+                child
+                    .depends_on
+                    .iter()
+                    .find(|&&d| matches!(self.children[d].outcome, Some(Err(_))))
+                    .map(|&d| (i, d))
+            });
+            let Some((i, d)) = blocked else { break };
+            let child = &mut self.children[i];
+            let error = TaskError::ExecFailed(format!("dependency {d} failed"));
+            if let Some(task) = &mut child.task {
+                task.fail_with(error.to_string());
+            }
+            child.outcome = Some(Err(error));
+        }
+    }
+}
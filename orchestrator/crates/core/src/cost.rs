@@ -0,0 +1,116 @@
+//! Cost — token counting and per-provider pricing for pre-flight budgeting.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::adapter::{AdapterConfig, Message, Provider};
+
+/// Approximate token count of `text`: one token per whitespace-separated
+/// word, the same measure [`MockAdapter`](crate::mock::MockAdapter) reports.
+pub fn count_tokens(text: &str) -> u32 {
+    text.split_whitespace().count() as u32
+}
+
+/// Approximate token count of a whole conversation.
+pub fn count_message_tokens(messages: &[Message]) -> u32 {
+    messages.iter().map(|m| count_tokens(&m.content)).sum()
+}
+
+/// Price of one provider, in currency units per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rates {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl Rates {
+    pub fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self { input_per_mtok, output_per_mtok }
+    }
+
+    /// Cost of a request with the given token counts.
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (f64::from(input_tokens) * self.input_per_mtok
+            + f64::from(output_tokens) * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Per-provider [`Rates`] plus the output length assumed for estimates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostTable {
+    rates: HashMap<Provider, Rates>,
+    max_tokens: u32,
+}
+
+impl Default for CostTable {
+    fn default() -> Self {
+        Self {
+            rates: HashMap::new(),
+            max_tokens: AdapterConfig::DEFAULT_MAX_TOKENS,
+        }
+    }
+}
+
+impl CostTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rates(mut self, provider: Provider, rates: Rates) -> Self {
+        self.rates.insert(provider, rates);
+        self
+    }
+
+    /// Output tokens assumed for a worst-case estimate.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn max_tokens(&self) -> u32 {
+        self.max_tokens
+    }
+
+    pub fn rates(&self, provider: Provider) -> Option<Rates> {
+        self.rates.get(&provider).copied()
+    }
+
+    /// Cost of a completed request, or zero when `provider` is not priced.
+    pub fn cost(&self, provider: Provider, input_tokens: u32, output_tokens: u32) -> f64 {
+        self.rates(provider).map_or(0.0, |r| r.cost(input_tokens, output_tokens))
+    }
+
+    /// Worst-case cost of sending `input_tokens` and receiving `max_tokens`.
+    ///
+    /// Uses `provider`'s rates when it is priced, and otherwise the most
+    /// expensive provider in the table. An empty table costs nothing.
+    pub fn worst_case(&self, provider: Option<Provider>, input_tokens: u32) -> f64 {
+        let cost = |r: &Rates| r.cost(input_tokens, self.max_tokens);
+        match provider.and_then(|p| self.rates.get(&p)) {
+            Some(rates) => cost(rates),
+            None => self.rates.values().map(cost).fold(0.0, f64::max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worst_case_uses_provider_rates_or_most_expensive() {
+        let table = CostTable::new()
+            .with_rates(Provider::Claude, Rates::new(3.0, 15.0))
+            .with_rates(Provider::Gemini, Rates::new(1.0, 2.0))
+            .with_max_tokens(1000);
+
+        // 1M input tokens at $1 + 1000 output tokens at $2/M.
+        assert_eq!(table.worst_case(Some(Provider::Gemini), 1_000_000), 1.002);
+        // Unpriced and unpinned both fall back to Claude, the most expensive.
+        assert_eq!(table.worst_case(Some(Provider::Grok), 0), 0.015);
+        assert_eq!(table.worst_case(None, 0), 0.015);
+        assert_eq!(CostTable::new().worst_case(None, 500), 0.0);
+    }
+}
@@ -0,0 +1,280 @@
+//! Events — a shared, append-only event feed kept in a [`MemorySystem`].
+//!
+//! An [`EventLog`] assigns each appended event the next index, starting at
+//! zero, and stores it under its own key below the log's prefix. An event
+//! claims its index by creating that key with
+//! [`store_if_version`](MemorySystem::store_if_version), so concurrent
+//! appenders never share an index. Only then is the head record, holding
+//! the next free index, advanced past it, so indices are assigned without
+//! gaps even if an appender stops between the two writes.
+//!
+//! Agents reach the log through two capabilities, `events.append` and
+//! `events.read`; see [`EventLogCapability`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::capability::Capability;
+use crate::memory::{MemoryError, MemorySystem};
+
+/// Events returned by one [`EventLog::read`] when no limit is given.
+pub const DEFAULT_READ_LIMIT: usize = 100;
+
+/// Append-only, monotonically indexed event list under a key prefix.
+pub struct EventLog<M> {
+    memory: Arc<M>,
+    prefix: String,
+}
+
+impl<M: MemorySystem> EventLog<M> {
+    /// A log stored under `{prefix}:` in `memory`.
+    pub fn new(memory: Arc<M>, prefix: impl Into<String>) -> Self {
+        Self { memory, prefix: prefix.into() }
+    }
+
+    fn head_key(&self) -> String {
+        format!("{}:head", self.prefix)
+    }
+
+    /// Key of the event at `index`; zero-padded so keys sort by index.
+    pub fn event_key(&self, index: u64) -> String {
+        format!("{}:{index:020}", self.prefix)
+    }
+
+    /// The next free index and the head record's version (`0` if absent).
+    async fn head(&self) -> Result<(u64, u64), MemoryError> {
+        match self.memory.load(&self.head_key()).await {
+            Ok(record) => Ok((record.value["next"].as_u64().unwrap_or_default(), record.version)),
+            Err(MemoryError::NotFound(_)) => Ok((0, 0)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Number of indices assigned so far. Lags by one while an appender is
+    /// between writing its event and advancing the head, or after it failed
+    /// to advance it; the next append catches up.
+    pub async fn len(&self) -> Result<u64, MemoryError> {
+        Ok(self.head().await?.0)
+    }
+
+    pub async fn is_empty(&self) -> Result<bool, MemoryError> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Append `event`, returning its index.
+    ///
+    /// The event is written to the key of the head's next free index only if
+    /// that key does not exist yet. If another appender got there first, the
+    /// head is moved past its event, on its behalf, and the next index tried.
+    pub async fn append(&self, event: Value) -> Result<u64, MemoryError> {
+        loop {
+            let (next, version) = self.head().await?;
+            let key = self.event_key(next);
+            let written = match self.memory.store_if_version(&key, event.clone(), 0).await {
+                Ok(_) => true,
+                Err(MemoryError::VersionConflict { .. }) => false,
+                Err(e) => return Err(e),
+            };
+            let advanced = json!({ "next": next + 1 });
+            match self.memory.store_if_version(&self.head_key(), advanced, version).await {
+                // Someone else already moved the head past `next`.
+                Ok(_) | Err(MemoryError::VersionConflict { .. }) => {}
+                // The event is stored; the next append advances the head.
+                Err(_) if written => {}
+                Err(e) => return Err(e),
+            }
+            if written {
+                return Ok(next);
+            }
+        }
+    }
+
+    /// Up to `limit` events starting at `from_index`, with the index to read
+    /// from next.
+    ///
+    /// Events are read until the first index not yet written, which is where
+    /// the next append goes, so a reader resuming from the returned cursor
+    /// never skips an event.
+    pub async fn read(
+        &self,
+        from_index: u64,
+        limit: usize,
+    ) -> Result<(Vec<(u64, Value)>, u64), MemoryError> {
+        let mut events = Vec::new();
+        let mut cursor = from_index;
+        while events.len() < limit {
+            match self.memory.load(&self.event_key(cursor)).await {
+                Ok(record) => events.push((cursor, record.value)),
+                Err(MemoryError::NotFound(_)) => break,
+                Err(e) => return Err(e),
+            }
+            cursor += 1;
+        }
+        Ok((events, cursor))
+    }
+}
+
+/// Which side of an [`EventLog`] a capability exposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Append,
+    Read,
+}
+
+/// `events.append` / `events.read` — an [`EventLog`] as agent capabilities.
+///
+/// `events.append` takes `{"event": ...}` and returns `{"index": n}`.
+/// `events.read` takes `{"from_index": n, "limit": m}`, both optional, and
+/// returns `{"events": [{"index", "event"}...], "next_index": n}`.
+pub struct EventLogCapability<M> {
+    log: Arc<EventLog<M>>,
+    operation: Operation,
+}
+
+impl<M: MemorySystem> EventLogCapability<M> {
+    /// The `events.append` capability for `log`.
+    pub fn append(log: Arc<EventLog<M>>) -> Self {
+        Self { log, operation: Operation::Append }
+    }
+
+    /// The `events.read` capability for `log`.
+    pub fn read(log: Arc<EventLog<M>>) -> Self {
+        Self { log, operation: Operation::Read }
+    }
+}
+
+#[async_trait]
+impl<M: MemorySystem + 'static> Capability for EventLogCapability<M> {
+    fn name(&self) -> &str {
+        match self.operation {
+            Operation::Append => "events.append",
+            Operation::Read => "events.read",
+        }
+    }
+
+    fn description(&self) -> &str {
+        match self.operation {
+            Operation::Append => {
+                "Append a JSON event to the shared event log and return its index."
+            }
+            Operation::Read => "Read events from the shared event log, starting at an index.",
+        }
+    }
+
+    fn input_schema(&self) -> Value {
+        match self.operation {
+            Operation::Append => json!({
+                "type": "object",
+                "properties": { "event": {} },
+                "required": ["event"]
+            }),
+            Operation::Read => json!({
+                "type": "object",
+                "properties": {
+                    "from_index": { "type": "integer" },
+                    "limit": { "type": "integer" }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<Value> {
+        match self.operation {
+            Operation::Append => {
+                let event = args
+                    .get("event")
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("missing `event`"))?;
+                Ok(json!({ "index": self.log.append(event).await? }))
+            }
+            Operation::Read => {
+                let from_index = args["from_index"].as_u64().unwrap_or(0);
+                let limit = args["limit"].as_u64().map_or(DEFAULT_READ_LIMIT, |n| n as usize);
+                let (events, next_index) = self.log.read(from_index, limit).await?;
+                let events: Vec<Value> = events
+                    .into_iter()
+                    .map(|(index, event)| json!({ "index": index, "event": event }))
+                    .collect();
+                Ok(json!({ "events": events, "next_index": next_index }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::CapabilityRegistry;
+    use crate::memory::InMemoryStore;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_appends_get_contiguous_indices() {
+        let log = Arc::new(EventLog::new(Arc::new(InMemoryStore::new()), "feed"));
+        let appends: Vec<_> = (0..50)
+            .map(|i| {
+                let log = log.clone();
+                tokio::spawn(async move { log.append(json!({ "n": i })).await.unwrap() })
+            })
+            .collect();
+        let mut indices = Vec::new();
+        for append in appends {
+            indices.push(append.await.unwrap());
+        }
+        indices.sort_unstable();
+        assert_eq!(indices, (0..50).collect::<Vec<u64>>());
+        assert_eq!(log.len().await.unwrap(), 50);
+
+        let (events, next) = log.read(0, 100).await.unwrap();
+        assert_eq!((events.len(), next), (50, 50));
+    }
+
+    #[tokio::test]
+    async fn appender_stopping_before_the_head_moves_leaves_no_hole() {
+        let memory = Arc::new(InMemoryStore::new());
+        let log = EventLog::new(memory.clone(), "feed");
+        log.append(json!("a")).await.unwrap();
+        // An appender wrote index 1 but never advanced the head.
+        memory.store(&log.event_key(1), json!("b")).await.unwrap();
+        assert_eq!(log.len().await.unwrap(), 1);
+
+        let (events, next) = log.read(0, 10).await.unwrap();
+        assert_eq!((events.len(), next), (2, 2));
+        assert_eq!(log.append(json!("c")).await.unwrap(), 2);
+        assert_eq!(log.len().await.unwrap(), 3);
+        let (events, _) = log.read(0, 10).await.unwrap();
+        let values: Vec<Value> = events.into_iter().map(|(_, event)| event).collect();
+        assert_eq!(values, [json!("a"), json!("b"), json!("c")]);
+    }
+
+    #[tokio::test]
+    async fn read_resumes_from_cursor() {
+        let log = Arc::new(EventLog::new(Arc::new(InMemoryStore::new()), "feed"));
+        let mut caps = CapabilityRegistry::new();
+        caps.register(Arc::new(EventLogCapability::append(log.clone())));
+        caps.register(Arc::new(EventLogCapability::read(log)));
+
+        for name in ["a", "b", "c", "d", "e"] {
+            caps.call("events.append", json!({ "event": { "name": name } })).await.unwrap();
+        }
+        let page = caps.call("events.read", json!({ "limit": 2 })).await.unwrap();
+        assert_eq!(page["events"][0], json!({ "index": 0, "event": { "name": "a" } }));
+        assert_eq!(page["next_index"], 2);
+
+        let rest = caps.call("events.read", json!({ "from_index": page["next_index"] })).await;
+        let rest = rest.unwrap();
+        let names: Vec<&str> = rest["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["event"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["c", "d", "e"]);
+        assert_eq!(rest["next_index"], 5);
+
+        let empty = caps.call("events.read", json!({ "from_index": 5 })).await.unwrap();
+        assert_eq!(empty, json!({ "events": [], "next_index": 5 }));
+        assert!(caps.call("events.append", json!({})).await.is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! JsonFileStore — durable [`MemorySystem`] kept in a single JSON file.
+//!
+//! Suited to small deployments and tooling that want state to survive a
+//! restart without running a database. Every record lives in memory; the
+//! file holds a snapshot of all of them and is rewritten whole, either after
+//! each write or only on [`flush`](MemorySystem::flush), depending on the
+//! store's [`FlushMode`].
+//!
+//! Unflushed writes are also written out when the store is dropped, so they
+//! survive a normal exit; only a crash or abort loses them. Dropping cannot
+//! report an I/O error, so prefer [`close`](JsonFileStore::close) where the
+//! outcome matters.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tokio::sync::Mutex;
+
+use crate::memory::{FlushMode, MemoryError, MemorySystem, Record, check_version, put};
+
+/// [`MemorySystem`] persisted as one JSON object of key → [`Record`].
+#[derive(Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    mode: FlushMode,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    records: HashMap<String, Record>,
+    /// Changes not yet written to the file.
+    dirty: bool,
+}
+
+impl JsonFileStore {
+    /// Open the store at `path`, loading its records if the file exists.
+    ///
+    /// Writes are persisted as they happen; see
+    /// [`with_flush_mode`](Self::with_flush_mode).
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, MemoryError> {
+        let path = path.as_ref().to_path_buf();
+        let records = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| MemoryError::Serialization(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(io_error(e)),
+        };
+        Ok(Self {
+            path,
+            mode: FlushMode::default(),
+            state: Mutex::new(State { records, dirty: false }),
+        })
+    }
+
+    /// Choose when writes reach the file.
+    ///
+    /// With [`FlushMode::Explicit`], batch many writes and call
+    /// [`flush`](MemorySystem::flush) once, e.g. at a phase boundary;
+    /// anything unflushed when the store is dropped is written then, and is
+    /// lost only if the process dies first.
+    pub fn with_flush_mode(mut self, mode: FlushMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn flush_mode(&self) -> FlushMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush any unwritten changes and close the store.
+    pub async fn close(self) -> Result<(), MemoryError> {
+        self.flush().await
+    }
+
+    /// Apply `change` to the records. Unless flushing is explicit, it is
+    /// applied to a copy that replaces the records only once it is on disk,
+    /// so a failed write leaves the store as it was.
+    async fn change<T>(
+        &self,
+        state: &mut State,
+        change: impl FnOnce(&mut HashMap<String, Record>) -> Result<T, MemoryError>,
+    ) -> Result<T, MemoryError> {
+        match self.mode {
+            FlushMode::PerWrite => {
+                let mut records = state.records.clone();
+                let out = change(&mut records)?;
+                self.persist(&records).await?;
+                state.records = records;
+                state.dirty = false;
+                Ok(out)
+            }
+            FlushMode::Explicit => {
+                let out = change(&mut state.records)?;
+                state.dirty = true;
+                Ok(out)
+            }
+        }
+    }
+
+    /// Replace the file with `records`; see [`write_snapshot`].
+    async fn persist(&self, records: &HashMap<String, Record>) -> Result<(), MemoryError> {
+        let bytes = encode(records)?;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || write_snapshot(&path, &bytes))
+            .await
+            .map_err(|e| MemoryError::Backend(e.to_string()))?
+            .map_err(io_error)
+    }
+}
+
+/// Replace the file at `path` with `bytes` via a temporary file, so a crash
+/// mid-write leaves the previous snapshot intact. The temporary file is
+/// synced before the rename and the directory after it, so neither the
+/// contents nor the rename can be lost once this returns.
+fn write_snapshot(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&tmp, path)?;
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Write out changes still pending, blocking briefly as drop cannot await.
+impl Drop for JsonFileStore {
+    fn drop(&mut self) {
+        let state = self.state.get_mut();
+        if !state.dirty {
+            return;
+        }
+        let written = encode(&state.records)
+            .and_then(|bytes| write_snapshot(&self.path, &bytes).map_err(io_error));
+        if let Err(e) = written {
+            tracing::error!(path = %self.path.display(), "unflushed writes lost on drop: {e}");
+        }
+    }
+}
+
+fn encode(records: &HashMap<String, Record>) -> Result<Vec<u8>, MemoryError> {
+    serde_json::to_vec(records).map_err(|e| MemoryError::Serialization(e.to_string()))
+}
+
+fn io_error(e: std::io::Error) -> MemoryError {
+    MemoryError::Backend(e.to_string())
+}
+
+impl MemorySystem for JsonFileStore {
+    async fn store(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
+        let mut state = self.state.lock().await;
+        self.change(&mut state, |records| put(records, key, value)).await
+    }
+
+    async fn store_if_version(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        expected_version: u64,
+    ) -> Result<u64, MemoryError> {
+        let mut state = self.state.lock().await;
+        let actual = state.records.get(key).map_or(0, |r| r.version);
+        check_version(key, actual, expected_version)?;
+        self.change(&mut state, |records| put(records, key, value)).await
+    }
+
+    /// Compares and writes under the store lock.
+    async fn store_dedup(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
+        let mut state = self.state.lock().await;
+        if let Some(current) = state.records.get(key)
+            && current.value == value
+        {
+            return Ok(current.version);
+        }
+        self.change(&mut state, |records| put(records, key, value)).await
+    }
+
+    async fn load(&self, key: &str) -> Result<Record, MemoryError> {
+        let state = self.state.lock().await;
+        state.records.get(key).cloned().ok_or_else(|| MemoryError::NotFound(key.to_owned()))
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), MemoryError> {
+        let mut state = self.state.lock().await;
+        self.change(&mut state, |records| {
+            records.remove(key).map(drop).ok_or_else(|| MemoryError::NotFound(key.to_owned()))
+        })
+        .await
+    }
+
+    async fn remove_if_version(&self, key: &str, expected_version: u64) -> Result<(), MemoryError> {
+        let mut state = self.state.lock().await;
+        let current =
+            state.records.get(key).ok_or_else(|| MemoryError::NotFound(key.to_owned()))?;
+        check_version(key, current.version, expected_version)?;
+        self.change(&mut state, |records| {
+            records.remove(key);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, MemoryError> {
+        Ok(self.state.lock().await.records.keys().cloned().collect())
+    }
+
+    async fn flush(&self) -> Result<(), MemoryError> {
+        let mut state = self.state.lock().await;
+        if state.dirty {
+            self.persist(&state.records).await?;
+            state.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn store_batch_reports_each_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.json");
+        // A record whose version cannot advance makes its item fail.
+        let full = Record {
+            key: "full".into(),
+            value: json!(null),
+            version: u64::MAX,
+            updated_at: chrono::Utc::now(),
+        };
+        std::fs::write(&path, encode(&HashMap::from([("full".into(), full)])).unwrap()).unwrap();
+
+        let store = JsonFileStore::open(&path).await.unwrap();
+        let items = ["a", "full", "b"].map(|k| (k.to_owned(), json!(k))).to_vec();
+        let results = store.store_batch(items).await;
+        assert_eq!(results[0].as_ref().unwrap(), &1);
+        assert!(matches!(results[1], Err(MemoryError::Backend(_))));
+        assert_eq!(results[2].as_ref().unwrap(), &1);
+        drop(store);
+
+        // The items before and after the failure were written durably.
+        let reopened = JsonFileStore::open(&path).await.unwrap();
+        let mut keys = reopened.keys().await.unwrap();
+        keys.sort();
+        assert_eq!(keys, ["a", "b", "full"]);
+    }
+
+    #[tokio::test]
+    async fn per_write_mode_persists_every_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.json");
+        let store = JsonFileStore::open(&path).await.unwrap();
+        store.store("k1", json!({"a": 1})).await.unwrap();
+        store.store("k1", json!({"a": 2})).await.unwrap();
+        drop(store);
+
+        let reopened = JsonFileStore::open(&path).await.unwrap();
+        let rec = reopened.load("k1").await.unwrap();
+        assert_eq!((rec.value, rec.version), (json!({"a": 2}), 2));
+    }
+
+    #[tokio::test]
+    async fn store_dedup_writes_only_changed_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.json");
+        let store = JsonFileStore::open(&path).await.unwrap();
+        assert_eq!(store.store_dedup("k1", json!({"a": 1, "b": 2})).await.unwrap(), 1);
+        let written = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(store.store_dedup("k1", json!({"b": 2, "a": 1})).await.unwrap(), 1);
+        assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), written);
+        assert_eq!(store.store_dedup("k1", json!({"a": 2})).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_write_leaves_the_store_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("memory.json");
+        std::fs::create_dir(path.parent().unwrap()).unwrap();
+        let store = JsonFileStore::open(&path).await.unwrap();
+        store.store("kept", json!(1)).await.unwrap();
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(store.store("lost", json!(2)).await.is_err());
+        assert!(store.remove("kept").await.is_err());
+        assert!(matches!(store.load("lost").await, Err(MemoryError::NotFound(_))));
+        assert_eq!(store.load("kept").await.unwrap().value, json!(1));
+
+        // The next successful write carries only what was accepted.
+        std::fs::create_dir(path.parent().unwrap()).unwrap();
+        store.store("next", json!(3)).await.unwrap();
+        let mut keys = JsonFileStore::open(&path).await.unwrap().keys().await.unwrap();
+        keys.sort();
+        assert_eq!(keys, ["kept", "next"]);
+    }
+
+    #[tokio::test]
+    async fn explicit_mode_writes_only_on_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.json");
+        let store =
+            JsonFileStore::open(&path).await.unwrap().with_flush_mode(FlushMode::Explicit);
+        for i in 0..10 {
+            store.store(&format!("k{i}"), json!(i)).await.unwrap();
+        }
+        assert!(!path.exists(), "file written before flush");
+
+        store.flush().await.unwrap();
+        let on_disk: HashMap<String, Record> =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(on_disk.len(), 10);
+
+        store.remove("k0").await.unwrap();
+        let reopened = JsonFileStore::open(&path).await.unwrap();
+        assert!(reopened.load("k0").await.is_ok(), "remove persisted before flush");
+        store.flush().await.unwrap();
+        let reopened = JsonFileStore::open(&path).await.unwrap();
+        assert!(matches!(reopened.load("k0").await, Err(MemoryError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn drop_writes_unflushed_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.json");
+        let store =
+            JsonFileStore::open(&path).await.unwrap().with_flush_mode(FlushMode::Explicit);
+        store.store("k1", json!({"a": 1})).await.unwrap();
+        assert!(!path.exists());
+        drop(store);
+
+        let reopened = JsonFileStore::open(&path).await.unwrap();
+        assert_eq!(reopened.load("k1").await.unwrap().value, json!({"a": 1}));
+        reopened.store("k2", json!(2)).await.unwrap();
+        reopened.close().await.unwrap();
+        let reopened = JsonFileStore::open(&path).await.unwrap();
+        assert_eq!(reopened.keys().await.unwrap().len(), 2);
+    }
+}
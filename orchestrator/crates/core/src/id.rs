@@ -0,0 +1,91 @@
+//! IdGenerator — where task and query ids come from.
+//!
+//! [`Task::new`](crate::task::Task::new) and [`Query::new`](crate::logic::Query::new)
+//! draw random v4 ids. Their `new_with_ids` counterparts, and
+//! [`Task::fork_with_ids`](crate::task::Task::fork_with_ids), take any
+//! [`IdGenerator`], e.g. a [`SequentialIdGenerator`] so a test run produces
+//! the same ids every time. [`TaskRunner`](crate::runner::TaskRunner) and
+//! [`LlmQueryCapability`](crate::capability::LlmQueryCapability) build their
+//! queries from one given `with_id_generator`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use uuid::Uuid;
+
+/// Source of fresh ids.
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> Uuid;
+}
+
+/// Random (v4) ids; the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Ids counting up from a starting number, so they are reproducible and
+/// sort in creation order: `00000000-0000-0000-0000-000000000001`, then
+/// `…0002`, and so on.
+#[derive(Debug)]
+pub struct SequentialIdGenerator {
+    next: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    /// Count from 1.
+    pub fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    pub fn starting_at(first: u64) -> Self {
+        Self { next: AtomicU64::new(first) }
+    }
+}
+
+impl Default for SequentialIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Uuid::from_u128(u128::from(self.next.fetch_add(1, Ordering::Relaxed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::Query;
+    use crate::protocol::TaskMeta;
+    use crate::task::Task;
+    use serde_json::json;
+
+    /// The ids of the tasks and queries one scripted run creates.
+    fn run(ids: &dyn IdGenerator) -> Vec<Uuid> {
+        let meta = TaskMeta::new("test", "probe", "");
+        let task = Task::new_with_ids(meta.clone(), json!({}), ids);
+        let query = Query::new_with_ids("hello", ids);
+        let other = Task::new_with_ids(meta, json!({}), ids);
+        vec![task.id, query.id, other.id]
+    }
+
+    #[test]
+    fn sequential_ids_are_reproducible_across_runs() {
+        let first = run(&SequentialIdGenerator::new());
+        assert_eq!(first, run(&SequentialIdGenerator::new()));
+        assert_eq!(first, [1, 2, 3].map(Uuid::from_u128));
+        assert!(first.is_sorted());
+
+        let offset = run(&SequentialIdGenerator::starting_at(10));
+        assert_eq!(offset[0], Uuid::from_u128(10));
+
+        let random = run(&RandomIdGenerator);
+        assert_ne!(random, run(&RandomIdGenerator));
+    }
+}
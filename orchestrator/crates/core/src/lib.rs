@@ -1,19 +1,98 @@
 pub mod adapter;
 pub mod agent;
 pub mod bus;
+pub mod cache;
 pub mod capability;
+#[cfg(any(test, feature = "test-util"))]
+pub mod cassette;
+pub mod codec;
+pub mod composite;
+pub mod cost;
+pub mod events;
+pub mod file_store;
+pub mod id;
 pub mod logic;
 pub mod memory;
+pub mod metrics;
+pub mod middleware;
+pub mod mock;
 pub mod orchestrator;
+pub mod phase_guard;
+pub mod pool;
+pub mod prompt;
 pub mod protocol;
+pub mod registry;
+pub mod runner;
+pub mod schema;
+pub mod semantic;
+#[cfg(any(test, feature = "test-util"))]
+pub mod sim;
+pub mod stream;
+#[cfg(feature = "sled")]
+pub mod sled_store;
+pub mod sse;
 pub mod task;
+pub mod text;
+pub mod throttle;
+pub mod worker;
 
-pub use adapter::{Adapter, AdapterConfig, AdapterError, ModelResponse, Provider, Role};
+pub use adapter::{
+    Adapter, AdapterConfig, AdapterConfigBuilder, AdapterError, ChunkStream, ConfigError,
+    ContentBlock, ContinuingAdapter, Feature, FinishReason, HealthStatus, ModelResponse,
+    ModelTier, Provider, Role, UnknownProvider,
+};
 pub use agent::{Agent, AgentMetadata};
-pub use bus::{MessageBus, MessageBusError};
-pub use capability::{Capability, CapabilityRegistry};
-pub use logic::{CoreLogic, LogicError, Query, QueryResult};
-pub use memory::{MemoryError, MemorySystem, Record};
-pub use orchestrator::Orchestrator;
-pub use protocol::{LogEntry, LogLevel, LogSink, MemoryLogSink, Message, MessageKind, TaskMeta};
-pub use task::{Task, TaskError, TaskPhase, TaskResult};
\ No newline at end of file
+pub use bus::{
+    BusJournal, BusSubscriber, DEFAULT_DEDUP_WINDOW, MemoryJournal, MessageBus, MessageBusError,
+    ReliableChannel, ReliableSubscriber,
+};
+pub use cache::{CacheKeyStrategy, Custom, Exact, Normalized};
+pub use capability::{
+    CachedCapability, Capability, CapabilityError, CapabilityRegistry, LlmQueryCapability,
+    MemGetCapability, MemSetCapability,
+};
+#[cfg(any(test, feature = "test-util"))]
+pub use cassette::{Cassette, CassetteAdapter};
+pub use codec::{Codec, CodecError, JsonCodec};
+pub use composite::{Aggregator, CollectOutputs, CompositeTask};
+#[cfg(feature = "msgpack")]
+pub use codec::MessagePackCodec;
+pub use cost::{CostTable, Rates};
+pub use events::{EventLog, EventLogCapability};
+pub use file_store::JsonFileStore;
+pub use id::{IdGenerator, RandomIdGenerator, SequentialIdGenerator};
+pub use logic::{
+    BatchSummary, CachingLogic, CoreLogic, DefaultLogic, InFlightRegistry, LogicError,
+    ProgressCallback, ProviderFailure, Query, QueryResult, QueryStream, SingleFlightLogic,
+    TimeoutLogic, resolve_timeout,
+};
+pub use memory::{FlushMode, MemoryError, MemorySystem, Record, SerializationFormat};
+pub use metrics::{Metrics, collect_all};
+pub use middleware::{
+    AdapterLayer, JitterStrategy, LoggingLayer, MeteringLayer, Next, ObservableAdapter,
+    RequestHook, ResponseHook, RetryLayer, ServiceStack, Usage,
+};
+pub use orchestrator::{Orchestrator, ShutdownHandle, ShutdownReport, SystemHandle, TickFailure};
+pub use phase_guard::PhaseGuard;
+pub use pool::PooledAdapter;
+pub use prompt::{Prompt, PromptAssembler, SystemPlacement};
+pub use protocol::{
+    AlertHook, AlertSink, DedupSink, HmacSink, LogEntry, LogLevel, LogSink, MemoryLogSink,
+    Message, MessageKind, SCHEMA_VERSION, SystemEvent, TaskLogSink, TaskMeta, verify_chain,
+};
+pub use registry::{
+    AdapterRegistry, HealthCache, HealthEntry, LatencyTracker, ProviderInfo, RateLimitState,
+};
+pub use runner::{Retention, RetryBudget, TaskRunner};
+pub use schema::SchemaRegistry;
+pub use semantic::{Embedder, SemanticCache};
+#[cfg(any(test, feature = "test-util"))]
+pub use sim::SimulatedOrchestrator;
+pub use stream::{StreamError, StreamSummary};
+#[cfg(feature = "sled")]
+pub use sled_store::SledStore;
+pub use sse::{SseEvent, parse_sse};
+pub use task::{Task, TaskError, TaskPhase, TaskResult, TaskTracker};
+pub use throttle::{ThrottledAdapter, TokenBucket};
+pub use worker::{PoolStats, TaskQueue, WorkerPool};
+pub use tokio_util::sync::CancellationToken;
\ No newline at end of file
@@ -1,10 +1,31 @@
 //! CoreLogic — multi-layered query handling and internal decision-making.
 
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::{Semaphore, watch};
+use tokio_util::sync::CancellationToken;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::adapter::{
+    Adapter, AdapterError, Message, ModelResponse, ModelTier, Provider, Role, UnknownProvider,
+};
+use crate::cache::{CacheKeyStrategy, Exact, hash_parts, load_fresh};
+use crate::codec::{Codec, CodecError};
+use crate::cost::{CostTable, count_message_tokens};
+use crate::id::{IdGenerator, RandomIdGenerator};
+use crate::memory::MemorySystem;
+use crate::prompt::PromptAssembler;
+use crate::protocol::{LogEntry, LogLevel, LogSink};
+use crate::registry::{AdapterRegistry, ProviderInfo};
+use crate::text;
 
 /// Errors produced by [`CoreLogic`] operations.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum LogicError {
     #[error("query failed: {0}")]
     QueryFailed(String),
@@ -12,6 +33,103 @@ pub enum LogicError {
     ProviderUnavailable(String),
     #[error("timeout after {0} ms")]
     Timeout(u64),
+    #[error("no healthy providers: {}", summarize_failures(.0))]
+    NoHealthyProviders(Vec<ProviderFailure>),
+    /// The registry is empty, so nothing can answer until an adapter is
+    /// registered.
+    #[error("no providers registered")]
+    NoProvidersRegistered,
+    #[error(transparent)]
+    UnknownProvider(#[from] UnknownProvider),
+    #[error("cancelled")]
+    Cancelled,
+}
+
+impl LogicError {
+    /// Whether retrying the same query might succeed.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(
+            self,
+            Self::NoProvidersRegistered | Self::UnknownProvider(_) | Self::Cancelled
+        )
+    }
+
+    /// Stable snake_case name of the variant, e.g. `"timeout"`, for grouping
+    /// errors in [`BatchSummary::by_error`].
+    pub fn class(&self) -> &'static str {
+        match self {
+            Self::QueryFailed(_) => "query_failed",
+            Self::ProviderUnavailable(_) => "provider_unavailable",
+            Self::Timeout(_) => "timeout",
+            Self::NoHealthyProviders(_) => "no_healthy_providers",
+            Self::NoProvidersRegistered => "no_providers_registered",
+            Self::UnknownProvider(_) => "unknown_provider",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Outcome counts of a batch of queries, with failures broken down by
+/// [`LogicError::class`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub by_error: HashMap<String, usize>,
+}
+
+impl BatchSummary {
+    /// Summarize `results`, e.g. as returned by [`CoreLogic::query_batch`].
+    pub fn from_results<'a>(
+        results: impl IntoIterator<Item = &'a Result<QueryResult, LogicError>>,
+    ) -> Self {
+        let mut summary = Self::default();
+        for result in results {
+            summary.total += 1;
+            match result {
+                Ok(_) => summary.succeeded += 1,
+                Err(e) => {
+                    summary.failed += 1;
+                    *summary.by_error.entry(e.class().to_owned()).or_default() += 1;
+                }
+            }
+        }
+        summary
+    }
+
+    /// The error class behind most failures, if any failed. Ties go to the
+    /// class that sorts first.
+    pub fn dominant_error(&self) -> Option<&str> {
+        self.by_error
+            .iter()
+            .max_by_key(|(class, n)| (**n, Reverse(class.as_str())))
+            .map(|(class, _)| class.as_str())
+    }
+}
+
+/// A provider together with the reason it was last seen failing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderFailure {
+    pub provider: Provider,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ProviderFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.provider, self.reason)
+    }
+}
+
+fn summarize_failures(failures: &[ProviderFailure]) -> String {
+    if failures.is_empty() {
+        return "none registered".into();
+    }
+    failures
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// A single query submitted to the orchestrator.
@@ -24,17 +142,48 @@ pub struct Query {
     /// Optional system-level context prepended to the query.
     pub system_context: Option<String>,
     /// Target provider name (e.g. "claude", "gemini", "grok"). `None` = best available.
+    ///
+    /// Kept as a string for wire compatibility; routing goes through
+    /// [`target_provider`](Self::target_provider).
     pub provider: Option<String>,
+    /// Deadline for the whole query in milliseconds. `None` = logic default.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Share of a limited batch's concurrency: a query of priority `p` is
+    /// launched `p + 1` times as often as one of priority 0.
+    #[serde(default)]
+    pub priority: u8,
+    /// Model size the query calls for. Unpinned queries route to an adapter
+    /// of this tier, or the next larger one available. `None` = any tier.
+    #[serde(default)]
+    pub tier: Option<ModelTier>,
+    /// Caller tags such as tenant, user or trace ids. They do not affect
+    /// routing or caching, and are carried onto the [`QueryResult`].
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// `provider`, parsed when it was set through a builder.
+    #[serde(skip)]
+    pinned: Option<Provider>,
 }
 
 impl Query {
     /// Create a new query with auto-generated id.
     pub fn new(content: impl Into<String>) -> Self {
+        Self::new_with_ids(content, &RandomIdGenerator)
+    }
+
+    /// Like [`new`](Self::new), taking the id from `ids`.
+    pub fn new_with_ids(content: impl Into<String>, ids: &dyn IdGenerator) -> Self {
         Self {
-            id: uuid::Uuid::new_v4(),
+            id: ids.next_id(),
             content: content.into(),
             system_context: None,
             provider: None,
+            timeout_ms: None,
+            priority: 0,
+            tier: None,
+            metadata: HashMap::new(),
+            pinned: None,
         }
     }
 
@@ -44,11 +193,74 @@ impl Query {
         self
     }
 
-    /// Pin the query to a specific provider.
+    /// Pin the query to a specific provider by name.
+    ///
+    /// Unknown names are kept as-is and rejected at routing time.
     pub fn with_provider(mut self, p: impl Into<String>) -> Self {
-        self.provider = Some(p.into());
+        let name = p.into();
+        self.pinned = name.parse().ok();
+        self.provider = Some(name);
+        self
+    }
+
+    /// Pin the query to `provider`, storing its canonical name.
+    pub fn with_provider_enum(mut self, provider: Provider) -> Self {
+        self.provider = Some(provider.as_str().into());
+        self.pinned = Some(provider);
+        self
+    }
+
+    /// The provider this query is pinned to, if any.
+    ///
+    /// Uses the value parsed by the builders when it still matches
+    /// `provider`, and parses the string otherwise (e.g. after
+    /// deserialization). Fails if the name is not a known provider.
+    pub fn target_provider(&self) -> Result<Option<Provider>, LogicError> {
+        match (self.pinned, self.provider.as_deref()) {
+            (_, None) => Ok(None),
+            (Some(p), Some(name)) if p.as_str() == name => Ok(Some(p)),
+            (_, Some(name)) => Ok(Some(name.parse()?)),
+        }
+    }
+
+    /// Give the query a larger share of a limited batch; see
+    /// [`priority`](Self::priority).
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Prefer adapters of `tier`; see [`tier`](Self::tier).
+    pub fn with_tier(mut self, tier: ModelTier) -> Self {
+        self.tier = Some(tier);
         self
     }
+
+    /// Tag the query with `key`; see [`metadata`](Self::metadata).
+    pub fn with_meta(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Fail with [`LogicError::Timeout`] if no result arrives within `ms`.
+    pub fn with_timeout_ms(mut self, ms: u64) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+
+    /// Encode for a transport with `codec`.
+    pub fn to_bytes(&self, codec: &impl Codec) -> Result<Vec<u8>, CodecError> {
+        codec.encode(self)
+    }
+
+    /// Decode a query encoded by [`to_bytes`](Self::to_bytes) with the same codec.
+    pub fn from_bytes(codec: &impl Codec, bytes: &[u8]) -> Result<Self, CodecError> {
+        codec.decode(bytes)
+    }
 }
 
 /// The result of processing a single query.
@@ -57,9 +269,64 @@ pub struct QueryResult {
     pub query_id: uuid::Uuid,
     pub provider_used: String,
     pub content: String,
+    /// End-to-end provider time; see [`ModelResponse::total_ms`].
     pub latency_ms: u64,
+    /// Share of `latency_ms` spent on the network.
+    #[serde(default)]
+    pub network_ms: u64,
+    #[serde(default)]
+    pub input_tokens: u32,
+    #[serde(default)]
+    pub output_tokens: u32,
+    /// The [metadata](Query::metadata) of the query this answers.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl QueryResult {
+    /// Build the result of `query_id` from the provider's response.
+    pub fn from_response(query_id: uuid::Uuid, response: ModelResponse) -> Self {
+        Self {
+            query_id,
+            provider_used: response.provider.to_string(),
+            content: response.content,
+            latency_ms: response.latency_ms,
+            network_ms: response.network_ms,
+            input_tokens: response.input_tokens,
+            output_tokens: response.output_tokens,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// End-to-end provider time; `latency_ms` under its split name.
+    pub fn total_ms(&self) -> u64 {
+        self.latency_ms
+    }
+
+    /// `content` cut to at most `max_chars` characters for display; see
+    /// [`text::preview`].
+    pub fn preview(&self, max_chars: usize) -> std::borrow::Cow<'_, str> {
+        text::preview(&self.content, max_chars)
+    }
+
+    /// Encode for a transport with `codec`.
+    pub fn to_bytes(&self, codec: &impl Codec) -> Result<Vec<u8>, CodecError> {
+        codec.encode(self)
+    }
+
+    /// Decode a result encoded by [`to_bytes`](Self::to_bytes) with the same codec.
+    pub fn from_bytes(codec: &impl Codec, bytes: &[u8]) -> Result<Self, CodecError> {
+        codec.decode(bytes)
+    }
+}
+
+/// Type-erased progress callback for
+/// [`query_batch_with_progress`](CoreLogic::query_batch_with_progress).
+pub type ProgressCallback = Box<dyn Fn(usize, &Result<QueryResult, LogicError>) + Send + Sync>;
+
+/// Content chunks of a streamed answer; see [`CoreLogic::query_stream`].
+pub type QueryStream<'a> = futures::stream::BoxStream<'a, Result<String, LogicError>>;
+
 /// Multi-layered query handling engine.
 ///
 /// Implementations route queries to the appropriate AI provider, manage
@@ -76,11 +343,872 @@ pub trait CoreLogic: Send + Sync {
         &self,
         queries: Vec<Query>,
     ) -> impl std::future::Future<Output = Vec<Result<QueryResult, LogicError>>> + Send;
+
+    /// Worst-case cost of `query` under `table`, computed before sending it.
+    ///
+    /// Counts the input tokens of the query's messages, assumes the table's
+    /// `max_tokens` of output, and prices them at the pinned provider's
+    /// rates — or the most expensive provider's when the query is unpinned.
+    fn estimate_cost(&self, query: &Query, table: &CostTable) -> f64 {
+        let provider = query.target_provider().ok().flatten();
+        table.worst_case(provider, count_message_tokens(&query_messages(query)))
+    }
+
+    /// The providers this logic can route to, with their current health
+    /// and capabilities. Default: none, for logic without a registry.
+    fn available_providers(&self) -> Vec<ProviderInfo> {
+        Vec::new()
+    }
+
+    /// Whether this logic applies query deadlines itself, resolving each
+    /// against the provider a query is routed to (see [`resolve_timeout`]).
+    /// [`TimeoutLogic`] then leaves deadlines to it. Decorators forward the
+    /// inner logic's answer. Default: `false`.
+    fn resolves_timeouts(&self) -> bool {
+        false
+    }
+
+    /// Stream the answer to `query` as content chunks, so it can be shown
+    /// while it is still being written.
+    ///
+    /// The default yields the whole [`query`](Self::query) answer as a
+    /// single chunk. The stream ends after the first error.
+    fn query_stream(&self, query: Query) -> QueryStream<'_> {
+        Box::pin(futures::stream::once(async move {
+            self.query(query).await.map(|result| result.content)
+        }))
+    }
+
+    /// Submit multiple queries concurrently until `cancel` is tripped.
+    ///
+    /// See [`query_batch_limited`](Self::query_batch_limited); this variant
+    /// places no limit on concurrency.
+    fn query_batch_cancellable(
+        &self,
+        queries: Vec<Query>,
+        cancel: &CancellationToken,
+    ) -> impl std::future::Future<Output = Vec<Result<QueryResult, LogicError>>> + Send {
+        let limit = queries.len();
+        self.query_batch_limited(queries, limit, cancel)
+    }
+
+    /// Submit multiple queries with at most `limit` in flight at once.
+    ///
+    /// Queries are launched in weighted-fair order by
+    /// [`priority`](Query::priority): higher priorities get proportionally
+    /// more of the `limit` slots, but every priority keeps making progress.
+    /// When `cancel` is tripped no further queries are launched and those in
+    /// flight are dropped. Results are returned in input order; every query
+    /// that did not finish before cancellation yields
+    /// [`LogicError::Cancelled`].
+    fn query_batch_limited(
+        &self,
+        queries: Vec<Query>,
+        limit: usize,
+        cancel: &CancellationToken,
+    ) -> impl std::future::Future<Output = Vec<Result<QueryResult, LogicError>>> + Send {
+        async move {
+            let mut results: Vec<Option<Result<QueryResult, LogicError>>> =
+                std::iter::repeat_with(|| None).take(queries.len()).collect();
+            let mut queue = FairQueue::default();
+            for (i, query) in queries.into_iter().enumerate() {
+                queue.push(query.priority, (i, query));
+            }
+            let mut in_flight = futures::stream::FuturesUnordered::new();
+            loop {
+                while in_flight.len() < limit.max(1) && !cancel.is_cancelled() {
+                    let Some((i, query)) = queue.pop() else { break };
+                    in_flight.push(async move { (i, self.query(query).await) });
+                }
+                if in_flight.is_empty() {
+                    break;
+                }
+                tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => break,
+                    Some((i, result)) = in_flight.next() => results[i] = Some(result),
+                }
+            }
+            results
+                .into_iter()
+                .map(|r| r.unwrap_or(Err(LogicError::Cancelled)))
+                .collect()
+        }
+    }
+
+    /// Send `query` to `providers[0]` and, each time `hedge_after` passes
+    /// without an answer, also to the next provider in `providers`.
+    ///
+    /// The first successful result wins and the queries still in flight are
+    /// dropped. A failure launches the next provider straight away; if every
+    /// provider fails the last error is returned. With no providers this is
+    /// a plain [`query`](Self::query).
+    fn query_hedged(
+        &self,
+        query: Query,
+        providers: &[Provider],
+        hedge_after: Duration,
+    ) -> impl std::future::Future<Output = Result<QueryResult, LogicError>> + Send {
+        async move {
+            let mut remaining = providers.iter().copied();
+            let Some(first) = remaining.next() else {
+                return self.query(query).await;
+            };
+            let mut in_flight = futures::stream::FuturesUnordered::new();
+            in_flight.push(self.query(query.clone().with_provider_enum(first)));
+            loop {
+                tokio::select! {
+                    Some(result) = in_flight.next() => match (result, remaining.next()) {
+                        (Ok(result), _) => return Ok(result),
+                        (Err(_), Some(next)) => {
+                            in_flight.push(self.query(query.clone().with_provider_enum(next)))
+                        }
+                        (Err(e), None) if in_flight.is_empty() => return Err(e),
+                        (Err(_), None) => {}
+                    },
+                    _ = tokio::time::sleep(hedge_after), if remaining.len() > 0 => {
+                        let next = remaining.next().expect("guarded by len");
+                        in_flight.push(self.query(query.clone().with_provider_enum(next)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Submit multiple queries concurrently, invoking `on_result` as each one
+    /// completes with the number of queries completed so far (1-based) and
+    /// its result. Results are returned in completion order.
+    ///
+    /// `on_result` may be a closure or a boxed [`ProgressCallback`].
+    fn query_batch_with_progress<F>(
+        &self,
+        queries: Vec<Query>,
+        on_result: F,
+    ) -> impl std::future::Future<Output = Vec<Result<QueryResult, LogicError>>> + Send
+    where
+        F: Fn(usize, &Result<QueryResult, LogicError>) + Send + Sync,
+    {
+        async move {
+            let mut pending: futures::stream::FuturesUnordered<_> =
+                queries.into_iter().map(|q| self.query(q)).collect();
+            let mut results = Vec::with_capacity(pending.len());
+            while let Some(result) = pending.next().await {
+                on_result(results.len() + 1, &result);
+                results.push(result);
+            }
+            results
+        }
+    }
+}
+
+/// Stride scheduler over FIFO queues, one per priority.
+///
+/// Each priority advances a virtual clock by `STRIDE / (priority + 1)` per
+/// item taken, and the priority with the earliest clock goes next (the
+/// higher one on ties), so priority `p` is served `p + 1` times as often as
+/// priority 0 without starving it.
+struct FairQueue<T> {
+    classes: BTreeMap<Reverse<u8>, (u64, VecDeque<T>)>,
+}
+
+impl<T> Default for FairQueue<T> {
+    fn default() -> Self {
+        Self { classes: Default::default() }
+    }
+}
+
+impl<T> FairQueue<T> {
+    const STRIDE: u64 = 1 << 16;
+
+    fn push(&mut self, priority: u8, item: T) {
+        self.classes.entry(Reverse(priority)).or_default().1.push_back(item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let (&Reverse(priority), (pass, items)) = self
+            .classes
+            .iter_mut()
+            .filter(|(_, (_, items))| !items.is_empty())
+            .min_by_key(|(_, (pass, _))| *pass)?;
+        *pass += Self::STRIDE / (u64::from(priority) + 1);
+        items.pop_front()
+    }
+}
+
+/// The conversation sent to a provider for `query`: its system context, if
+/// any, followed by the prompt as a user message.
+pub fn query_messages(query: &Query) -> Vec<Message> {
+    let system = query.system_context.iter().map(|ctx| Message {
+        role: Role::System,
+        content: ctx.clone(),
+    });
+    system
+        .chain(std::iter::once(Message { role: Role::User, content: query.content.clone() }))
+        .collect()
+}
+
+/// Cancellation tokens of the queries currently running, by [`Query::id`],
+/// so a control plane can stop one without holding its token.
+///
+/// Entries exist only while their query runs: [`run`](Self::run) adds one
+/// and removes it when the query finishes or is dropped. Clones share the
+/// same entries.
+#[derive(Debug, Clone, Default)]
+pub struct InFlightRegistry {
+    /// Each token with the number of runs sharing it.
+    running: Arc<Mutex<HashMap<uuid::Uuid, (CancellationToken, usize)>>>,
+}
+
+impl InFlightRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `query` as query `id`, failing with [`LogicError::Cancelled`] if
+    /// [`cancel`](Self::cancel) is called for `id` before it finishes.
+    pub async fn run<F>(&self, id: uuid::Uuid, query: F) -> Result<QueryResult, LogicError>
+    where
+        F: std::future::Future<Output = Result<QueryResult, LogicError>>,
+    {
+        let entry = InFlightEntry::enter(self, id);
+        tokio::select! {
+            biased;
+            _ = entry.token.cancelled() => Err(LogicError::Cancelled),
+            result = query => result,
+        }
+    }
+
+    /// Cancel query `id`, returning whether it was running.
+    pub fn cancel(&self, id: uuid::Uuid) -> bool {
+        match self.running.lock().unwrap().get(&id) {
+            Some((token, _)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, id: uuid::Uuid) -> bool {
+        self.running.lock().unwrap().contains_key(&id)
+    }
+
+    /// Ids of the queries running now.
+    pub fn ids(&self) -> Vec<uuid::Uuid> {
+        self.running.lock().unwrap().keys().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.running.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// One run's hold on its registry entry, released on drop. Runs of the same
+/// id share a token, so cancelling the id stops them all.
+struct InFlightEntry<'a> {
+    registry: &'a InFlightRegistry,
+    id: uuid::Uuid,
+    token: CancellationToken,
+}
+
+impl<'a> InFlightEntry<'a> {
+    fn enter(registry: &'a InFlightRegistry, id: uuid::Uuid) -> Self {
+        let mut running = registry.running.lock().unwrap();
+        let (token, runs) = running.entry(id).or_insert_with(|| (CancellationToken::new(), 0));
+        *runs += 1;
+        Self { registry, id, token: token.clone() }
+    }
+}
+
+impl Drop for InFlightEntry<'_> {
+    fn drop(&mut self) {
+        let mut running = self.registry.running.lock().unwrap();
+        if let Some((_, runs)) = running.get_mut(&self.id) {
+            *runs -= 1;
+            if *runs == 0 {
+                running.remove(&self.id);
+            }
+        }
+    }
+}
+
+/// [`CoreLogic`] that answers queries through the adapters of an
+/// [`AdapterRegistry`].
+///
+/// Each query goes to its pinned provider, or to the healthy provider with
+/// the lowest latency estimate. Outcomes feed back into the registry's
+/// health cache and latency tracker, so routing adapts as providers slow
+/// down or fail.
+///
+/// Providers given a [limit](Self::with_provider_limit) serve at most that
+/// many queries at once; further queries routed to them wait their turn
+/// without holding up queries to other providers.
+///
+/// Running queries are listed in an [`InFlightRegistry`], through which any
+/// of them can be cancelled by id.
+pub struct DefaultLogic {
+    registry: AdapterRegistry,
+    prompts: PromptAssembler,
+    limits: HashMap<Provider, Semaphore>,
+    timeouts: HashMap<Provider, Duration>,
+    default_timeout: Option<Duration>,
+    in_flight: InFlightRegistry,
+}
+
+impl DefaultLogic {
+    pub fn new(registry: AdapterRegistry) -> Self {
+        Self {
+            registry,
+            prompts: PromptAssembler::default(),
+            limits: HashMap::new(),
+            timeouts: HashMap::new(),
+            default_timeout: None,
+            in_flight: InFlightRegistry::new(),
+        }
+    }
+
+    /// List running queries in `in_flight`, e.g. one shared with a control
+    /// plane, instead of a registry of this logic's own.
+    pub fn with_in_flight_registry(mut self, in_flight: InFlightRegistry) -> Self {
+        self.in_flight = in_flight;
+        self
+    }
+
+    /// Send at most `max_in_flight` queries to `provider` at once. At least
+    /// one is always allowed.
+    pub fn with_provider_limit(mut self, provider: Provider, max_in_flight: usize) -> Self {
+        self.limits.insert(provider, Semaphore::new(max_in_flight.max(1)));
+        self
+    }
+
+    /// Give queries routed to `provider` `timeout` to answer, unless they
+    /// set their own; see [`resolve_timeout`]. Pass an
+    /// [`AdapterConfig::timeout`](crate::adapter::AdapterConfig::timeout) here.
+    pub fn with_provider_timeout(mut self, provider: Provider, timeout: Duration) -> Self {
+        self.timeouts.insert(provider, timeout);
+        self
+    }
+
+    /// Give queries `timeout` to answer when neither they nor their
+    /// provider set one. Default: no deadline.
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// The deadline for `query` on `provider`, if any timeout applies.
+    fn deadline(&self, query: &Query, provider: Provider) -> Option<Duration> {
+        let provider = self.timeouts.get(&provider).copied();
+        let global = self.default_timeout;
+        (query.timeout_ms.is_some() || provider.is_some() || global.is_some())
+            .then(|| resolve_timeout(query, provider, global.unwrap_or(Duration::MAX)))
+    }
+
+    /// Shape each provider's conversation with `prompts` instead of the
+    /// default conventions.
+    pub fn with_prompt_assembler(mut self, prompts: PromptAssembler) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    pub fn registry(&self) -> &AdapterRegistry {
+        &self.registry
+    }
+
+    pub fn in_flight(&self) -> &InFlightRegistry {
+        &self.in_flight
+    }
+
+    /// Startup check: with no adapters registered, log a `Warn` to `log`
+    /// and return [`LogicError::NoProvidersRegistered`], the error every
+    /// query will fail with.
+    pub fn validate(&self, log: &impl LogSink) -> Result<(), LogicError> {
+        if !self.registry.is_empty() {
+            return Ok(());
+        }
+        log.emit(&LogEntry::new(
+            LogLevel::Warn,
+            "logic",
+            "no providers registered; every query will fail until an adapter is registered",
+        ));
+        Err(LogicError::NoProvidersRegistered)
+    }
+
+    async fn ask(
+        &self,
+        adapter: Arc<dyn Adapter>,
+        query: Query,
+    ) -> Result<QueryResult, LogicError> {
+        let provider = adapter.provider();
+        let _permit = match self.limits.get(&provider) {
+            Some(limit) => Some(limit.acquire().await.expect("provider limits are never closed")),
+            None => None,
+        };
+        let prompt = self.prompts.assemble(provider, &query);
+        match adapter.chat_prompt(&prompt).await {
+            Ok(response) => {
+                self.registry.health().record_success(provider);
+                self.registry.latency().observe(&response);
+                let result = QueryResult::from_response(query.id, response);
+                Ok(QueryResult { metadata: query.metadata, ..result })
+            }
+            Err(e) => Err(self.failed(provider, e)),
+        }
+    }
+
+    /// Record `provider` failing with `e`, as the error to report.
+    ///
+    /// Only transient failures mark the provider unhealthy: a client or auth
+    /// error is about the request, and says nothing about the next one.
+    fn failed(&self, provider: Provider, e: AdapterError) -> LogicError {
+        // A rate limit says nothing about health; it just expires.
+        if let AdapterError::RateLimited { retry_after_ms } = e {
+            self.registry.rate_limits().record(provider, retry_after_ms);
+        } else if e.is_retryable() {
+            self.registry.health().record_error(provider, &e);
+        }
+        LogicError::ProviderUnavailable(format!("{provider}: {e}"))
+    }
+}
+
+impl CoreLogic for DefaultLogic {
+    async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+        self.in_flight
+            .run(query.id, async {
+                let adapter = self.registry.route(&query)?;
+                match self.deadline(&query, adapter.provider()) {
+                    Some(limit) => with_deadline(limit, self.ask(adapter, query)).await,
+                    None => self.ask(adapter, query).await,
+                }
+            })
+            .await
+    }
+
+    /// Prices the query at the rates of the provider it would be routed to.
+    fn estimate_cost(&self, query: &Query, table: &CostTable) -> f64 {
+        let provider = self.registry.route(query).ok().map(|a| a.provider());
+        table.worst_case(provider, count_message_tokens(&query_messages(query)))
+    }
+
+    fn available_providers(&self) -> Vec<ProviderInfo> {
+        self.registry.provider_info()
+    }
+
+    /// Query, provider and default timeouts all apply, by the precedence of
+    /// [`resolve_timeout`].
+    fn resolves_timeouts(&self) -> bool {
+        true
+    }
+
+    /// Streams from the routed adapter's
+    /// [`chat_prompt_stream`](crate::adapter::Adapter::chat_prompt_stream),
+    /// feeding the outcome back into the registry's health cache. The whole
+    /// stream must end within the query's deadline, as `query` must; provider
+    /// limits do not apply.
+    fn query_stream(&self, query: Query) -> QueryStream<'_> {
+        let (chunks, received) = futures::channel::mpsc::unbounded();
+        // The adapter's stream borrows the assembled prompt, so it is
+        // driven here and its chunks forwarded through the channel.
+        let pump = async move {
+            let adapter = match self.registry.route(&query) {
+                Ok(adapter) => adapter,
+                Err(e) => {
+                    let _ = chunks.unbounded_send(Err(e));
+                    return;
+                }
+            };
+            let provider = adapter.provider();
+            let prompt = self.prompts.assemble(provider, &query);
+            let stream = adapter
+                .chat_prompt_stream(&prompt)
+                .map(|chunk| chunk.map_err(|e| self.failed(provider, e)))
+                .boxed();
+            let mut stream = match self.deadline(&query, provider) {
+                Some(limit) => stream_with_deadline(limit, stream),
+                None => stream,
+            };
+            while let Some(chunk) = stream.next().await {
+                let failed = chunk.is_err();
+                if chunks.unbounded_send(chunk).is_err() || failed {
+                    return;
+                }
+            }
+            self.registry.health().record_success(provider);
+        };
+        let pump = futures::stream::once(pump).filter_map(|()| async { None });
+        Box::pin(futures::stream::select(received, pump))
+    }
+
+    async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+        queries
+            .into_iter()
+            .map(|q| self.query(q))
+            .collect::<futures::stream::FuturesUnordered<_>>()
+            .collect()
+            .await
+    }
+}
+
+/// The deadline for `query`, by precedence: the query's own
+/// [`timeout_ms`](Query::timeout_ms), else `provider`'s timeout (e.g. from
+/// its [`AdapterConfig`](crate::adapter::AdapterConfig)), else `global`.
+pub fn resolve_timeout(query: &Query, provider: Option<Duration>, global: Duration) -> Duration {
+    query.timeout_ms.map(Duration::from_millis).or(provider).unwrap_or(global)
+}
+
+/// Race `query` against `limit`, failing with [`LogicError::Timeout`].
+async fn with_deadline<F>(limit: Duration, query: F) -> Result<QueryResult, LogicError>
+where
+    F: std::future::Future<Output = Result<QueryResult, LogicError>>,
+{
+    let ms = u64::try_from(limit.as_millis()).unwrap_or(u64::MAX);
+    tokio::time::timeout(limit, query).await.unwrap_or(Err(LogicError::Timeout(ms)))
+}
+
+/// `stream`, cut off with [`LogicError::Timeout`] if it has not ended within
+/// `limit`.
+pub(crate) fn stream_with_deadline(limit: Duration, stream: QueryStream<'_>) -> QueryStream<'_> {
+    let ms = u64::try_from(limit.as_millis()).unwrap_or(u64::MAX);
+    let expiry = Box::pin(tokio::time::sleep(limit));
+    Box::pin(futures::stream::unfold(Some((stream, expiry)), move |state| async move {
+        let (mut stream, mut expiry) = state?;
+        tokio::select! {
+            chunk = stream.next() => {
+                let chunk = chunk?;
+                let next = chunk.is_ok().then_some((stream, expiry));
+                Some((chunk, next))
+            }
+            () = &mut expiry => Some((Err(LogicError::Timeout(ms)), None)),
+        }
+    }))
+}
+
+/// [`CoreLogic`] decorator that enforces query deadlines.
+///
+/// Each query is raced against its `timeout_ms`, or the default given here.
+/// On expiry the inner future is dropped — cancelling the in-flight provider
+/// request — and [`LogicError::Timeout`] is returned.
+///
+/// Logic that [resolves timeouts](CoreLogic::resolves_timeouts) per provider,
+/// like [`DefaultLogic`], owns the deadline instead: queries pass through
+/// untouched, so a provider timeout longer than the default is not cut
+/// short. Give such logic its default with
+/// [`DefaultLogic::with_default_timeout`].
+pub struct TimeoutLogic<L> {
+    inner: L,
+    default_timeout_ms: Option<u64>,
+}
+
+impl<L: CoreLogic> TimeoutLogic<L> {
+    /// Wrap `inner`, applying `default_timeout_ms` to queries without their own.
+    pub fn new(inner: L, default_timeout_ms: Option<u64>) -> Self {
+        Self { inner, default_timeout_ms }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// The deadline this layer applies to `query`, if it owns one.
+    fn limit(&self, query: &Query) -> Option<Duration> {
+        if self.inner.resolves_timeouts() {
+            return None;
+        }
+        query.timeout_ms.or(self.default_timeout_ms).map(Duration::from_millis)
+    }
+}
+
+impl<L: CoreLogic> CoreLogic for TimeoutLogic<L> {
+    async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+        match self.limit(&query) {
+            Some(limit) => with_deadline(limit, self.inner.query(query)).await,
+            None => self.inner.query(query).await,
+        }
+    }
+
+    /// Streams from the inner logic, cut off at the same deadline as `query`.
+    fn query_stream(&self, query: Query) -> QueryStream<'_> {
+        match self.limit(&query) {
+            Some(limit) => stream_with_deadline(limit, self.inner.query_stream(query)),
+            None => self.inner.query_stream(query),
+        }
+    }
+
+    fn available_providers(&self) -> Vec<ProviderInfo> {
+        self.inner.available_providers()
+    }
+
+    fn resolves_timeouts(&self) -> bool {
+        true
+    }
+
+    async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+        queries
+            .into_iter()
+            .map(|q| self.query(q))
+            .collect::<futures::stream::FuturesUnordered<_>>()
+            .collect()
+            .await
+    }
+}
+
+/// Logic decorator that answers repeated queries from a cache.
+///
+/// Successful results are stored in a [`MemorySystem`] under a key hashed
+/// from the query's content, system context and provider, as rewritten by
+/// the [key strategy](Self::with_key_strategy) ([`Exact`] by default), and
+/// are served until `ttl` has elapsed since they were written. A cached
+/// answer carries the new query's id. Errors are never cached, and a cache
+/// that cannot be read or written is bypassed rather than failing the query.
+pub struct CachingLogic<L, M> {
+    inner: L,
+    memory: Arc<M>,
+    ttl: Duration,
+    keys: Box<dyn CacheKeyStrategy>,
+}
+
+impl<L: CoreLogic, M: MemorySystem> CachingLogic<L, M> {
+    pub fn new(inner: L, memory: Arc<M>, ttl: Duration) -> Self {
+        Self { inner, memory, ttl, keys: Box::new(Exact) }
+    }
+
+    /// Decide which queries share a cache entry with `strategy`.
+    pub fn with_key_strategy(mut self, strategy: impl CacheKeyStrategy + 'static) -> Self {
+        self.keys = Box::new(strategy);
+        self
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// Memory key for the answer to `query`.
+    pub fn cache_key(&self, query: &Query) -> String {
+        format!("query:{:016x}", query_hash(self.keys.as_ref(), query, &[]))
+    }
+}
+
+/// Hash of the fields that decide `query`'s answer — content, system
+/// context, provider and tier — followed by `extra`, rewritten by `strategy`.
+fn query_hash(strategy: &dyn CacheKeyStrategy, query: &Query, extra: &[Option<&str>]) -> u64 {
+    let tier = query.tier.map(|t| format!("{t:?}"));
+    let mut parts = vec![
+        Some(query.content.as_str()),
+        query.system_context.as_deref(),
+        query.provider.as_deref(),
+        tier.as_deref(),
+    ];
+    parts.extend_from_slice(extra);
+    hash_parts(strategy, &parts)
+}
+
+impl<L: CoreLogic, M: MemorySystem> CoreLogic for CachingLogic<L, M> {
+    async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+        let key = self.cache_key(&query);
+        let cached = load_fresh(self.memory.as_ref(), &key, self.ttl).await.ok().flatten();
+        if let Some(hit) = cached.and_then(|v| serde_json::from_value::<QueryResult>(v).ok()) {
+            return Ok(QueryResult { query_id: query.id, metadata: query.metadata, ..hit });
+        }
+        let result = self.inner.query(query).await?;
+        if let Ok(value) = serde_json::to_value(&result) {
+            let _ = self.memory.store(&key, value).await;
+        }
+        Ok(result)
+    }
+
+    /// A cached answer arrives as one chunk; otherwise the inner logic's
+    /// stream is passed through, and not cached.
+    fn query_stream(&self, query: Query) -> QueryStream<'_> {
+        let stream = async move {
+            let key = self.cache_key(&query);
+            let cached = load_fresh(self.memory.as_ref(), &key, self.ttl).await.ok().flatten();
+            match cached.and_then(|v| serde_json::from_value::<QueryResult>(v).ok()) {
+                Some(hit) => futures::stream::once(async { Ok(hit.content) }).boxed(),
+                None => self.inner.query_stream(query),
+            }
+        };
+        Box::pin(futures::stream::once(stream).flatten())
+    }
+
+    fn available_providers(&self) -> Vec<ProviderInfo> {
+        self.inner.available_providers()
+    }
+
+    fn resolves_timeouts(&self) -> bool {
+        self.inner.resolves_timeouts()
+    }
+
+    async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+        queries
+            .into_iter()
+            .map(|q| self.query(q))
+            .collect::<futures::stream::FuturesUnordered<_>>()
+            .collect()
+            .await
+    }
+}
+
+/// The outcome of a [`SingleFlightLogic`] call, once it has one.
+type Flight = watch::Receiver<Option<Result<QueryResult, LogicError>>>;
+
+/// Logic decorator that coalesces identical concurrent queries.
+///
+/// While a query is in flight, queries with the same content, system
+/// context, provider, tier and timeout wait for it instead of calling the inner
+/// logic, and receive its result, or its error, under their own id. Unlike
+/// [`CachingLogic`] nothing outlives the call: the next identical query
+/// after it finishes calls the inner logic again.
+///
+/// If the caller running the shared call drops it, a waiting caller takes
+/// over.
+pub struct SingleFlightLogic<L> {
+    inner: L,
+    flights: Mutex<HashMap<u64, Flight>>,
+}
+
+impl<L: CoreLogic> SingleFlightLogic<L> {
+    pub fn new(inner: L) -> Self {
+        Self { inner, flights: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// Number of distinct queries in flight.
+    pub fn in_flight(&self) -> usize {
+        self.flights.lock().unwrap().len()
+    }
+
+    /// Queries with different timeouts fly apart, so none is held to
+    /// another's deadline.
+    fn flight_key(query: &Query) -> u64 {
+        let timeout = query.timeout_ms.map(|ms| ms.to_string());
+        query_hash(&Exact, query, &[timeout.as_deref()])
+    }
+}
+
+/// Removes a flight from its map when the call leading it ends or is dropped.
+struct Landing<'a> {
+    flights: &'a Mutex<HashMap<u64, Flight>>,
+    key: u64,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        self.flights.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl<L: CoreLogic> CoreLogic for SingleFlightLogic<L> {
+    async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+        let key = Self::flight_key(&query);
+        loop {
+            let (lead, mut flight) = {
+                let mut flights = self.flights.lock().unwrap();
+                match flights.get(&key) {
+                    Some(flight) => (None, flight.clone()),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        flights.insert(key, rx.clone());
+                        (Some(tx), rx)
+                    }
+                }
+            };
+            if let Some(tx) = lead {
+                let landing = Landing { flights: &self.flights, key };
+                let result = self.inner.query(query).await;
+                drop(landing);
+                tx.send_replace(Some(result.clone()));
+                return result;
+            }
+            // An error means the leader was dropped; try to lead instead.
+            let Ok(shared) = flight.wait_for(Option::is_some).await.map(|r| r.clone()) else {
+                continue;
+            };
+            let shared = shared.expect("waited for an outcome");
+            return shared
+                .map(|hit| QueryResult { query_id: query.id, metadata: query.metadata, ..hit });
+        }
+    }
+
+    /// Streams are not shared: each caller streams from the inner logic.
+    fn query_stream(&self, query: Query) -> QueryStream<'_> {
+        self.inner.query_stream(query)
+    }
+
+    fn available_providers(&self) -> Vec<ProviderInfo> {
+        self.inner.available_providers()
+    }
+
+    fn resolves_timeouts(&self) -> bool {
+        self.inner.resolves_timeouts()
+    }
+
+    async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+        queries
+            .into_iter()
+            .map(|q| self.query(q))
+            .collect::<futures::stream::FuturesUnordered<_>>()
+            .collect()
+            .await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    fn roundtrip(codec: &impl Codec) {
+        let query = Query::new("hello")
+            .with_system("Be brief.")
+            .with_provider_enum(Provider::Gemini)
+            .with_timeout_ms(250);
+        let back = Query::from_bytes(codec, &query.to_bytes(codec).unwrap()).unwrap();
+        assert_eq!(back.id, query.id);
+        assert_eq!(back.content, query.content);
+        assert_eq!(back.system_context, query.system_context);
+        assert_eq!(back.provider, query.provider);
+        assert_eq!(back.timeout_ms, query.timeout_ms);
+        assert_eq!(back.target_provider().unwrap(), Some(Provider::Gemini));
+
+        let result = QueryResult {
+            query_id: query.id,
+            provider_used: "gemini".into(),
+            content: "hi".into(),
+            latency_ms: 12,
+            network_ms: 9,
+            input_tokens: 3,
+            output_tokens: 1,
+            metadata: Default::default(),
+        };
+        let back = QueryResult::from_bytes(codec, &result.to_bytes(codec).unwrap()).unwrap();
+        assert_eq!(back.query_id, result.query_id);
+        assert_eq!(back.provider_used, result.provider_used);
+        assert_eq!(back.content, result.content);
+        assert_eq!((back.latency_ms, back.network_ms), (12, 9));
+        assert_eq!((back.input_tokens, back.output_tokens), (3, 1));
+
+        assert!(QueryResult::from_bytes(codec, b"\xc1 not an encoding").is_err());
+    }
+
+    #[test]
+    fn json_codec_roundtrip() {
+        roundtrip(&crate::codec::JsonCodec);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_codec_roundtrip() {
+        roundtrip(&crate::codec::MessagePackCodec);
+    }
 
     #[test]
     fn query_builder() {
@@ -91,4 +1219,492 @@ mod tests {
         assert_eq!(q.system_context.as_deref(), Some("You are helpful."));
         assert_eq!(q.provider.as_deref(), Some("claude"));
     }
+
+    #[test]
+    fn provider_pin_validates_and_survives_serde() {
+        let q = Query::new("hi").with_provider_enum(Provider::OpenWeight);
+        assert_eq!(q.provider.as_deref(), Some("openweight"));
+        let back: Query = serde_json::from_str(&serde_json::to_string(&q).unwrap()).unwrap();
+        assert_eq!(back.target_provider().unwrap(), Some(Provider::OpenWeight));
+
+        let bad = Query::new("hi").with_provider("cluade");
+        assert!(matches!(bad.target_provider(), Err(LogicError::UnknownProvider(_))));
+        assert_eq!(Query::new("hi").target_provider().unwrap(), None);
+    }
+
+    #[test]
+    fn estimate_cost_assumes_max_output() {
+        use crate::cost::Rates;
+        use crate::mock::MockAdapter;
+
+        let table = CostTable::new()
+            .with_rates(Provider::Claude, Rates::new(3.0, 15.0))
+            .with_rates(Provider::Gemini, Rates::new(1.0, 5.0))
+            .with_max_tokens(2000);
+        // 4 system + 6 prompt tokens.
+        let query = Query::new("summarize the attached report in French")
+            .with_system("You are a translator.")
+            .with_provider_enum(Provider::Gemini);
+        let expected = (10.0 * 1.0 + 2000.0 * 5.0) / 1e6;
+
+        let (sleepy, _) = sleepy(0);
+        assert_eq!(sleepy.estimate_cost(&query, &table), expected);
+
+        let mut registry = AdapterRegistry::new();
+        registry.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        registry.register(Arc::new(MockAdapter::new(Provider::Gemini)));
+        registry.latency().record(Provider::Gemini, 10);
+        let logic = DefaultLogic::new(registry);
+        assert_eq!(logic.estimate_cost(&query, &table), expected);
+        // Unpinned: the registry would route to Gemini, the fastest.
+        let unpinned = Query::new("summarize the attached report in French")
+            .with_system("You are a translator.");
+        assert_eq!(logic.estimate_cost(&unpinned, &table), expected);
+        // Without a registry to consult, assume the most expensive provider.
+        assert_eq!(sleepy.estimate_cost(&unpinned, &table), (10.0 * 3.0 + 2000.0 * 15.0) / 1e6);
+    }
+
+    /// Sleeps for the number of milliseconds given as the query content.
+    struct StaggeredLogic;
+
+    impl CoreLogic for StaggeredLogic {
+        async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+            let ms = query.content.parse().unwrap();
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(QueryResult {
+                query_id: query.id,
+                provider_used: "claude".into(),
+                content: query.content,
+                latency_ms: ms,
+                network_ms: ms,
+                input_tokens: 0,
+                output_tokens: 0,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+            self.query_batch_cancellable(queries, &CancellationToken::new()).await
+        }
+    }
+
+    fn staggered(n: u64) -> Vec<Query> {
+        (1..=n).map(|i| Query::new((i * 10).to_string())).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelling_batch_marks_unfinished_queries() {
+        let cancel = CancellationToken::new();
+        let trip = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(35)).await;
+            trip.cancel();
+        });
+
+        let started = tokio::time::Instant::now();
+        let results = StaggeredLogic.query_batch_cancellable(staggered(10), &cancel).await;
+        assert_eq!(started.elapsed(), Duration::from_millis(35));
+        assert_eq!(results.len(), 10);
+        for (i, result) in results.iter().enumerate() {
+            if i < 3 {
+                assert_eq!(result.as_ref().unwrap().content, ((i + 1) * 10).to_string());
+            } else {
+                assert!(matches!(result, Err(LogicError::Cancelled)), "entry {i}: {result:?}");
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limited_batch_stops_launching_after_cancel() {
+        let cancel = CancellationToken::new();
+        let trip = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(45)).await;
+            trip.cancel();
+        });
+
+        // Two at a time: 10 and 20 finish, then 30 (at 40 ms); 40 is in flight.
+        let results = StaggeredLogic.query_batch_limited(staggered(6), 2, &cancel).await;
+        let done = results.iter().filter(|r| r.is_ok()).count();
+        assert_eq!(done, 3);
+        assert!(results[3..].iter().all(|r| matches!(r, Err(LogicError::Cancelled))));
+
+        let uncancelled = StaggeredLogic.query_batch(staggered(4)).await;
+        assert!(uncancelled.iter().all(Result::is_ok));
+    }
+
+    /// Answers after 10 ms with the query's content, reporting in
+    /// `latency_ms` when it finished relative to `start`.
+    struct ClockLogic {
+        start: tokio::time::Instant,
+    }
+
+    impl CoreLogic for ClockLogic {
+        async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(QueryResult {
+                query_id: query.id,
+                provider_used: "claude".into(),
+                content: query.content,
+                latency_ms: self.start.elapsed().as_millis() as u64,
+                network_ms: 10,
+                input_tokens: 0,
+                output_tokens: 0,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+            self.query_batch_limited(queries, 2, &CancellationToken::new()).await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limited_batch_favours_priority_without_starving() {
+        let logic = ClockLogic { start: tokio::time::Instant::now() };
+        // Low-priority queries come first in the batch.
+        let queries: Vec<Query> = (0..8)
+            .map(|_| Query::new("low"))
+            .chain((0..8).map(|_| Query::new("high").with_priority(3)))
+            .collect();
+
+        let results: Vec<QueryResult> =
+            logic.query_batch(queries).await.into_iter().map(Result::unwrap).collect();
+        assert_eq!(results.len(), 16);
+        let mean = |content: &str| {
+            let times: Vec<u64> =
+                results.iter().filter(|r| r.content == content).map(|r| r.latency_ms).collect();
+            times.iter().sum::<u64>() as f64 / times.len() as f64
+        };
+        assert!(mean("high") < mean("low"), "high {} low {}", mean("high"), mean("low"));
+        // Low priority still runs alongside high, not only after it.
+        let first_low = results.iter().filter(|r| r.content == "low").map(|r| r.latency_ms).min();
+        let last_high = results.iter().filter(|r| r.content == "high").map(|r| r.latency_ms).max();
+        assert!(first_low < last_high);
+    }
+
+    #[test]
+    fn fair_queue_serves_in_proportion_to_priority() {
+        let mut queue = FairQueue::default();
+        for i in 0..4 {
+            queue.push(0, ("low", i));
+            queue.push(1, ("high", i));
+        }
+        let order: Vec<&str> = std::iter::from_fn(|| queue.pop()).map(|(name, _)| name).collect();
+        assert_eq!(order, ["high", "low", "high", "high", "low", "high", "low", "low"]);
+    }
+
+    /// Sleeps for a fixed time before answering; records whether it finished.
+    struct SleepyLogic {
+        delay_ms: u64,
+        finished: Arc<AtomicBool>,
+    }
+
+    impl CoreLogic for SleepyLogic {
+        async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+            tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            self.finished.store(true, Ordering::SeqCst);
+            Ok(QueryResult {
+                query_id: query.id,
+                provider_used: "claude".into(),
+                content: "late".into(),
+                latency_ms: self.delay_ms,
+                network_ms: self.delay_ms,
+                input_tokens: 0,
+                output_tokens: 0,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+            let mut out = Vec::new();
+            for q in queries {
+                out.push(self.query(q).await);
+            }
+            out
+        }
+    }
+
+    fn sleepy(delay_ms: u64) -> (SleepyLogic, Arc<AtomicBool>) {
+        let finished = Arc::new(AtomicBool::new(false));
+        (SleepyLogic { delay_ms, finished: finished.clone() }, finished)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn batch_progress_reports_each_completion() {
+        let (logic, _) = sleepy(10);
+        let seen = std::sync::Mutex::new(Vec::new());
+        let queries: Vec<Query> = (0..5).map(|i| Query::new(format!("q{i}"))).collect();
+
+        let results = logic
+            .query_batch_with_progress(queries, |done, result| {
+                assert!(result.is_ok());
+                seen.lock().unwrap().push(done);
+            })
+            .await;
+
+        assert_eq!(results.len(), 5);
+        let mut seen = seen.into_inner().unwrap();
+        assert_eq!(seen.len(), 5);
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn batch_progress_accepts_boxed_callback() {
+        let (logic, _) = sleepy(1);
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = count.clone();
+        let callback: ProgressCallback = Box::new(move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        logic
+            .query_batch_with_progress(vec![Query::new("a"), Query::new("b")], callback)
+            .await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn query_times_out_and_cancels() {
+        let (inner, finished) = sleepy(1_000);
+        let logic = TimeoutLogic::new(inner, None);
+
+        let err = logic.query(Query::new("slow").with_timeout_ms(50)).await.unwrap_err();
+        assert!(matches!(err, LogicError::Timeout(50)));
+
+        // The inner request was dropped, so it never completes.
+        tokio::time::sleep(Duration::from_millis(2_000)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn timeout_precedence_is_query_then_provider_then_global() {
+        let (provider, global) = (Some(Duration::from_secs(5)), Duration::from_secs(60));
+        let pinned = Query::new("q").with_timeout_ms(250);
+        assert_eq!(resolve_timeout(&pinned, provider, global), Duration::from_millis(250));
+        assert_eq!(resolve_timeout(&pinned, None, global), Duration::from_millis(250));
+        let open = Query::new("q");
+        assert_eq!(resolve_timeout(&open, provider, global), Duration::from_secs(5));
+        assert_eq!(resolve_timeout(&open, None, global), global);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn default_logic_applies_provider_timeout_over_its_default() {
+        use crate::mock::MockAdapter;
+
+        let slow_logic = || {
+            let mock = MockAdapter::new(Provider::Claude).with_delay(Duration::from_millis(100));
+            let mut registry = AdapterRegistry::new();
+            registry.register(Arc::new(mock));
+            DefaultLogic::new(registry)
+        };
+
+        let strict = slow_logic()
+            .with_default_timeout(Duration::from_millis(500))
+            .with_provider_timeout(Provider::Claude, Duration::from_millis(50));
+        let err = strict.query(Query::new("slow")).await.unwrap_err();
+        assert!(matches!(err, LogicError::Timeout(50)), "{err}");
+        strict.query(Query::new("patient").with_timeout_ms(200)).await.unwrap();
+
+        let lenient = slow_logic()
+            .with_default_timeout(Duration::from_millis(50))
+            .with_provider_timeout(Provider::Claude, Duration::from_millis(500));
+        lenient.query(Query::new("slow but allowed")).await.unwrap();
+
+        let global = slow_logic().with_default_timeout(Duration::from_millis(50));
+        assert!(matches!(global.query(Query::new("slow")).await, Err(LogicError::Timeout(50))));
+
+        // An outer TimeoutLogic leaves the deadline to the routing logic, so
+        // its default does not cap a longer provider timeout.
+        let lenient = slow_logic().with_provider_timeout(Provider::Claude, Duration::from_secs(1));
+        let memory = Arc::new(crate::memory::InMemoryStore::new());
+        let cached = CachingLogic::new(lenient, memory, Duration::ZERO);
+        let wrapped = TimeoutLogic::new(cached, Some(50));
+        wrapped.query(Query::new("slow but allowed")).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn default_timeout_applies_and_query_overrides() {
+        let (inner, _) = sleepy(100);
+        let logic = TimeoutLogic::new(inner, Some(20));
+
+        let err = logic.query(Query::new("slow")).await.unwrap_err();
+        assert!(matches!(err, LogicError::Timeout(20)));
+        let ok = logic.query(Query::new("patient").with_timeout_ms(500)).await.unwrap();
+        assert_eq!(ok.content, "late");
+    }
+
+    #[tokio::test]
+    async fn key_strategy_decides_whitespace_hits() {
+        use crate::cache::Normalized;
+        use crate::memory::InMemoryStore;
+        use crate::mock::MockAdapter;
+
+        let caching = |mock: &Arc<MockAdapter>| {
+            let mut registry = AdapterRegistry::new();
+            registry.register(mock.clone());
+            let memory = Arc::new(InMemoryStore::new());
+            CachingLogic::new(DefaultLogic::new(registry), memory, Duration::from_secs(60))
+        };
+        let tidy = || Query::new("What is a braid?").with_system("Be brief.");
+        let messy = || Query::new("  What is\ta   braid?\n").with_system(" Be  brief. ");
+
+        let mock = Arc::new(MockAdapter::new(Provider::Claude));
+        let exact = caching(&mock);
+        assert_ne!(exact.cache_key(&tidy()), exact.cache_key(&messy()));
+        exact.query(tidy()).await.unwrap();
+        exact.query(messy()).await.unwrap();
+        assert_eq!(mock.calls(), 2);
+
+        let mock = Arc::new(MockAdapter::new(Provider::Claude));
+        let normalized = caching(&mock).with_key_strategy(Normalized);
+        assert_eq!(normalized.cache_key(&tidy()), normalized.cache_key(&messy()));
+        let first = normalized.query(tidy()).await.unwrap();
+        let again = messy();
+        let hit = normalized.query(again.clone()).await.unwrap();
+        assert_eq!(mock.calls(), 1);
+        assert_eq!((hit.content, hit.query_id), (first.content, again.id));
+
+        // Answers from different tiers are cached apart.
+        let fast = tidy().with_tier(ModelTier::Fast);
+        assert_ne!(normalized.cache_key(&tidy()), normalized.cache_key(&fast));
+        normalized.query(fast.clone()).await.unwrap();
+        normalized.query(fast).await.unwrap();
+        assert_eq!(mock.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn decorators_pass_streams_through() {
+        use crate::memory::InMemoryStore;
+        use crate::mock::MockAdapter;
+
+        let logic = |delay| {
+            let mut registry = AdapterRegistry::new();
+            let mock = MockAdapter::new(Provider::Claude).with_reply("a streamed answer");
+            registry.register(Arc::new(mock.with_chunk_size(3).with_delay(delay)));
+            DefaultLogic::new(registry)
+        };
+        async fn chunks(stream: QueryStream<'_>) -> Vec<Result<String, LogicError>> {
+            stream.collect().await
+        }
+        let quick = Duration::from_millis(10);
+
+        let timeout = TimeoutLogic::new(logic(quick), Some(1_000));
+        assert_eq!(chunks(timeout.query_stream(Query::new("hi"))).await.len(), 6);
+        let single_flight = SingleFlightLogic::new(logic(quick));
+        assert_eq!(chunks(single_flight.query_stream(Query::new("hi"))).await.len(), 6);
+
+        let memory = Arc::new(InMemoryStore::new());
+        let caching = CachingLogic::new(logic(quick), memory, Duration::from_secs(60));
+        assert_eq!(chunks(caching.query_stream(Query::new("hi"))).await.len(), 6);
+        caching.query(Query::new("hi")).await.unwrap();
+        let hit = chunks(caching.query_stream(Query::new("hi"))).await;
+        assert_eq!(hit.into_iter().map(Result::unwrap).collect::<Vec<_>>(), ["a streamed answer"]);
+
+        // The deadline bounds the stream too, whichever layer owns it.
+        let slow = TimeoutLogic::new(sleepy(5_000).0, Some(1_000));
+        let streamed = chunks(slow.query_stream(Query::new("hi"))).await;
+        assert!(matches!(streamed[..], [Err(LogicError::Timeout(1_000))]));
+        let slow = logic(Duration::from_secs(5))
+            .with_provider_timeout(Provider::Claude, Duration::from_millis(1_000));
+        let streamed = chunks(TimeoutLogic::new(slow, None).query_stream(Query::new("hi"))).await;
+        assert!(matches!(streamed[..], [Err(LogicError::Timeout(1_000))]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_flight_shares_one_call_between_identical_queries() {
+        use crate::mock::MockAdapter;
+
+        let mock =
+            Arc::new(MockAdapter::new(Provider::Claude).with_delay(Duration::from_millis(50)));
+        let mut registry = AdapterRegistry::new();
+        registry.register(mock.clone());
+        let logic = SingleFlightLogic::new(DefaultLogic::new(registry));
+
+        let queries: Vec<Query> = (0..50).map(|_| Query::new("same question")).collect();
+        let ids: Vec<uuid::Uuid> = queries.iter().map(|q| q.id).collect();
+        let results = futures::future::join_all(queries.into_iter().map(|q| logic.query(q))).await;
+        assert_eq!(mock.calls(), 1);
+        for (result, id) in results.into_iter().zip(ids) {
+            let result = result.unwrap();
+            assert_eq!((result.content.as_str(), result.query_id), ("mock response", id));
+        }
+        assert_eq!(logic.in_flight(), 0);
+
+        // Nothing is kept once the call lands.
+        logic.query(Query::new("same question")).await.unwrap();
+        assert_eq!(mock.calls(), 2);
+
+        // A different timeout does not join the flight.
+        let (a, b) = tokio::join!(
+            logic.query(Query::new("same question").with_timeout_ms(1_000)),
+            logic.query(Query::new("same question").with_timeout_ms(5_000)),
+        );
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(mock.calls(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_flight_waiter_takes_over_from_a_dropped_leader() {
+        use crate::mock::MockAdapter;
+
+        let mock =
+            Arc::new(MockAdapter::new(Provider::Claude).with_delay(Duration::from_millis(50)));
+        let mut registry = AdapterRegistry::new();
+        registry.register(mock.clone());
+        let logic = SingleFlightLogic::new(DefaultLogic::new(registry));
+
+        let leader = tokio::time::timeout(Duration::from_millis(10), logic.query(Query::new("q")));
+        let (led, waited) = tokio::join!(leader, logic.query(Query::new("q")));
+        assert!(led.is_err());
+        assert_eq!(waited.unwrap().content, "mock response");
+        assert_eq!(mock.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn batch_summary_breaks_down_errors() {
+        use crate::mock::MockAdapter;
+
+        let mut registry = AdapterRegistry::new();
+        registry.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        registry.register(Arc::new(MockAdapter::new(Provider::Grok).rate_limited(1_000)));
+        registry.register(Arc::new(MockAdapter::new(Provider::Gemini).failing("refused")));
+        let logic = DefaultLogic::new(registry);
+        let queries = vec![
+            Query::new("a").with_provider_enum(Provider::Claude),
+            Query::new("b").with_provider_enum(Provider::Claude),
+            Query::new("c").with_provider_enum(Provider::Grok),
+            Query::new("d").with_provider_enum(Provider::Gemini),
+            Query::new("e").with_provider("cluade"),
+        ];
+        let mut results = logic.query_batch(queries).await;
+        results.push(Err(LogicError::Timeout(50)));
+
+        let summary = BatchSummary::from_results(&results);
+        assert_eq!((summary.total, summary.succeeded, summary.failed), (6, 2, 4));
+        assert_eq!(
+            summary.by_error,
+            HashMap::from([
+                ("provider_unavailable".to_owned(), 2),
+                ("unknown_provider".to_owned(), 1),
+                ("timeout".to_owned(), 1),
+            ])
+        );
+        assert_eq!(summary.dominant_error(), Some("provider_unavailable"));
+        assert_eq!(BatchSummary::from_results(&[]).dominant_error(), None);
+    }
+
+    #[test]
+    fn no_healthy_providers_lists_reasons() {
+        let err = LogicError::NoHealthyProviders(vec![
+            ProviderFailure { provider: Provider::Claude, reason: "auth failed".into() },
+            ProviderFailure { provider: Provider::Grok, reason: "timeout".into() },
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "no healthy providers: claude (auth failed), grok (timeout)"
+        );
+    }
 }
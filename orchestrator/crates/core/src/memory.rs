@@ -3,7 +3,15 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+#[cfg(feature = "msgpack")]
+use crate::codec::MessagePackCodec;
+use crate::codec::{Codec, CodecError, JsonCodec};
+use crate::metrics::Metrics;
+use crate::text::to_hex;
 
 /// Errors produced by [`MemorySystem`] operations.
 #[derive(Debug, Error)]
@@ -14,6 +22,11 @@ pub enum MemoryError {
     Serialization(String),
     #[error("storage backend error: {0}")]
     Backend(String),
+    #[error("version conflict on {key}: expected v{expected}, found v{actual}")]
+    VersionConflict { key: String, expected: u64, actual: u64 },
+    /// Not written because item `{0}` of an atomic batch failed.
+    #[error("batch aborted: item {0} failed")]
+    BatchAborted(usize),
 }
 
 /// A single versioned record held in the memory system.
@@ -25,6 +38,117 @@ pub struct Record {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl Record {
+    /// Size of the record serialized as JSON, a rough measure of the memory
+    /// it occupies.
+    pub fn approximate_bytes(&self) -> usize {
+        serde_json::to_vec(self).map_or(0, |bytes| bytes.len())
+    }
+
+    /// An RFC 6902 JSON Patch that turns `old` into `new`.
+    ///
+    /// Objects and arrays are compared member by member, so the patch names
+    /// only what changed. Array elements are matched by index: extra trailing
+    /// elements are added or removed, the rest are diffed in place.
+    pub fn diff(old: &serde_json::Value, new: &serde_json::Value) -> serde_json::Value {
+        let mut ops = Vec::new();
+        diff_into(String::new(), old, new, &mut ops);
+        serde_json::Value::Array(ops)
+    }
+}
+
+fn diff_into(
+    path: String,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    ops: &mut Vec<serde_json::Value>,
+) {
+    use serde_json::{Value, json};
+
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, value) in old {
+                let path = format!("{path}/{}", escape_pointer(key));
+                match new.get(key) {
+                    Some(next) => diff_into(path, value, next, ops),
+                    None => ops.push(json!({ "op": "remove", "path": path })),
+                }
+            }
+            for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                let path = format!("{path}/{}", escape_pointer(key));
+                ops.push(json!({ "op": "add", "path": path, "value": value }));
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (i, (value, next)) in old.iter().zip(new).enumerate() {
+                diff_into(format!("{path}/{i}"), value, next, ops);
+            }
+            for (i, value) in new.iter().enumerate().skip(old.len()) {
+                ops.push(json!({ "op": "add", "path": format!("{path}/{i}"), "value": value }));
+            }
+            // Highest index first, so earlier removals don't shift later ones.
+            for i in (new.len()..old.len()).rev() {
+                ops.push(json!({ "op": "remove", "path": format!("{path}/{i}") }));
+            }
+        }
+        _ => ops.push(json!({ "op": "replace", "path": path, "value": new })),
+    }
+}
+
+/// Escape `key` as a JSON Pointer reference token (RFC 6901).
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// On-disk encoding of [`Record`]s for persistent backends.
+///
+/// JSON is the default and the interop format. MessagePack (feature
+/// `msgpack`) is more compact for large payloads. A store must be reopened
+/// with the format it was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SerializationFormat {
+    #[default]
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+impl SerializationFormat {
+    pub fn encode(self, record: &Record) -> Result<Vec<u8>, MemoryError> {
+        match self {
+            Self::Json => JsonCodec.encode(record).map_err(serialization),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => MessagePackCodec.encode(record).map_err(serialization),
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Result<Record, MemoryError> {
+        match self {
+            Self::Json => JsonCodec.decode(bytes).map_err(serialization),
+            #[cfg(feature = "msgpack")]
+            Self::MessagePack => MessagePackCodec.decode(bytes).map_err(serialization),
+        }
+    }
+}
+
+/// When a durable store writes changes to its backing storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushMode {
+    /// Persist each write as it happens.
+    #[default]
+    PerWrite,
+    /// Hold writes until [`MemorySystem::flush`], so a burst of writes costs
+    /// one persist instead of one each.
+    Explicit,
+}
+
+fn serialization(CodecError(e): CodecError) -> MemoryError {
+    MemoryError::Serialization(e)
+}
+
 /// State persistence and consistency interface.
 ///
 /// Implementations back the entire task lifecycle — every phase reads from
@@ -43,8 +167,317 @@ pub trait MemorySystem: Send + Sync {
     /// Remove `key` from the store.
     fn remove(&self, key: &str) -> impl std::future::Future<Output = Result<(), MemoryError>> + Send;
 
+    /// Like [`store`](Self::store), but a no-op when `value` equals the
+    /// current record's value as JSON, whatever its object member order:
+    /// nothing is written and the current version is returned.
+    ///
+    /// The default loads then stores, so it can race with concurrent
+    /// writers; backends should override it atomically.
+    fn store_dedup(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+    ) -> impl std::future::Future<Output = Result<u64, MemoryError>> + Send {
+        async move {
+            match self.load(key).await {
+                Ok(current) if current.value == value => Ok(current.version),
+                Ok(_) | Err(MemoryError::NotFound(_)) => self.store(key, value).await,
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    /// Store every `(key, value)` pair, returning one result per item in
+    /// order, so a caller can tell exactly which items were written.
+    ///
+    /// The default stores items one at a time and keeps going past a
+    /// failure; durable backends use it as is. Backends that can apply a
+    /// batch atomically override it: then either every item succeeds, or
+    /// nothing is written, the first failing item reports its own error and
+    /// every other item reports [`MemoryError::BatchAborted`].
+    fn store_batch(
+        &self,
+        items: Vec<(String, serde_json::Value)>,
+    ) -> impl std::future::Future<Output = Vec<Result<u64, MemoryError>>> + Send {
+        async move {
+            let mut results = Vec::with_capacity(items.len());
+            for (key, value) in items {
+                results.push(self.store(&key, value).await);
+            }
+            results
+        }
+    }
+
+    /// Store `value` under `key` only if its current version is
+    /// `expected_version`, where `0` means the key must not exist yet.
+    ///
+    /// Returns the new version, or fails with [`MemoryError::VersionConflict`]
+    /// (an absent key reporting version `0`). Like
+    /// [`remove_if_version`](Self::remove_if_version), the default is not
+    /// atomic and backends should override it.
+    fn store_if_version(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        expected_version: u64,
+    ) -> impl std::future::Future<Output = Result<u64, MemoryError>> + Send {
+        async move {
+            let actual = match self.load(key).await {
+                Ok(record) => record.version,
+                Err(MemoryError::NotFound(_)) => 0,
+                Err(e) => return Err(e),
+            };
+            check_version(key, actual, expected_version)?;
+            self.store(key, value).await
+        }
+    }
+
+    /// Remove `key` only if its current version is `expected_version`.
+    ///
+    /// Fails with [`MemoryError::VersionConflict`] if the key has been
+    /// written since that version, and [`MemoryError::NotFound`] if it is
+    /// absent. The default implementation loads then removes, so it can race
+    /// with concurrent writers; backends should override it atomically.
+    fn remove_if_version(
+        &self,
+        key: &str,
+        expected_version: u64,
+    ) -> impl std::future::Future<Output = Result<(), MemoryError>> + Send {
+        async move {
+            check_version(key, self.load(key).await?.version, expected_version)?;
+            self.remove(key).await
+        }
+    }
+
     /// List all keys currently held by the system.
     fn keys(&self) -> impl std::future::Future<Output = Result<Vec<String>, MemoryError>> + Send;
+
+    /// Persist any buffered writes to durable storage.
+    ///
+    /// A no-op for volatile stores and for durable stores that write through.
+    fn flush(&self) -> impl std::future::Future<Output = Result<(), MemoryError>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Retrieve `key` as it was at `version`.
+    ///
+    /// The default only knows the latest version; backends that keep history
+    /// override it. Missing versions fail with [`MemoryError::NotFound`].
+    #[cfg(feature = "version-history")]
+    fn load_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> impl std::future::Future<Output = Result<Record, MemoryError>> + Send {
+        async move {
+            match self.load(key).await? {
+                record if record.version == version => Ok(record),
+                _ => Err(MemoryError::NotFound(format!("{key} v{version}"))),
+            }
+        }
+    }
+
+    /// The [`Record::diff`] patch from version `from` of `key` to version `to`.
+    #[cfg(feature = "version-history")]
+    fn version_diff(
+        &self,
+        key: &str,
+        from: u64,
+        to: u64,
+    ) -> impl std::future::Future<Output = Result<serde_json::Value, MemoryError>> + Send {
+        async move {
+            let old = self.load_version(key, from).await?;
+            let new = self.load_version(key, to).await?;
+            Ok(Record::diff(&old.value, &new.value))
+        }
+    }
+
+    /// List up to `limit` keys in sorted order, starting after `cursor`.
+    ///
+    /// Returns the page and the cursor for the next one (the last key in the
+    /// page), or `None` once the keys are exhausted. A `limit` of zero yields
+    /// an empty page and no cursor.
+    ///
+    /// The default implementation sorts the full [`keys`](Self::keys) list;
+    /// ordered backends should override it with a range scan.
+    fn keys_paginated(
+        &self,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> impl std::future::Future<Output = Result<(Vec<String>, Option<String>), MemoryError>> + Send
+    {
+        async move {
+            let mut keys = self.keys().await?;
+            keys.sort_unstable();
+            let start = cursor.map_or(0, |c| keys.partition_point(|k| *k <= c));
+            Ok(paginate(keys.into_iter().skip(start), limit))
+        }
+    }
+
+    /// Store a large byte payload as `key:chunk:G:N` records of at most
+    /// `chunk_size` bytes each, plus a manifest under `key`, so backends with
+    /// a per-value size limit can hold it. Returns the manifest's version.
+    ///
+    /// `G` names the payload's generation, derived from its digest and chunk
+    /// size, so a new payload never overwrites the chunks the current
+    /// manifest points at. Once every chunk is written the manifest is
+    /// swapped to the new generation, then the previous generation's chunks
+    /// are removed. A reader that loaded the old manifest just before that
+    /// removal fails with [`MemoryError::NotFound`] rather than reading a
+    /// mix of both payloads. Chunks are written with
+    /// [`store_dedup`](Self::store_dedup), so retrying an interrupted store
+    /// does not rewrite the chunks that already made it.
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero.
+    fn store_chunked(
+        &self,
+        key: &str,
+        data: &[u8],
+        chunk_size: usize,
+    ) -> impl std::future::Future<Output = Result<u64, MemoryError>> + Send {
+        assert!(chunk_size > 0, "chunk_size must be positive");
+        async move {
+            let previous = match self.load(key).await {
+                Ok(record) => serde_json::from_value::<ChunkManifest>(record.value).ok(),
+                Err(MemoryError::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            };
+            let sha256 = to_hex(&Sha256::digest(data));
+            let manifest = ChunkManifest {
+                chunks: data.len().div_ceil(chunk_size),
+                bytes: data.len(),
+                generation: format!("{}-{chunk_size}", &sha256[..16]),
+                sha256,
+            };
+            for (n, chunk) in data.chunks(chunk_size).enumerate() {
+                let encoded = serde_json::Value::String(base64_encode(chunk));
+                self.store_dedup(&chunk_key(key, &manifest.generation, n), encoded).await?;
+            }
+            let value = serde_json::to_value(&manifest)
+                .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+            let version = self.store(key, value).await?;
+            if let Some(old) = previous.filter(|old| old.generation != manifest.generation) {
+                for n in 0..old.chunks {
+                    match self.remove(&chunk_key(key, &old.generation, n)).await {
+                        Ok(()) | Err(MemoryError::NotFound(_)) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            Ok(version)
+        }
+    }
+
+    /// Reassemble a payload written by [`store_chunked`](Self::store_chunked).
+    ///
+    /// Fails with [`MemoryError::Serialization`] if `key` holds no manifest
+    /// or the chunks do not add up to the payload it describes.
+    fn load_chunked(
+        &self,
+        key: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>, MemoryError>> + Send {
+        async move {
+            let manifest: ChunkManifest = serde_json::from_value(self.load(key).await?.value)
+                .map_err(|e| MemoryError::Serialization(format!("{key} has no manifest: {e}")))?;
+            let mut data = Vec::with_capacity(manifest.bytes);
+            let mut digest = Sha256::new();
+            for n in 0..manifest.chunks {
+                let chunk_key = chunk_key(key, &manifest.generation, n);
+                let record = self.load(&chunk_key).await?;
+                let chunk = record.value.as_str().and_then(base64_decode).ok_or_else(|| {
+                    MemoryError::Serialization(format!("{chunk_key} is not a chunk"))
+                })?;
+                digest.update(&chunk);
+                data.extend_from_slice(&chunk);
+            }
+            if data.len() != manifest.bytes || to_hex(&digest.finalize()) != manifest.sha256 {
+                return Err(MemoryError::Serialization(format!(
+                    "chunks of {key} do not match its manifest"
+                )));
+            }
+            Ok(data)
+        }
+    }
+}
+
+/// Fail with [`MemoryError::VersionConflict`] unless `actual == expected`.
+pub(crate) fn check_version(key: &str, actual: u64, expected: u64) -> Result<(), MemoryError> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(MemoryError::VersionConflict { key: key.to_owned(), expected, actual })
+    }
+}
+
+/// Take one page of at most `limit` keys from a sorted iterator that starts
+/// after the cursor, peeking one further key to decide whether more remain.
+pub(crate) fn paginate(
+    mut sorted: impl Iterator<Item = String>,
+    limit: usize,
+) -> (Vec<String>, Option<String>) {
+    let page: Vec<String> = sorted.by_ref().take(limit).collect();
+    let next = match (page.last(), sorted.next()) {
+        (Some(last), Some(_)) => Some(last.clone()),
+        _ => None,
+    };
+    (page, next)
+}
+
+/// The record [`MemorySystem::store_chunked`] writes under the payload's key.
+#[derive(Serialize, Deserialize)]
+struct ChunkManifest {
+    chunks: usize,
+    bytes: usize,
+    sha256: String,
+    /// Names the chunk records of this payload.
+    generation: String,
+}
+
+fn chunk_key(key: &str, generation: &str, n: usize) -> String {
+    format!("{key}:chunk:{generation}:{n}")
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard, padded base64, so chunks travel as JSON strings.
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let b = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for group in text.chunks(4) {
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &group[..4 - padding] {
+            let digit = BASE64.iter().position(|&d| d == c)?;
+            n = n << 6 | digit as u32;
+        }
+        n <<= 6 * padding;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
 }
 
 // ---------------------------------------------------------------------------
@@ -54,30 +487,208 @@ pub trait MemorySystem: Send + Sync {
 /// Simple in-memory implementation of [`MemorySystem`] backed by a `HashMap`.
 ///
 /// Useful for tests and single-session runs where persistence is not required.
+///
+/// With the `version-history` feature every version written is kept, until
+/// its key is removed, and can be read back with
+/// [`load_version`](MemorySystem::load_version).
+///
+/// Writes can be followed as they happen with [`watch`](Self::watch).
 #[derive(Debug, Default)]
 pub struct InMemoryStore {
     inner: tokio::sync::RwLock<HashMap<String, Record>>,
+    /// Key prefix and channel of every live [`watch`](Self::watch).
+    watchers: std::sync::Mutex<Vec<(String, UnboundedSender<Record>)>>,
+    #[cfg(feature = "version-history")]
+    history: std::sync::Mutex<HashMap<String, Vec<Record>>>,
 }
 
 impl InMemoryStore {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Insert `record` verbatim, e.g. when restoring from a backup.
+    ///
+    /// The record's version must be strictly greater than the version
+    /// currently stored under its key; otherwise the restore is rejected so
+    /// state can never silently regress.
+    pub async fn restore(&self, record: Record) -> Result<(), MemoryError> {
+        let mut map = self.inner.write().await;
+        ensure_monotonic(map.get(&record.key), record.version)?;
+        #[cfg(feature = "version-history")]
+        self.remember(&record);
+        self.notify(&record);
+        map.insert(record.key.clone(), record);
+        Ok(())
+    }
+
+    /// Add `record` to its key's history. Callers hold the write lock, so
+    /// versions are appended in order.
+    #[cfg(feature = "version-history")]
+    fn remember(&self, record: &Record) {
+        let mut history = self.history.lock().unwrap();
+        history.entry(record.key.clone()).or_default().push(record.clone());
+    }
+
+    #[cfg(feature = "version-history")]
+    fn forget(&self, key: &str) {
+        self.history.lock().unwrap().remove(key);
+    }
+
+    /// Receive every record written under a key starting with `prefix`, in
+    /// write order, from now on. An empty prefix watches every key; removals
+    /// are not reported. Dropping the receiver ends the watch.
+    pub fn watch(&self, prefix: impl Into<String>) -> UnboundedReceiver<Record> {
+        let (tx, rx) = unbounded_channel();
+        self.watchers.lock().unwrap().push((prefix.into(), tx));
+        rx
+    }
+
+    /// Send `record` to the watchers of its key. Callers hold the write
+    /// lock, so watchers see versions in order.
+    fn notify(&self, record: &Record) {
+        let mut watchers = self.watchers.lock().unwrap();
+        watchers.retain(|(prefix, tx)| {
+            !record.key.starts_with(prefix.as_str()) || tx.send(record.clone()).is_ok()
+        });
+    }
+
+    /// Number of records held.
+    pub async fn len(&self) -> usize {
+        self.inner.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.inner.read().await.is_empty()
+    }
+
+    /// Total [`Record::approximate_bytes`] of every record held.
+    pub async fn approximate_bytes(&self) -> usize {
+        self.inner.read().await.values().map(Record::approximate_bytes).sum()
+    }
+}
+
+impl Metrics for InMemoryStore {
+    fn subsystem(&self) -> &'static str {
+        "memory"
+    }
+
+    /// Record count and total [`Record::approximate_bytes`], or `busy` while
+    /// a writer holds the store.
+    fn snapshot(&self) -> serde_json::Value {
+        match self.inner.try_read() {
+            Ok(map) => serde_json::json!({
+                "records": map.len(),
+                "bytes": map.values().map(Record::approximate_bytes).sum::<usize>(),
+            }),
+            Err(_) => serde_json::json!({ "busy": true }),
+        }
+    }
+}
+
+/// Reject writes whose version does not advance past the existing record.
+fn ensure_monotonic(existing: Option<&Record>, next: u64) -> Result<(), MemoryError> {
+    match existing {
+        Some(current) if next <= current.version => {
+            Err(MemoryError::Backend("version regression".into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Write `value` under `key` at the next version, returning that version.
+pub(crate) fn put(
+    map: &mut HashMap<String, Record>,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<u64, MemoryError> {
+    let existing = map.get(key);
+    // A wrapped version shows up as a regression rather than a silent reset.
+    let version = existing.map_or(1, |r| r.version.wrapping_add(1));
+    ensure_monotonic(existing, version)?;
+    map.insert(
+        key.to_owned(),
+        Record {
+            key: key.to_owned(),
+            value,
+            version,
+            updated_at: chrono::Utc::now(),
+        },
+    );
+    Ok(version)
 }
 
 impl MemorySystem for InMemoryStore {
     async fn store(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
         let mut map = self.inner.write().await;
-        let version = map.get(key).map_or(1, |r| r.version + 1);
-        map.insert(
-            key.to_owned(),
-            Record {
-                key: key.to_owned(),
-                value,
-                version,
-                updated_at: chrono::Utc::now(),
-            },
-        );
+        let version = put(&mut map, key, value)?;
+        #[cfg(feature = "version-history")]
+        self.remember(&map[key]);
+        self.notify(&map[key]);
+        Ok(version)
+    }
+
+    async fn store_dedup(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
+        let mut map = self.inner.write().await;
+        if let Some(current) = map.get(key)
+            && current.value == value
+        {
+            return Ok(current.version);
+        }
+        let version = put(&mut map, key, value)?;
+        #[cfg(feature = "version-history")]
+        self.remember(&map[key]);
+        self.notify(&map[key]);
+        Ok(version)
+    }
+
+    /// All or nothing: every item is checked under the write lock before
+    /// any is written.
+    async fn store_batch(
+        &self,
+        items: Vec<(String, serde_json::Value)>,
+    ) -> Vec<Result<u64, MemoryError>> {
+        let mut map = self.inner.write().await;
+        // Versions the batch gives each key so far, for repeated keys.
+        let mut staged: HashMap<&str, u64> = HashMap::new();
+        for (i, (key, _)) in items.iter().enumerate() {
+            let current = staged.get(key.as_str()).copied().or(map.get(key).map(|r| r.version));
+            let version = current.map_or(1, |v| v.wrapping_add(1));
+            if let Some(current) = current
+                && version <= current
+            {
+                let mut results: Vec<_> =
+                    items.iter().map(|_| Err(MemoryError::BatchAborted(i))).collect();
+                // The same error a lone `store` of this item would give.
+                results[i] = Err(MemoryError::Backend("version regression".into()));
+                return results;
+            }
+            staged.insert(key, version);
+        }
+        items
+            .iter()
+            .map(|(key, value)| {
+                let version = put(&mut map, key, value.clone())?;
+                #[cfg(feature = "version-history")]
+                self.remember(&map[key.as_str()]);
+                self.notify(&map[key.as_str()]);
+                Ok(version)
+            })
+            .collect()
+    }
+
+    async fn store_if_version(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        expected_version: u64,
+    ) -> Result<u64, MemoryError> {
+        let mut map = self.inner.write().await;
+        check_version(key, map.get(key).map_or(0, |r| r.version), expected_version)?;
+        let version = put(&mut map, key, value)?;
+        #[cfg(feature = "version-history")]
+        self.remember(&map[key]);
+        self.notify(&map[key]);
         Ok(version)
     }
 
@@ -90,15 +701,36 @@ impl MemorySystem for InMemoryStore {
 
     async fn remove(&self, key: &str) -> Result<(), MemoryError> {
         let mut map = self.inner.write().await;
-        map.remove(key)
-            .map(|_| ())
-            .ok_or_else(|| MemoryError::NotFound(key.to_owned()))
+        map.remove(key).ok_or_else(|| MemoryError::NotFound(key.to_owned()))?;
+        #[cfg(feature = "version-history")]
+        self.forget(key);
+        Ok(())
+    }
+
+    async fn remove_if_version(&self, key: &str, expected_version: u64) -> Result<(), MemoryError> {
+        let mut map = self.inner.write().await;
+        let current = map.get(key).ok_or_else(|| MemoryError::NotFound(key.to_owned()))?;
+        check_version(key, current.version, expected_version)?;
+        map.remove(key);
+        #[cfg(feature = "version-history")]
+        self.forget(key);
+        Ok(())
     }
 
     async fn keys(&self) -> Result<Vec<String>, MemoryError> {
         let map = self.inner.read().await;
         Ok(map.keys().cloned().collect())
     }
+
+    #[cfg(feature = "version-history")]
+    async fn load_version(&self, key: &str, version: u64) -> Result<Record, MemoryError> {
+        let history = self.history.lock().unwrap();
+        history
+            .get(key)
+            .and_then(|versions| versions.iter().find(|r| r.version == version))
+            .cloned()
+            .ok_or_else(|| MemoryError::NotFound(format!("{key} v{version}")))
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +757,78 @@ mod tests {
         assert_eq!(v2, 2);
     }
 
+    #[tokio::test]
+    async fn store_dedup_skips_identical_content() {
+        let mem = InMemoryStore::new();
+        assert_eq!(mem.store_dedup("k1", json!({"a": 1, "b": [2]})).await.unwrap(), 1);
+        let written = mem.load("k1").await.unwrap().updated_at;
+        assert_eq!(mem.store_dedup("k1", json!({"b": [2], "a": 1})).await.unwrap(), 1);
+        assert_eq!(mem.load("k1").await.unwrap().updated_at, written);
+    }
+
+    #[tokio::test]
+    async fn store_dedup_writes_changed_content() {
+        let mem = InMemoryStore::new();
+        mem.store_dedup("k1", json!({"a": 1})).await.unwrap();
+        assert_eq!(mem.store_dedup("k1", json!({"a": 2})).await.unwrap(), 2);
+        assert_eq!(mem.load("k1").await.unwrap().value, json!({"a": 2}));
+        // Plain `store` still bumps the version on identical content.
+        assert_eq!(mem.store("k1", json!({"a": 2})).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn watch_sees_writes_under_its_prefix() {
+        let store = InMemoryStore::new();
+        let mut tasks = store.watch("task:");
+        store.store("task:1", json!(1)).await.unwrap();
+        store.store("other", json!(2)).await.unwrap();
+        store.store_dedup("task:1", json!(1)).await.unwrap();
+        store.store_if_version("task:1", json!(3), 1).await.unwrap();
+
+        let first = tasks.recv().await.unwrap();
+        assert_eq!((first.key.as_str(), first.version), ("task:1", 1));
+        let second = tasks.recv().await.unwrap();
+        assert_eq!((second.value, second.version), (json!(3), 2));
+        assert!(tasks.try_recv().is_err());
+
+        // A dropped receiver is pruned on the next write.
+        drop(tasks);
+        store.store("task:2", json!(4)).await.unwrap();
+        assert!(store.watchers.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn store_batch_is_all_or_nothing() {
+        let store = InMemoryStore::new();
+        let items = |keys: &[&str]| -> Vec<(String, serde_json::Value)> {
+            keys.iter().map(|k| (k.to_string(), json!(k))).collect()
+        };
+        let versions: Vec<u64> = store
+            .store_batch(items(&["a", "b", "a"]))
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(versions, [1, 1, 2]);
+
+        // A key whose version cannot advance fails the whole batch.
+        let full = Record {
+            key: "full".into(),
+            value: json!(null),
+            version: u64::MAX,
+            updated_at: chrono::Utc::now(),
+        };
+        store.restore(full).await.unwrap();
+        let results = store.store_batch(items(&["a", "c", "full", "d"])).await;
+        assert!(matches!(results[2], Err(MemoryError::Backend(_))));
+        for i in [0, 1, 3] {
+            assert!(matches!(results[i], Err(MemoryError::BatchAborted(2))), "item {i}");
+        }
+        assert_eq!(store.load("a").await.unwrap().version, 2);
+        assert!(matches!(store.load("c").await, Err(MemoryError::NotFound(_))));
+        assert_eq!(store.len().await, 3);
+    }
+
     #[tokio::test]
     async fn load_missing_returns_not_found() {
         let mem = InMemoryStore::new();
@@ -140,6 +844,90 @@ mod tests {
         assert!(mem.load("k1").await.is_err());
     }
 
+    #[tokio::test]
+    async fn restore_rejects_version_regression() {
+        let mem = InMemoryStore::new();
+        for i in 0..3 {
+            mem.store("k1", json!(i)).await.unwrap();
+        }
+
+        let stale = Record {
+            key: "k1".into(),
+            value: json!("stale"),
+            version: 2,
+            updated_at: chrono::Utc::now(),
+        };
+        let err = mem.restore(stale.clone()).await.unwrap_err();
+        assert!(matches!(err, MemoryError::Backend(ref m) if m == "version regression"));
+        let equal = Record { version: 3, ..stale.clone() };
+        assert!(mem.restore(equal).await.is_err());
+        assert_eq!(mem.load("k1").await.unwrap().value, json!(2));
+
+        let newer = Record { version: 5, ..stale };
+        mem.restore(newer).await.unwrap();
+        assert_eq!(mem.load("k1").await.unwrap().version, 5);
+        assert_eq!(mem.store("k1", json!("next")).await.unwrap(), 6);
+    }
+
+    #[tokio::test]
+    async fn store_rejects_version_overflow() {
+        let mem = InMemoryStore::new();
+        mem.restore(Record {
+            key: "k1".into(),
+            value: json!(0),
+            version: u64::MAX,
+            updated_at: chrono::Utc::now(),
+        })
+        .await
+        .unwrap();
+        assert!(matches!(mem.store("k1", json!(1)).await, Err(MemoryError::Backend(_))));
+    }
+
+    #[tokio::test]
+    async fn remove_if_version_deletes_only_matching_version() {
+        let mem = InMemoryStore::new();
+        mem.store("lock", json!("owner-a")).await.unwrap();
+        let v2 = mem.store("lock", json!("owner-b")).await.unwrap();
+
+        let err = mem.remove_if_version("lock", 1).await.unwrap_err();
+        assert!(matches!(
+            err,
+            MemoryError::VersionConflict { ref key, expected: 1, actual: 2 } if key == "lock"
+        ));
+        assert_eq!(mem.load("lock").await.unwrap().value, json!("owner-b"));
+
+        mem.remove_if_version("lock", v2).await.unwrap();
+        assert!(matches!(mem.load("lock").await, Err(MemoryError::NotFound(_))));
+        assert!(matches!(
+            mem.remove_if_version("lock", v2).await,
+            Err(MemoryError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn store_if_version_writes_only_matching_version() {
+        let mem = InMemoryStore::new();
+        assert_eq!(mem.store_if_version("k", json!("a"), 0).await.unwrap(), 1);
+        assert!(matches!(
+            mem.store_if_version("k", json!("b"), 0).await,
+            Err(MemoryError::VersionConflict { expected: 0, actual: 1, .. })
+        ));
+        assert_eq!(mem.store_if_version("k", json!("c"), 1).await.unwrap(), 2);
+        assert_eq!(mem.load("k").await.unwrap().value, json!("c"));
+        assert!(matches!(
+            mem.store_if_version("missing", json!(1), 3).await,
+            Err(MemoryError::VersionConflict { expected: 3, actual: 0, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn flush_is_a_no_op() {
+        let mem = InMemoryStore::new();
+        mem.store("k1", json!(1)).await.unwrap();
+        mem.flush().await.unwrap();
+        assert_eq!(mem.load("k1").await.unwrap().version, 1);
+    }
+
     #[tokio::test]
     async fn keys_lists_all() {
         let mem = InMemoryStore::new();
@@ -149,4 +937,210 @@ mod tests {
         keys.sort();
         assert_eq!(keys, vec!["a", "b"]);
     }
+
+    #[tokio::test]
+    async fn size_introspection_tracks_contents() {
+        let mem = InMemoryStore::new();
+        assert!(mem.is_empty().await);
+        assert_eq!(mem.approximate_bytes().await, 0);
+
+        mem.store("a", json!("x")).await.unwrap();
+        mem.store("b", json!("y")).await.unwrap();
+        assert_eq!(mem.len().await, 2);
+        let small = mem.approximate_bytes().await;
+        assert!(small > 0);
+
+        mem.store("b", json!("y".repeat(1_000))).await.unwrap();
+        assert_eq!(mem.len().await, 2);
+        assert!(mem.approximate_bytes().await >= small + 999);
+    }
+
+    fn large_record() -> Record {
+        Record {
+            key: "blob".into(),
+            value: json!({ "samples": (0..10_000).map(|i| i % 256).collect::<Vec<_>>() }),
+            version: 7,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn json_format_roundtrip() {
+        let record = large_record();
+        let bytes = SerializationFormat::Json.encode(&record).unwrap();
+        let back = SerializationFormat::Json.decode(&bytes).unwrap();
+        assert_eq!(back.value, record.value);
+        assert_eq!(back.version, 7);
+        assert_eq!(back.updated_at, record.updated_at);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_format_roundtrip_is_smaller() {
+        let record = large_record();
+        let packed = SerializationFormat::MessagePack.encode(&record).unwrap();
+        let back = SerializationFormat::MessagePack.decode(&packed).unwrap();
+        assert_eq!(back.value, record.value);
+        assert_eq!(back.updated_at, record.updated_at);
+
+        let json = SerializationFormat::Json.encode(&record).unwrap();
+        assert!(packed.len() * 3 < json.len() * 2, "{} vs {} bytes", packed.len(), json.len());
+        assert!(SerializationFormat::Json.decode(&packed).is_err());
+    }
+
+    #[tokio::test]
+    async fn keys_paginated_covers_all_keys_once() {
+        let mem = InMemoryStore::new();
+        for i in 0..25 {
+            mem.store(&format!("key{i:02}"), json!(i)).await.unwrap();
+        }
+
+        let mut pages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next) = mem.keys_paginated(cursor, 10).await.unwrap();
+            pages.push(page);
+            match next {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+
+        let sizes: Vec<usize> = pages.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![10, 10, 5]);
+        let all: Vec<String> = pages.concat();
+        let expected: Vec<String> = (0..25).map(|i| format!("key{i:02}")).collect();
+        assert_eq!(all, expected);
+    }
+
+    #[tokio::test]
+    async fn keys_paginated_exact_fit_has_no_cursor() {
+        let mem = InMemoryStore::new();
+        for k in ["a", "b"] {
+            mem.store(k, json!(0)).await.unwrap();
+        }
+        let (page, cursor) = mem.keys_paginated(None, 2).await.unwrap();
+        assert_eq!((page, cursor), (vec!["a".to_string(), "b".to_string()], None));
+        assert_eq!(mem.keys_paginated(Some("b".into()), 2).await.unwrap(), (vec![], None));
+        assert_eq!(mem.keys_paginated(None, 0).await.unwrap(), (vec![], None));
+    }
+
+    #[test]
+    fn diff_replaces_changed_field() {
+        let old = json!({"phase": "executing", "meta": {"owner": "a/b", "tries": 1}});
+        let new = json!({"phase": "completed", "meta": {"owner": "a/c", "tries": 1}});
+        assert_eq!(
+            Record::diff(&old, &new),
+            json!([
+                {"op": "replace", "path": "/meta/owner", "value": "a/c"},
+                {"op": "replace", "path": "/phase", "value": "completed"},
+            ])
+        );
+        assert_eq!(Record::diff(&old, &old), json!([]));
+        assert_eq!(
+            Record::diff(&json!(1), &json!("one")),
+            json!([{"op": "replace", "path": "", "value": "one"}])
+        );
+    }
+
+    #[test]
+    fn diff_adds_new_members() {
+        let old = json!({"tags": ["a"]});
+        let new = json!({"tags": ["a", "b", "c"], "a/b~c": {"n": 1}});
+        assert_eq!(
+            Record::diff(&old, &new),
+            json!([
+                {"op": "add", "path": "/tags/1", "value": "b"},
+                {"op": "add", "path": "/tags/2", "value": "c"},
+                {"op": "add", "path": "/a~1b~0c", "value": {"n": 1}},
+            ])
+        );
+    }
+
+    #[test]
+    fn diff_removes_missing_members() {
+        let old = json!({"result": {"ok": true}, "tags": ["a", "b", "c"], "keep": 1});
+        let new = json!({"tags": ["a"], "keep": 1});
+        assert_eq!(
+            Record::diff(&old, &new),
+            json!([
+                {"op": "remove", "path": "/result"},
+                {"op": "remove", "path": "/tags/2"},
+                {"op": "remove", "path": "/tags/1"},
+            ])
+        );
+    }
+
+    #[cfg(feature = "version-history")]
+    #[tokio::test]
+    async fn version_diff_patches_between_stored_versions() {
+        let mem = InMemoryStore::new();
+        mem.store("task", json!({"phase": "pending", "retries": 0})).await.unwrap();
+        mem.store("task", json!({"phase": "executing", "retries": 0})).await.unwrap();
+        mem.store("task", json!({"phase": "executing", "owner": "x"})).await.unwrap();
+
+        assert_eq!(
+            mem.version_diff("task", 1, 2).await.unwrap(),
+            json!([{"op": "replace", "path": "/phase", "value": "executing"}])
+        );
+        assert_eq!(
+            mem.version_diff("task", 2, 3).await.unwrap(),
+            json!([
+                {"op": "remove", "path": "/retries"},
+                {"op": "add", "path": "/owner", "value": "x"},
+            ])
+        );
+        assert_eq!(mem.version_diff("task", 3, 3).await.unwrap(), json!([]));
+        assert!(matches!(mem.version_diff("task", 1, 4).await, Err(MemoryError::NotFound(_))));
+
+        mem.remove("task").await.unwrap();
+        assert!(mem.load_version("task", 1).await.is_err());
+    }
+
+    #[test]
+    fn base64_round_trips_every_padding() {
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(b"fooba"), "Zm9vYmE=");
+        assert_eq!(base64_encode(b"foob"), "Zm9vYg==");
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..8 {
+            let encoded = base64_encode(&bytes[..len]);
+            assert_eq!(base64_decode(&encoded).as_deref(), Some(&bytes[..len]));
+        }
+        assert_eq!(base64_decode(&base64_encode(&bytes)), Some(bytes));
+        assert_eq!(base64_decode("Zm9"), None);
+        assert_eq!(base64_decode("Zm9*"), None);
+    }
+
+    #[tokio::test]
+    async fn chunked_payload_round_trips() {
+        let mem = InMemoryStore::new();
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        mem.store_chunked("blob", &data, 1024).await.unwrap();
+        let manifest = mem.load("blob").await.unwrap().value;
+        assert_eq!(manifest["sha256"], to_hex(&Sha256::digest(&data)));
+        let first = manifest["generation"].as_str().unwrap().to_owned();
+        let chunk = |generation: &str, n: usize| format!("blob:chunk:{generation}:{n}");
+        assert!(mem.load(&chunk(&first, 9)).await.is_ok());
+        assert!(mem.load(&chunk(&first, 10)).await.is_err());
+        assert_eq!(mem.load_chunked("blob").await.unwrap(), data);
+
+        // A new payload is written beside the current one, which is removed
+        // only once the manifest points at the new chunks.
+        mem.store_chunked("blob", &data[..3000], 1024).await.unwrap();
+        let second = mem.load("blob").await.unwrap().value["generation"].clone();
+        let second = second.as_str().unwrap().to_owned();
+        assert_ne!(second, first);
+        assert_eq!(mem.load_chunked("blob").await.unwrap(), &data[..3000]);
+        assert!(mem.load(&chunk(&first, 0)).await.is_err());
+        assert_eq!(mem.keys().await.unwrap().len(), 4);
+
+        // Storing the same payload again keeps its chunks.
+        mem.store_chunked("blob", &data[..3000], 1024).await.unwrap();
+        assert_eq!(mem.load(&chunk(&second, 0)).await.unwrap().version, 1);
+
+        mem.store(&chunk(&second, 1), json!("AAAA")).await.unwrap();
+        assert!(matches!(mem.load_chunked("blob").await, Err(MemoryError::Serialization(_))));
+        assert!(matches!(mem.load_chunked("missing").await, Err(MemoryError::NotFound(_))));
+    }
 }
@@ -0,0 +1,119 @@
+//! Metrics — point-in-time snapshots of each subsystem for monitoring.
+//!
+//! Memory stores, the message bus, the adapter registry and the task tracker
+//! all implement [`Metrics`]; [`collect_all`] gathers their snapshots into
+//! one JSON document suitable for a monitoring endpoint.
+
+use serde_json::{Map, Value};
+
+/// A subsystem that can report its current state.
+///
+/// Snapshots are cheap and never block: a subsystem that cannot be read
+/// right now reports what it can rather than waiting.
+pub trait Metrics: Send + Sync {
+    /// Key under which [`collect_all`] places this snapshot, e.g. `"bus"`.
+    fn subsystem(&self) -> &'static str;
+
+    /// The subsystem's current state as a JSON object.
+    fn snapshot(&self) -> Value;
+}
+
+/// Gather every snapshot into one object keyed by
+/// [`subsystem`](Metrics::subsystem). A later source with the same key
+/// replaces an earlier one.
+pub fn collect_all(sources: &[&dyn Metrics]) -> Value {
+    let snapshots: Map<String, Value> =
+        sources.iter().map(|m| (m.subsystem().to_owned(), m.snapshot())).collect();
+    Value::Object(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Provider;
+    use crate::bus::MessageBus;
+    use crate::memory::{InMemoryStore, MemorySystem};
+    use crate::protocol::{Message, MessageKind, TaskMeta};
+    use crate::registry::AdapterRegistry;
+    use crate::task::{Task, TaskPhase, TaskTracker};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn memory_reports_records_and_bytes() {
+        let memory = InMemoryStore::new();
+        memory.store("k1", json!("value")).await.unwrap();
+        let snapshot = memory.snapshot();
+        assert_eq!(snapshot["records"], 1);
+        assert_eq!(snapshot["bytes"], memory.approximate_bytes().await);
+    }
+
+    #[tokio::test]
+    async fn bus_reports_subscribers_and_lag() {
+        let bus = MessageBus::new(8);
+        let _slow = bus.subscribe();
+        let _idle = bus.subscribe();
+        let message = Message {
+            id: uuid::Uuid::new_v4(),
+            source: uuid::Uuid::new_v4(),
+            target: None,
+            kind: MessageKind::Status,
+            payload: json!({}),
+            timestamp: 0,
+        };
+        bus.publish(message).await.unwrap();
+        assert_eq!(bus.snapshot(), json!({"subscribers": 2, "lag": 1, "capacity": 8}));
+    }
+
+    #[test]
+    fn registry_reports_health_per_provider() {
+        use crate::mock::MockAdapter;
+
+        let mut registry = AdapterRegistry::new();
+        registry.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        registry.register(Arc::new(MockAdapter::new(Provider::Grok)));
+        registry.health().record_failure(Provider::Grok, "timeout");
+        registry.latency().record(Provider::Claude, 120);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot["providers"]["claude"]["healthy"], true);
+        assert_eq!(snapshot["providers"]["claude"]["latency_ewma_ms"], 120.0);
+        assert_eq!(snapshot["providers"]["grok"]["healthy"], false);
+        assert_eq!(snapshot["providers"]["grok"]["last_failure"], "timeout");
+        assert_eq!(snapshot["healthy"], 1);
+    }
+
+    fn task() -> Task {
+        let meta = TaskMeta::new("test", "metrics", "metrics test");
+        Task::new(meta, json!({}))
+    }
+
+    #[test]
+    fn tracker_counts_tasks_per_phase() {
+        let tracker = TaskTracker::new();
+        let mut running = task();
+        running.initialize().unwrap();
+        let pending = task();
+        tracker.track(&running);
+        tracker.track(&pending);
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot["total"], 2);
+        assert_eq!(snapshot["phases"]["initialized"], 1);
+        assert_eq!(snapshot["phases"]["pending"], 1);
+        assert_eq!(snapshot["phases"]["failed"], 0);
+
+        tracker.forget(pending.id);
+        assert_eq!(tracker.count(TaskPhase::Pending), 0);
+    }
+
+    #[test]
+    fn collect_all_keys_by_subsystem() {
+        let bus = MessageBus::new(4);
+        let tracker = TaskTracker::new();
+        let all = collect_all(&[&bus, &tracker, &InMemoryStore::new()]);
+        let mut keys: Vec<_> = all.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["bus", "memory", "tasks"]);
+        assert_eq!(all["tasks"]["total"], 0);
+    }
+}
@@ -0,0 +1,636 @@
+//! Middleware — composable layers around any [`Adapter`].
+//!
+//! A [`ServiceStack`] wraps a base adapter in an ordered list of
+//! [`AdapterLayer`]s. The first layer added is the outermost: it sees the
+//! request first and the response last. Each layer decides whether, and how
+//! many times, to call the rest of the stack through [`Next`].
+//!
+//! For read-only observation of a single adapter, [`ObservableAdapter`]
+//! exposes plain request/response hooks instead.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::adapter::{
+    Adapter, AdapterError, Feature, Message, ModelResponse, ModelTier, Provider,
+};
+use crate::prompt::Prompt;
+use crate::protocol::{LogEntry, LogLevel, LogSink};
+
+/// A single middleware layer.
+#[async_trait]
+pub trait AdapterLayer: Send + Sync {
+    /// Handle a `chat` request, delegating to `next` as needed.
+    async fn call(
+        &self,
+        messages: &[Message],
+        next: Next<'_>,
+    ) -> Result<ModelResponse, AdapterError>;
+}
+
+/// The remainder of a [`ServiceStack`] below the current layer.
+///
+/// `Next` is `Copy`, so a layer may invoke it more than once (e.g. to retry).
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    base: &'a dyn Adapter,
+    layers: &'a [Arc<dyn AdapterLayer>],
+}
+
+impl Next<'_> {
+    /// Provider served by the base adapter.
+    pub fn provider(&self) -> Provider {
+        self.base.provider()
+    }
+
+    /// Pass `messages` to the next layer, or to the base adapter.
+    pub async fn run(self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        match self.layers.split_first() {
+            Some((layer, rest)) => {
+                layer
+                    .call(messages, Next { base: self.base, layers: rest })
+                    .await
+            }
+            None => self.base.chat(messages).await,
+        }
+    }
+}
+
+/// An [`Adapter`] built from a base adapter and an ordered list of layers.
+pub struct ServiceStack {
+    base: Arc<dyn Adapter>,
+    layers: Vec<Arc<dyn AdapterLayer>>,
+}
+
+impl ServiceStack {
+    pub fn new(base: Arc<dyn Adapter>) -> Self {
+        Self { base, layers: Vec::new() }
+    }
+
+    /// Add `layer` beneath the layers added so far.
+    pub fn layer(mut self, layer: Arc<dyn AdapterLayer>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+}
+
+#[async_trait]
+impl Adapter for ServiceStack {
+    fn provider(&self) -> Provider {
+        self.base.provider()
+    }
+
+    fn tier(&self) -> ModelTier {
+        self.base.tier()
+    }
+
+    fn model(&self) -> Option<String> {
+        self.base.model()
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.base.features()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        Next { base: self.base.as_ref(), layers: &self.layers }
+            .run(messages)
+            .await
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        self.base.health_check().await
+    }
+
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        self.base.warm_up().await
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Built-in layers
+// ---------------------------------------------------------------------------
+
+/// How [`RetryLayer`] randomizes its backoff delays.
+///
+/// Without jitter, clients that fail together retry together, and every
+/// retry lands on the recovering provider at once. Prefer [`Full`](Self::Full)
+/// when many clients share a provider: it spreads retries the most for the
+/// least total waiting. [`Equal`](Self::Equal) guarantees at least half the
+/// exponential delay, and [`Decorrelated`](Self::Decorrelated) grows each
+/// delay from the previous one rather than from the attempt number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterStrategy {
+    /// Wait exactly `base_delay * 2^attempt`.
+    #[default]
+    None,
+    /// Wait a uniformly random time in `[0, base_delay * 2^attempt]`.
+    Full,
+    /// Wait half the exponential delay plus a random time up to the other half.
+    Equal,
+    /// Wait a random time in `[base_delay, previous_delay * 3]`.
+    Decorrelated,
+}
+
+/// SplitMix64: a small, seedable generator, plenty for spreading retries.
+#[derive(Debug)]
+struct Rng(AtomicU64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(AtomicU64::new(seed))
+    }
+
+    fn next_u64(&self) -> u64 {
+        let mut z = self.0.fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed);
+        z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random duration in `[lo, hi]`.
+    fn between(&self, lo: Duration, hi: Duration) -> Duration {
+        let lo_ns = u64::try_from(lo.as_nanos()).unwrap_or(u64::MAX);
+        let hi_ns = u64::try_from(hi.as_nanos()).unwrap_or(u64::MAX);
+        if hi_ns <= lo_ns {
+            return lo;
+        }
+        let span = hi_ns - lo_ns;
+        let offset = match span.checked_add(1) {
+            Some(n) => self.next_u64() % n,
+            None => self.next_u64(),
+        };
+        Duration::from_nanos(lo_ns + offset)
+    }
+}
+
+impl Clone for Rng {
+    fn clone(&self) -> Self {
+        Self::new(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Retries transient failures with exponential backoff.
+///
+/// Only [retryable](AdapterError::is_retryable) errors are retried:
+/// `RateLimited` waits for the provider's `retry_after_ms`, and server,
+/// network and timeout failures wait `base_delay * 2^attempt`, randomized by
+/// the layer's [`JitterStrategy`] and capped by its
+/// [maximum delay](Self::with_max_delay). Anything else is returned
+/// immediately.
+#[derive(Debug, Clone)]
+pub struct RetryLayer {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Option<Duration>,
+    jitter: JitterStrategy,
+    rng: Rng,
+}
+
+impl RetryLayer {
+    pub fn new(max_retries: u32, base_delay: Duration) -> Self {
+        let seed = std::collections::hash_map::RandomState::new().build_hasher().finish();
+        Self {
+            max_retries,
+            base_delay,
+            max_delay: None,
+            jitter: JitterStrategy::None,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: JitterStrategy) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Never wait longer than `max` between attempts, whatever the strategy.
+    /// A provider's `retry_after_ms` is still honoured in full.
+    pub fn with_max_delay(mut self, max: Duration) -> Self {
+        self.max_delay = Some(max);
+        self
+    }
+
+    /// Seed the jitter, so tests see the same delays on every run.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    fn backoff(&self, attempt: u32, err: &AdapterError) -> Option<Duration> {
+        match err {
+            AdapterError::RateLimited { retry_after_ms } => {
+                Some(Duration::from_millis(*retry_after_ms))
+            }
+            e if e.is_retryable() => Some(self.base_delay * 2u32.saturating_pow(attempt)),
+            _ => None,
+        }
+    }
+
+    /// The delay before retry `attempt`, given the previous delay waited.
+    fn delay(&self, attempt: u32, err: &AdapterError, previous: Duration) -> Option<Duration> {
+        let backoff = self.backoff(attempt, err)?;
+        if matches!(err, AdapterError::RateLimited { .. }) {
+            return Some(backoff);
+        }
+        let delay = match self.jitter {
+            JitterStrategy::None => backoff,
+            JitterStrategy::Full => self.rng.between(Duration::ZERO, backoff),
+            JitterStrategy::Equal => backoff / 2 + self.rng.between(Duration::ZERO, backoff / 2),
+            JitterStrategy::Decorrelated => {
+                self.rng.between(self.base_delay, previous.saturating_mul(3))
+            }
+        };
+        Some(self.max_delay.map_or(delay, |max| delay.min(max)))
+    }
+}
+
+#[async_trait]
+impl AdapterLayer for RetryLayer {
+    async fn call(
+        &self,
+        messages: &[Message],
+        next: Next<'_>,
+    ) -> Result<ModelResponse, AdapterError> {
+        let mut attempt = 0;
+        let mut previous = self.base_delay;
+        loop {
+            match next.run(messages).await {
+                Err(err) if attempt < self.max_retries => {
+                    match self.delay(attempt, &err, previous) {
+                        Some(delay) => {
+                            tokio::time::sleep(delay).await;
+                            previous = delay;
+                            attempt += 1;
+                        }
+                        None => return Err(err),
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Accumulated usage for one provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub requests: u64,
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Records per-provider request counts and token usage.
+#[derive(Debug, Default)]
+pub struct MeteringLayer {
+    usage: Mutex<HashMap<Provider, Usage>>,
+}
+
+impl MeteringLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Usage recorded so far for `provider`.
+    pub fn usage(&self, provider: Provider) -> Usage {
+        self.usage.lock().unwrap().get(&provider).copied().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl AdapterLayer for MeteringLayer {
+    async fn call(
+        &self,
+        messages: &[Message],
+        next: Next<'_>,
+    ) -> Result<ModelResponse, AdapterError> {
+        let result = next.run(messages).await;
+        let mut map = self.usage.lock().unwrap();
+        let usage = map.entry(next.provider()).or_default();
+        usage.requests += 1;
+        match &result {
+            Ok(resp) => {
+                usage.input_tokens += u64::from(resp.input_tokens);
+                usage.output_tokens += u64::from(resp.output_tokens);
+            }
+            Err(_) => usage.errors += 1,
+        }
+        result
+    }
+}
+
+/// Emits a log entry for every request and its outcome.
+pub struct LoggingLayer {
+    sink: Arc<dyn LogSink>,
+}
+
+impl LoggingLayer {
+    pub fn new(sink: Arc<dyn LogSink>) -> Self {
+        Self { sink }
+    }
+}
+
+#[async_trait]
+impl AdapterLayer for LoggingLayer {
+    async fn call(
+        &self,
+        messages: &[Message],
+        next: Next<'_>,
+    ) -> Result<ModelResponse, AdapterError> {
+        let provider = next.provider();
+        self.sink.emit(&LogEntry::new(
+            LogLevel::Debug,
+            "adapter",
+            format!("{provider} request: {} messages", messages.len()),
+        ));
+        let result = next.run(messages).await;
+        let entry = match &result {
+            Ok(resp) => LogEntry::new(
+                LogLevel::Info,
+                "adapter",
+                format!(
+                    "{provider} response: {} output tokens in {} ms",
+                    resp.output_tokens, resp.latency_ms
+                ),
+            ),
+            Err(e) => LogEntry::new(LogLevel::Error, "adapter", format!("{provider} error: {e}")),
+        };
+        self.sink.emit(&entry);
+        result
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Observability hooks
+// ---------------------------------------------------------------------------
+
+/// Called with the provider and the conversation before each `chat`.
+pub type RequestHook = Box<dyn Fn(Provider, &[Message]) + Send + Sync>;
+
+/// Called with the provider and the outcome after each `chat`.
+pub type ResponseHook = Box<dyn Fn(Provider, &Result<ModelResponse, AdapterError>) + Send + Sync>;
+
+/// [`Adapter`] decorator that reports every request and response to
+/// caller-supplied hooks, e.g. for central logging or tracing.
+///
+/// Hooks only observe: the messages and the result pass through unchanged.
+pub struct ObservableAdapter<A> {
+    inner: A,
+    on_request: Option<RequestHook>,
+    on_response: Option<ResponseHook>,
+}
+
+impl<A: Adapter> ObservableAdapter<A> {
+    pub fn new(inner: A) -> Self {
+        Self { inner, on_request: None, on_response: None }
+    }
+
+    pub fn on_request<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(Provider, &[Message]) + Send + Sync + 'static,
+    {
+        self.on_request = Some(Box::new(hook));
+        self
+    }
+
+    pub fn on_response<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(Provider, &Result<ModelResponse, AdapterError>) + Send + Sync + 'static,
+    {
+        self.on_response = Some(Box::new(hook));
+        self
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<A: Adapter> Adapter for ObservableAdapter<A> {
+    fn provider(&self) -> Provider {
+        self.inner.provider()
+    }
+
+    fn tier(&self) -> ModelTier {
+        self.inner.tier()
+    }
+
+    fn model(&self) -> Option<String> {
+        self.inner.model()
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.inner.features()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        let provider = self.inner.provider();
+        if let Some(hook) = &self.on_request {
+            hook(provider, messages);
+        }
+        let result = self.inner.chat(messages).await;
+        if let Some(hook) = &self.on_response {
+            hook(provider, &result);
+        }
+        result
+    }
+
+    /// The request hook sees the prompt flattened into messages.
+    async fn chat_prompt(&self, prompt: &Prompt) -> Result<ModelResponse, AdapterError> {
+        let provider = self.inner.provider();
+        if let Some(hook) = &self.on_request {
+            hook(provider, &prompt.clone().into_messages());
+        }
+        let result = self.inner.chat_prompt(prompt).await;
+        if let Some(hook) = &self.on_response {
+            hook(provider, &result);
+        }
+        result
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        self.inner.health_check().await
+    }
+
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        self.inner.warm_up().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Role;
+    use crate::mock::MockAdapter;
+    use crate::protocol::MemoryLogSink;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn user(content: &str) -> Vec<Message> {
+        vec![Message { role: Role::User, content: content.into() }]
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_stack_retries_meters_and_logs() {
+        let mock = Arc::new(
+            MockAdapter::new(Provider::Claude)
+                .with_reply("all good")
+                .with_failures(2, "connection reset"),
+        );
+        let sink = MemoryLogSink::new();
+        let metering = Arc::new(MeteringLayer::new());
+        let stack = ServiceStack::new(mock.clone())
+            .layer(Arc::new(LoggingLayer::new(Arc::new(sink.clone()))))
+            .layer(metering.clone())
+            .layer(Arc::new(RetryLayer::new(3, Duration::from_millis(10))));
+
+        let resp = stack.chat(&user("hello world")).await.unwrap();
+        assert_eq!(resp.content, "all good");
+
+        // Retry: two failures absorbed beneath the metering layer.
+        assert_eq!(mock.calls(), 3);
+        // Metering: one logical request with its token usage.
+        let usage = metering.usage(Provider::Claude);
+        assert_eq!(usage, Usage { requests: 1, errors: 0, input_tokens: 2, output_tokens: 2 });
+        // Logging: request and response entries.
+        let messages: Vec<String> = sink.entries().into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            messages,
+            vec!["claude request: 1 messages", "claude response: 2 output tokens in 0 ms"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_gives_up_after_max_retries() {
+        let mock = Arc::new(MockAdapter::new(Provider::Gemini).with_failures(5, "down"));
+        let metering = Arc::new(MeteringLayer::new());
+        let stack = ServiceStack::new(mock.clone())
+            .layer(Arc::new(RetryLayer::new(2, Duration::from_millis(10))))
+            .layer(metering.clone());
+
+        assert!(stack.chat(&user("hi")).await.is_err());
+        assert_eq!(mock.calls(), 3);
+        // Metering sits beneath retry here, so it sees every attempt.
+        assert_eq!(metering.usage(Provider::Gemini).errors, 3);
+    }
+
+    #[tokio::test]
+    async fn hooks_fire_once_per_chat_with_payloads() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(Vec::new()));
+        let adapter = ObservableAdapter::new(MockAdapter::new(Provider::Grok).with_reply("pong"))
+            .on_request({
+                let requests = requests.clone();
+                move |p, msgs: &[Message]| {
+                    requests.lock().unwrap().push((p, msgs[0].content.clone()));
+                }
+            })
+            .on_response({
+                let responses = responses.clone();
+                move |p, result: &Result<ModelResponse, AdapterError>| {
+                    let content = result.as_ref().map(|r| r.content.clone()).ok();
+                    responses.lock().unwrap().push((p, content));
+                }
+            });
+
+        let resp = adapter.chat(&user("ping")).await.unwrap();
+        assert_eq!(resp.content, "pong");
+        assert_eq!(*requests.lock().unwrap(), vec![(Provider::Grok, "ping".to_string())]);
+        assert_eq!(*responses.lock().unwrap(), vec![(Provider::Grok, Some("pong".to_string()))]);
+        assert_eq!(adapter.inner().calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn response_hook_sees_errors() {
+        let errors = Arc::new(AtomicUsize::new(0));
+        let adapter = ObservableAdapter::new(MockAdapter::new(Provider::Claude).failing("down"))
+            .on_response({
+                let errors = errors.clone();
+                move |_, result: &Result<ModelResponse, AdapterError>| {
+                    if result.is_err() {
+                        errors.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        assert!(adapter.chat(&user("hi")).await.is_err());
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        let layer = RetryLayer::new(3, Duration::from_millis(10));
+        assert!(layer.backoff(0, &AdapterError::Auth("bad key".into())).is_none());
+        assert!(layer.backoff(0, &AdapterError::from_status(400, "bad request", None)).is_none());
+        assert_eq!(
+            layer.backoff(2, &AdapterError::Network("reset".into())),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(
+            layer.backoff(1, &AdapterError::from_status(503, "unavailable", None)),
+            Some(Duration::from_millis(20))
+        );
+    }
+
+    #[test]
+    fn full_jitter_stays_within_the_exponential_delay() {
+        let layer = RetryLayer::new(5, Duration::from_millis(100))
+            .with_jitter(JitterStrategy::Full)
+            .with_seed(7);
+        let err = AdapterError::Network("reset".into());
+        for attempt in 0..4 {
+            let base = layer.backoff(attempt, &err).unwrap();
+            let delays: Vec<Duration> =
+                (0..200).map(|_| layer.delay(attempt, &err, base).unwrap()).collect();
+            assert!(delays.iter().all(|d| *d <= base), "attempt {attempt}");
+            // Spread across the range rather than stuck at one end.
+            assert!(delays.iter().any(|d| *d < base / 4));
+            assert!(delays.iter().any(|d| *d > base * 3 / 4));
+        }
+
+        // The same seed yields the same delays.
+        let sample = |seed| -> Vec<_> {
+            let layer = RetryLayer::new(5, Duration::from_millis(100))
+                .with_jitter(JitterStrategy::Full)
+                .with_seed(seed);
+            (0..5).map(|a| layer.delay(a, &err, layer.base_delay)).collect()
+        };
+        assert_eq!(sample(7), sample(7));
+        assert_ne!(sample(7), sample(8));
+    }
+
+    #[test]
+    fn decorrelated_jitter_stays_within_its_bound() {
+        let base = Duration::from_millis(10);
+        let cap = Duration::from_secs(2);
+        let layer = RetryLayer::new(50, base)
+            .with_jitter(JitterStrategy::Decorrelated)
+            .with_max_delay(cap)
+            .with_seed(42);
+        let err = AdapterError::from_status(503, "unavailable", None);
+        let mut previous = base;
+        for attempt in 0..50 {
+            let delay = layer.delay(attempt, &err, previous).unwrap();
+            assert!(delay >= base && delay <= (previous * 3).min(cap), "attempt {attempt}");
+            previous = delay;
+        }
+
+        // Equal jitter keeps at least half the exponential delay.
+        let equal = RetryLayer::new(3, base).with_jitter(JitterStrategy::Equal).with_seed(1);
+        for _ in 0..100 {
+            let delay = equal.delay(2, &err, base).unwrap();
+            assert!(delay >= base * 2 && delay <= base * 4);
+        }
+        // A provider's retry-after is never jittered.
+        let limited = AdapterError::RateLimited { retry_after_ms: 750 };
+        assert_eq!(layer.delay(0, &limited, base), Some(Duration::from_millis(750)));
+    }
+}
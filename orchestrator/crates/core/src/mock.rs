@@ -0,0 +1,484 @@
+//! MockAdapter — scriptable in-process [`Adapter`] for tests and examples,
+//! plus [`MockLogic`], a stand-in [`CoreLogic`] for tests that never reach
+//! an adapter.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::time::Instant;
+
+use crate::adapter::{
+    Adapter, AdapterError, ChunkStream, ContentBlock, Feature, FinishReason, Message,
+    ModelResponse, ModelTier, Provider,
+};
+use crate::logic::{CoreLogic, LogicError, Query, QueryResult};
+use crate::prompt::Prompt;
+
+/// An [`Adapter`] that answers `chat` from a script of replies, then with a
+/// fixed reply, or fails with a fixed reason, without touching the network.
+#[derive(Debug)]
+pub struct MockAdapter {
+    provider: Provider,
+    model: String,
+    tier: ModelTier,
+    features: Vec<Feature>,
+    reply: String,
+    blocks: Vec<ContentBlock>,
+    script: Mutex<VecDeque<(String, FinishReason)>>,
+    failure: Option<String>,
+    transient_failures: AtomicUsize,
+    transient_reason: Option<String>,
+    rejections: AtomicUsize,
+    rejection_status: u16,
+    rate_limit: Option<u64>,
+    delay: Option<Duration>,
+    processing: Option<Duration>,
+    chunk_size: Option<usize>,
+    warm_up: Option<Duration>,
+    warm_ups: AtomicUsize,
+    calls: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+    requests: Mutex<Vec<Vec<Message>>>,
+    prompts: Mutex<Vec<Prompt>>,
+}
+
+impl MockAdapter {
+    /// Create a healthy mock that replies with `"mock response"`.
+    pub fn new(provider: Provider) -> Self {
+        Self {
+            provider,
+            model: format!("{provider}-mock"),
+            tier: ModelTier::default(),
+            features: Vec::new(),
+            reply: "mock response".into(),
+            blocks: Vec::new(),
+            script: Mutex::new(VecDeque::new()),
+            failure: None,
+            transient_failures: AtomicUsize::new(0),
+            transient_reason: None,
+            rejections: AtomicUsize::new(0),
+            rejection_status: 400,
+            rate_limit: None,
+            delay: None,
+            processing: None,
+            chunk_size: None,
+            warm_up: None,
+            warm_ups: AtomicUsize::new(0),
+            calls: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+            requests: Mutex::new(Vec::new()),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Report `tier` as the model's size class.
+    pub fn with_tier(mut self, tier: ModelTier) -> Self {
+        self.tier = tier;
+        self
+    }
+
+    /// Report `features` as the model's capabilities.
+    pub fn with_features(mut self, features: impl IntoIterator<Item = Feature>) -> Self {
+        self.features = features.into_iter().collect();
+        self
+    }
+
+    /// Set the reply returned by `chat`.
+    pub fn with_reply(mut self, reply: impl Into<String>) -> Self {
+        self.reply = reply.into();
+        self
+    }
+
+    /// Reply with a multimodal answer made of `blocks`, its text blocks
+    /// becoming the reply's `content`.
+    pub fn with_blocks(mut self, blocks: Vec<ContentBlock>) -> Self {
+        self.reply = ContentBlock::text(&blocks);
+        self.blocks = blocks;
+        self
+    }
+
+    /// Answer the next `chat` calls with `replies`, in order, before falling
+    /// back to the fixed reply.
+    pub fn with_script<S: Into<String>>(
+        self,
+        replies: impl IntoIterator<Item = (S, FinishReason)>,
+    ) -> Self {
+        self.script
+            .lock()
+            .unwrap()
+            .extend(replies.into_iter().map(|(text, finish)| (text.into(), finish)));
+        self
+    }
+
+    /// Make both `chat` and `health_check` fail with a network error.
+    pub fn failing(mut self, reason: impl Into<String>) -> Self {
+        self.failure = Some(reason.into());
+        self
+    }
+
+    /// Fail the next `n` calls to `chat` with a network error, then recover.
+    pub fn with_failures(mut self, n: usize, reason: impl Into<String>) -> Self {
+        self.transient_failures = AtomicUsize::new(n);
+        self.transient_reason = Some(reason.into());
+        self
+    }
+
+    /// Reject the next `n` calls to `chat` with HTTP `status`, classified by
+    /// [`AdapterError::from_status`], then recover. Health checks still pass.
+    pub fn with_rejections(mut self, n: usize, status: u16) -> Self {
+        self.rejections = AtomicUsize::new(n);
+        self.rejection_status = status;
+        self
+    }
+
+    /// Reject every `chat` as rate limited, asking to retry after
+    /// `retry_after_ms`. Health checks still pass.
+    pub fn rate_limited(mut self, retry_after_ms: u64) -> Self {
+        self.rate_limit = Some(retry_after_ms);
+        self
+    }
+
+    /// Sleep for `delay` before answering each `chat`, simulating time on
+    /// the network (reported as `network_ms`).
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sleep for `processing` after the simulated network round trip of each
+    /// successful `chat`, standing in for local response handling. It counts
+    /// towards `latency_ms` but not `network_ms`.
+    pub fn with_processing_delay(mut self, processing: Duration) -> Self {
+        self.processing = Some(processing);
+        self
+    }
+
+    /// Make `chat_stream` split each reply into chunks of `chars` characters
+    /// instead of yielding it whole.
+    pub fn with_chunk_size(mut self, chars: usize) -> Self {
+        self.chunk_size = Some(chars.max(1));
+        self
+    }
+
+    /// Sleep for `delay` in `warm_up`, simulating a connection handshake.
+    pub fn with_warm_up(mut self, delay: Duration) -> Self {
+        self.warm_up = Some(delay);
+        self
+    }
+
+    /// Number of `warm_up` calls received so far.
+    pub fn warm_ups(&self) -> usize {
+        self.warm_ups.load(Ordering::SeqCst)
+    }
+
+    /// Number of `chat` calls received so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Most `chat` calls that were ever running at once.
+    pub fn peak_in_flight(&self) -> usize {
+        self.peak_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Every conversation received by `chat`, in call order.
+    pub fn requests(&self) -> Vec<Vec<Message>> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Every prompt received by `chat_prompt` or `chat_prompt_stream`, in
+    /// call order. Each is also in [`requests`](Self::requests), flattened.
+    pub fn prompts(&self) -> Vec<Prompt> {
+        self.prompts.lock().unwrap().clone()
+    }
+
+    /// `result` split into chunks of the configured size.
+    fn chunks(&self, result: Result<ModelResponse, AdapterError>) -> ChunkStream<'static> {
+        let size = self.chunk_size.unwrap_or(usize::MAX);
+        let chunks: Vec<Result<String, AdapterError>> = match result {
+            Ok(response) => {
+                let chars: Vec<char> = response.content.chars().collect();
+                chars.chunks(size).map(|c| Ok(c.iter().collect())).collect()
+            }
+            Err(e) => vec![Err(e)],
+        };
+        Box::pin(futures::stream::iter(chunks))
+    }
+}
+
+/// Counts a call as in flight until dropped, on every return path.
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl<'a> InFlight<'a> {
+    fn enter(in_flight: &'a AtomicUsize, peak: &AtomicUsize) -> Self {
+        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        Self(in_flight)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Adapter for MockAdapter {
+    fn provider(&self) -> Provider {
+        self.provider
+    }
+
+    fn tier(&self) -> ModelTier {
+        self.tier
+    }
+
+    fn model(&self) -> Option<String> {
+        Some(self.model.clone())
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.features.clone()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let _in_flight = InFlight::enter(&self.in_flight, &self.peak_in_flight);
+        self.requests.lock().unwrap().push(messages.to_vec());
+        let started = Instant::now();
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        let network_ms = started.elapsed().as_millis() as u64;
+        if let Some(reason) = &self.failure {
+            return Err(AdapterError::Network(reason.clone()));
+        }
+        if let Some(retry_after_ms) = self.rate_limit {
+            return Err(AdapterError::RateLimited { retry_after_ms });
+        }
+        if self
+            .rejections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            return Err(AdapterError::from_status(self.rejection_status, "rejected", None));
+        }
+        if let Some(reason) = &self.transient_reason
+            && self
+                .transient_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        {
+            return Err(AdapterError::Network(reason.clone()));
+        }
+        let scripted = self.script.lock().unwrap().pop_front();
+        let (content, blocks, finish_reason) = match scripted {
+            Some((content, finish_reason)) => (content, Vec::new(), finish_reason),
+            None => (self.reply.clone(), self.blocks.clone(), FinishReason::Stop),
+        };
+        let input_tokens = messages
+            .iter()
+            .map(|m| m.content.split_whitespace().count() as u32)
+            .sum();
+        if let Some(processing) = self.processing {
+            tokio::time::sleep(processing).await;
+        }
+        Ok(ModelResponse {
+            provider: self.provider,
+            model: self.model.clone(),
+            output_tokens: content.split_whitespace().count() as u32,
+            content,
+            blocks,
+            input_tokens,
+            latency_ms: started.elapsed().as_millis() as u64,
+            network_ms,
+            finish_reason,
+        })
+    }
+
+    /// Records the prompt, then answers it flattened, like `chat`.
+    async fn chat_prompt(&self, prompt: &Prompt) -> Result<ModelResponse, AdapterError> {
+        self.prompts.lock().unwrap().push(prompt.clone());
+        self.chat(&prompt.clone().into_messages()).await
+    }
+
+    fn chat_stream<'a>(&'a self, messages: &'a [Message]) -> ChunkStream<'a> {
+        Box::pin(futures::stream::once(self.chat(messages)).flat_map(|r| self.chunks(r)))
+    }
+
+    fn chat_prompt_stream<'a>(&'a self, prompt: &'a Prompt) -> ChunkStream<'a> {
+        Box::pin(futures::stream::once(self.chat_prompt(prompt)).flat_map(|r| self.chunks(r)))
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        match &self.failure {
+            Some(reason) => Err(AdapterError::Network(reason.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Sleeps for the [`with_warm_up`](Self::with_warm_up) delay, then fails
+    /// like `health_check`.
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        self.warm_ups.fetch_add(1, Ordering::SeqCst);
+        if let Some(delay) = self.warm_up {
+            tokio::time::sleep(delay).await;
+        }
+        self.health_check().await
+    }
+}
+
+/// A [`CoreLogic`] that answers every query with its own content, as
+/// `claude`, without routing anywhere.
+///
+/// Clones share their counters, so a test can keep one to inspect while a
+/// runner or pool owns another.
+#[derive(Debug, Clone, Default)]
+pub struct MockLogic {
+    delay: Duration,
+    usage: (u32, u32),
+    failures: usize,
+    counters: Arc<LogicCounters>,
+}
+
+#[derive(Debug, Default)]
+struct LogicCounters {
+    calls: AtomicUsize,
+    answered: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+impl MockLogic {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sleep for `delay` before answering, reported as both `latency_ms`
+    /// and `network_ms`.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Report `input` and `output` tokens for every answer.
+    pub fn with_usage(mut self, input: u32, output: u32) -> Self {
+        self.usage = (input, output);
+        self
+    }
+
+    /// Fail the first `n` queries with [`LogicError::ProviderUnavailable`].
+    pub fn with_failures(mut self, n: usize) -> Self {
+        self.failures = n;
+        self
+    }
+
+    /// Number of queries received so far.
+    pub fn calls(&self) -> usize {
+        self.counters.calls.load(Ordering::SeqCst)
+    }
+
+    /// Number of queries answered successfully so far.
+    pub fn answered(&self) -> usize {
+        self.counters.answered.load(Ordering::SeqCst)
+    }
+
+    /// Most queries that were ever running at once.
+    pub fn peak_in_flight(&self) -> usize {
+        self.counters.peak_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+impl CoreLogic for MockLogic {
+    async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+        let counters = &self.counters;
+        if counters.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(LogicError::ProviderUnavailable("mock failure".into()));
+        }
+        let _in_flight = InFlight::enter(&counters.in_flight, &counters.peak_in_flight);
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        counters.answered.fetch_add(1, Ordering::SeqCst);
+        Ok(QueryResult {
+            query_id: query.id,
+            provider_used: "claude".into(),
+            content: query.content,
+            latency_ms: self.delay.as_millis() as u64,
+            network_ms: self.delay.as_millis() as u64,
+            input_tokens: self.usage.0,
+            output_tokens: self.usage.1,
+            metadata: Default::default(),
+        })
+    }
+
+    async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+        let mut results = Vec::with_capacity(queries.len());
+        for query in queries {
+            results.push(self.query(query).await);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Role;
+
+    #[tokio::test]
+    async fn replies_and_counts_calls() {
+        let mock = MockAdapter::new(Provider::Claude).with_reply("hello there");
+        let msgs = vec![Message { role: Role::User, content: "hi".into() }];
+        let resp = mock.chat(&msgs).await.unwrap();
+        assert_eq!(resp.content, "hello there");
+        assert_eq!(resp.output_tokens, 2);
+        assert_eq!(mock.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn multimodal_reply_keeps_every_block() {
+        let blocks = vec![
+            ContentBlock::Text("Here is the chart: ".into()),
+            ContentBlock::Image { mime: "image/png".into(), data: "iVBORw0KGgo=".into() },
+            ContentBlock::Text("as requested.".into()),
+        ];
+        let mock = MockAdapter::new(Provider::Gemini).with_blocks(blocks.clone());
+        let resp = mock.chat(&[]).await.unwrap();
+        assert_eq!(resp.blocks, blocks);
+        assert_eq!(resp.content, "Here is the chart: as requested.");
+
+        let json = serde_json::to_value(&resp).unwrap();
+        assert_eq!(json["blocks"][1]["image"]["mime"], "image/png");
+        let back: ModelResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(back.blocks, blocks);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn total_time_includes_network_time() {
+        let mock = MockAdapter::new(Provider::Claude)
+            .with_delay(Duration::from_millis(40))
+            .with_processing_delay(Duration::from_millis(15));
+        let resp = mock.chat(&[]).await.unwrap();
+        assert_eq!(resp.network_ms, 40);
+        assert_eq!(resp.total_ms(), 55);
+        assert!(resp.total_ms() >= resp.network_ms);
+        assert_eq!(resp.latency_ms, resp.total_ms());
+
+        let result = crate::logic::QueryResult::from_response(uuid::Uuid::nil(), resp);
+        assert_eq!((result.network_ms, result.total_ms()), (40, 55));
+    }
+
+    #[tokio::test]
+    async fn failing_mock_fails_health_check() {
+        let mock = MockAdapter::new(Provider::Grok).failing("connection refused");
+        assert!(mock.health_check().await.is_err());
+        assert!(mock.chat(&[]).await.is_err());
+    }
+}
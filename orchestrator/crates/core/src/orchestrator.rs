@@ -1,39 +1,643 @@
 use std::collections::HashMap;
-use uuid::Uuid;
 use crate::agent::Agent;
-use crate::bus::MessageBus;
+use crate::bus::{BusSubscriber, MessageBus};
+use crate::protocol::{LogEntry, LogLevel, LogSink, MemoryLogSink, SystemEvent};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+
+/// Delay between ticks of the orchestration loop.
+pub const DEFAULT_TICK_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct Orchestrator {
-    agents: HashMap<Uuid, Box<dyn Agent>>,
+    agents: HashMap<String, Box<dyn Agent>>,
+    interests: HashMap<String, Vec<String>>,
     bus: Arc<MessageBus>,
+    tick_interval: Duration,
+    idle_timeout: Option<Duration>,
+    shutdown: Arc<watch::Sender<bool>>,
+    log_sink: MemoryLogSink,
+    system: SystemHandle,
+    system_events: mpsc::UnboundedReceiver<SystemEvent>,
+}
+
+/// Requests a graceful stop of a running [`Orchestrator`].
+///
+/// The loop finishes the tick in progress, then `run` returns its
+/// [`ShutdownReport`].
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    signal: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.signal.send_replace(true);
+    }
+}
+
+/// Broadcasts [`SystemEvent`]s to the agents of a running [`Orchestrator`];
+/// see [`Orchestrator::broadcast_system`].
+#[derive(Debug, Clone)]
+pub struct SystemHandle {
+    events: mpsc::UnboundedSender<SystemEvent>,
+}
+
+impl SystemHandle {
+    pub fn broadcast(&self, event: SystemEvent) {
+        // The receiver lives as long as the orchestrator; once it is gone
+        // there is nobody left to tell.
+        let _ = self.events.send(event);
+    }
+}
+
+/// A tick or message delivery that returned an error. The loop keeps
+/// running after failures.
+#[derive(Debug, Clone, Serialize)]
+pub struct TickFailure {
+    pub agent_id: String,
+    /// The orchestrator tick (1-based) on which the agent failed.
+    pub tick: u64,
+    pub error: String,
+}
+
+/// Summary of a completed [`Orchestrator::run`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShutdownReport {
+    pub uptime: Duration,
+    /// Completed iterations of the orchestration loop.
+    pub total_ticks: u64,
+    /// Messages returned by agent ticks and published to the bus.
+    pub total_messages: u64,
+    pub per_agent_ticks: HashMap<String, u64>,
+    pub errors: Vec<TickFailure>,
+    /// Whether the loop stopped on its own after the
+    /// [idle timeout](Orchestrator::with_idle_timeout).
+    pub idle: bool,
 }
 
 impl Orchestrator {
     pub fn new() -> Self {
+        let (events, system_events) = mpsc::unbounded_channel();
         Self {
             agents: HashMap::new(),
+            interests: HashMap::new(),
             bus: Arc::new(MessageBus::new(1024)),
+            tick_interval: DEFAULT_TICK_INTERVAL,
+            idle_timeout: None,
+            shutdown: Arc::new(watch::Sender::new(false)),
+            log_sink: MemoryLogSink::new(),
+            system: SystemHandle { events },
+            system_events,
         }
     }
 
+    /// Use `bus` instead of the default in-memory bus (e.g. one with a journal).
+    pub fn with_bus(mut self, bus: MessageBus) -> Self {
+        self.bus = Arc::new(bus);
+        self
+    }
+
+    /// Use `sink` for orchestrator logs (e.g. one shared with the TUI).
+    pub fn with_log_sink(mut self, sink: MemoryLogSink) -> Self {
+        self.log_sink = sink;
+        self
+    }
+
+    pub fn with_tick_interval(mut self, interval: Duration) -> Self {
+        self.tick_interval = interval;
+        self
+    }
+
+    /// Shut down once `timeout` passes without any activity: no agent tick
+    /// returning a message, no message published on the [bus](Self::bus) by
+    /// anyone, no system event delivered, and no agent
+    /// [busy](Agent::is_busy) with tasks. For ephemeral deployments that
+    /// should exit when there is nothing left to do.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
     pub fn register_agent(&mut self, agent: Box<dyn Agent>) {
-        let id = agent.metadata().id;
+        let id = agent.metadata().id.clone();
+        self.interests.insert(id.clone(), agent.interests());
         self.agents.insert(id, agent);
     }
 
-    pub async fn run(&mut self) -> anyhow::Result<()> {
+    /// Handle that stops [`run`](Self::run) after the current tick.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { signal: self.shutdown.clone() }
+    }
+
+    /// Deliver `event` to the [`on_system`](Agent::on_system) of every agent,
+    /// at the start of the next round and ahead of its ticks and messages.
+    /// Events still queued when the loop stops are delivered before
+    /// [`run`](Self::run) returns.
+    pub fn broadcast_system(&self, event: SystemEvent) {
+        self.system.broadcast(event);
+    }
+
+    /// Handle for [`broadcast_system`](Self::broadcast_system) while
+    /// [`run`](Self::run) holds the orchestrator.
+    pub fn system_handle(&self) -> SystemHandle {
+        self.system.clone()
+    }
+
+    /// Tick every agent until shutdown is requested, publishing the messages
+    /// they return on the bus. After each round of ticks, every message is
+    /// passed to the `on_message` of each agent whose
+    /// [interests](Agent::interests) it matches. Agent errors are recorded in
+    /// the report rather than stopping the loop.
+    ///
+    /// Once every interested agent has handled a message without error, it is
+    /// [acknowledged](MessageBus::ack) on the bus, so a journal does not
+    /// replay it after a restart. A message some agent failed on stays
+    /// journaled.
+    ///
+    /// With an [idle timeout](Self::with_idle_timeout), the loop also stops
+    /// after the first round that finds the orchestrator idle for that long.
+    pub async fn run(&mut self) -> anyhow::Result<ShutdownReport> {
+        let started = Instant::now();
+        let mut stop = self.shutdown.subscribe();
+        let mut report = ShutdownReport::default();
+        let mut last_active = started;
+
         // Main orchestration loop
-        loop {
-            for agent in self.agents.values_mut() {
-                agent.tick().await?;
+        while !*stop.borrow_and_update() {
+            report.total_ticks += 1;
+            if self.deliver_system_events(&mut report).await {
+                last_active = Instant::now();
+            }
+            let mut round = Vec::new();
+            for (id, agent) in self.agents.iter_mut() {
+                *report.per_agent_ticks.entry(id.clone()).or_default() += 1;
+                match agent.tick().await {
+                    Ok(messages) => {
+                        report.total_messages += messages.len() as u64;
+                        for message in messages {
+                            // Nobody listening is not an error for the loop.
+                            let _ = self.bus.publish(message.clone()).await;
+                            round.push(message);
+                        }
+                    }
+                    Err(e) => report.errors.push(TickFailure {
+                        agent_id: id.clone(),
+                        tick: report.total_ticks,
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            for message in &round {
+                let mut handled = true;
+                for (id, agent) in self.agents.iter_mut() {
+                    let interested = self.interests[id].iter().any(|i| message.matches_interest(i));
+                    if interested && let Err(e) = agent.on_message(message.clone()).await {
+                        handled = false;
+                        report.errors.push(TickFailure {
+                            agent_id: id.clone(),
+                            tick: report.total_ticks,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+                if handled && let Err(e) = self.bus.ack(message.id).await {
+                    let warning = format!("could not ack message {}: {e}", message.id);
+                    self.log_sink.emit(&LogEntry::new(LogLevel::Warn, "orchestrator", warning));
+                }
+            }
+            if !round.is_empty() || self.agents.values().any(|agent| agent.is_busy()) {
+                last_active = Instant::now();
+            }
+            last_active = last_active.max(self.bus.last_activity());
+            if let Some(timeout) = self.idle_timeout
+                && last_active.elapsed() >= timeout
+            {
+                report.idle = true;
+                self.shutdown.send_replace(true);
+            }
+            if *stop.borrow_and_update() {
+                break;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.tick_interval) => {}
+                _ = stop.changed() => {}
             }
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
+
+        self.deliver_system_events(&mut report).await;
+        report.uptime = started.elapsed();
+        Ok(report)
+    }
+
+    /// Pass every queued system event to every agent, in broadcast order.
+    /// Returns whether there were any.
+    async fn deliver_system_events(&mut self, report: &mut ShutdownReport) -> bool {
+        let mut delivered = false;
+        while let Ok(event) = self.system_events.try_recv() {
+            delivered = true;
+            for (id, agent) in self.agents.iter_mut() {
+                if let Err(e) = agent.on_system(event.clone()).await {
+                    report.errors.push(TickFailure {
+                        agent_id: id.clone(),
+                        tick: report.total_ticks,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        delivered
     }
 
     pub fn bus(&self) -> Arc<MessageBus> {
         self.bus.clone()
     }
-}
\ No newline at end of file
+
+    pub fn log_sink(&self) -> &MemoryLogSink {
+        &self.log_sink
+    }
+
+    /// Subscribe `agent_id` to the bus, logging any messages it misses by
+    /// falling behind to the orchestrator's sink.
+    pub fn subscribe(&self, agent_id: impl Into<String>) -> BusSubscriber<MemoryLogSink> {
+        self.bus.subscribe_agent(agent_id, self.log_sink.clone())
+    }
+}
+
+impl Default for Orchestrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentMetadata;
+    use crate::capability::CapabilityInfo;
+    use crate::protocol::{Message, MessageKind};
+    use async_trait::async_trait;
+
+    /// A broadcast `Status` message for tick `tick`.
+    fn status(tick: u64) -> Message {
+        Message {
+            id: uuid::Uuid::new_v4(),
+            source: uuid::Uuid::nil(),
+            target: None,
+            kind: MessageKind::Status,
+            payload: serde_json::json!({ "tick": tick }),
+            timestamp: 0,
+        }
+    }
+
+    /// Emits one message per tick until `quiet_after`, fails on `fail_on`,
+    /// and requests shutdown on `stop_after`.
+    struct Ticker {
+        meta: AgentMetadata,
+        ticks: u64,
+        quiet_after: Option<u64>,
+        busy_until: Option<u64>,
+        fail_on: Option<u64>,
+        stop_after: Option<(u64, ShutdownHandle)>,
+    }
+
+    impl Ticker {
+        fn new(id: &str) -> Self {
+            Self {
+                meta: AgentMetadata {
+                    id: id.into(),
+                    name: id.into(),
+                    version: "0.1.0".into(),
+                    capabilities: Vec::new(),
+                },
+                ticks: 0,
+                quiet_after: None,
+                busy_until: None,
+                fail_on: None,
+                stop_after: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Agent for Ticker {
+        fn metadata(&self) -> &AgentMetadata {
+            &self.meta
+        }
+
+        async fn init(&mut self, _: Vec<CapabilityInfo>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn tick(&mut self) -> anyhow::Result<Vec<Message>> {
+            self.ticks += 1;
+            if let Some((n, handle)) = &self.stop_after
+                && self.ticks == *n
+            {
+                handle.shutdown();
+            }
+            if self.fail_on == Some(self.ticks) {
+                anyhow::bail!("tick {} failed", self.ticks);
+            }
+            if self.quiet_after.is_some_and(|n| self.ticks > n) {
+                return Ok(Vec::new());
+            }
+            Ok(vec![status(self.ticks)])
+        }
+
+        async fn on_message(&mut self, _: Message) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn is_busy(&self) -> bool {
+            self.busy_until.is_some_and(|n| self.ticks < n)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn report_counts_ticks_until_shutdown() {
+        let mut orch = Orchestrator::new();
+        let mut stopper = Ticker::new("stopper");
+        stopper.stop_after = Some((5, orch.shutdown_handle()));
+        let mut flaky = Ticker::new("flaky");
+        flaky.fail_on = Some(2);
+        orch.register_agent(Box::new(stopper));
+        orch.register_agent(Box::new(flaky));
+
+        let report = orch.run().await.unwrap();
+        assert_eq!(report.total_ticks, 5);
+        assert_eq!(report.per_agent_ticks["stopper"], 5);
+        assert_eq!(report.per_agent_ticks["flaky"], 5);
+        assert_eq!(report.total_messages, 9);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].agent_id, "flaky");
+        assert_eq!(report.errors[0].tick, 2);
+        assert_eq!(report.uptime, DEFAULT_TICK_INTERVAL * 4);
+    }
+
+    /// Records the messages delivered to it, failing on them if `refuse`.
+    struct Listener {
+        meta: AgentMetadata,
+        interests: Vec<String>,
+        received: Arc<std::sync::Mutex<Vec<Message>>>,
+        refuse: bool,
+    }
+
+    #[async_trait]
+    impl Agent for Listener {
+        fn metadata(&self) -> &AgentMetadata {
+            &self.meta
+        }
+
+        async fn init(&mut self, _: Vec<CapabilityInfo>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn tick(&mut self) -> anyhow::Result<Vec<Message>> {
+            Ok(Vec::new())
+        }
+
+        fn interests(&self) -> Vec<String> {
+            self.interests.clone()
+        }
+
+        async fn on_message(&mut self, message: Message) -> anyhow::Result<()> {
+            self.received.lock().unwrap().push(message);
+            if self.refuse {
+                anyhow::bail!("refused");
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_timeout_stops_the_loop_once_agents_go_quiet() {
+        let mut orch = Orchestrator::new()
+            .with_tick_interval(Duration::from_millis(10))
+            .with_idle_timeout(Duration::from_millis(100));
+        let mut ticker = Ticker::new("ticker");
+        ticker.quiet_after = Some(3);
+        orch.register_agent(Box::new(ticker));
+
+        let report = orch.run().await.unwrap();
+        assert!(report.idle);
+        assert_eq!(report.total_messages, 3);
+        // The last message came on the third tick, 20 ms in.
+        assert!(report.uptime >= Duration::from_millis(120));
+        assert!(report.uptime < Duration::from_millis(140));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bus_traffic_and_busy_agents_keep_the_loop_alive() {
+        let mut orch = Orchestrator::new()
+            .with_tick_interval(Duration::from_millis(10))
+            .with_idle_timeout(Duration::from_millis(100));
+        let mut worker = Ticker::new("worker");
+        worker.quiet_after = Some(0);
+        worker.busy_until = Some(20);
+        orch.register_agent(Box::new(worker));
+        // Another component publishes straight to the bus from 250 ms in,
+        // once the worker is done, until 600 ms in.
+        let bus = orch.bus();
+        let publisher = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            for tick in 0..8 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let _ = bus.publish(status(tick)).await;
+            }
+        });
+
+        let report = orch.run().await.unwrap();
+        publisher.await.unwrap();
+        assert!(report.idle);
+        assert_eq!(report.total_messages, 0);
+        // Kept alive by the busy worker for its first 200 ms, then by the
+        // bus until the last publish at 600 ms.
+        assert!(report.uptime >= Duration::from_millis(700), "{:?}", report.uptime);
+        assert!(report.uptime < Duration::from_millis(720), "{:?}", report.uptime);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn messages_reach_only_interested_agents() {
+        let mut orch = Orchestrator::new();
+        let mut ticker = Ticker::new("ticker");
+        ticker.stop_after = Some((1, orch.shutdown_handle()));
+        orch.register_agent(Box::new(ticker));
+
+        let mut inboxes = Vec::new();
+        for (id, interests) in [("status", "status"), ("weather", "weather"), ("all", "*")] {
+            let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+            orch.register_agent(Box::new(Listener {
+                meta: Ticker::new(id).meta,
+                interests: vec![interests.into()],
+                received: received.clone(),
+                refuse: false,
+            }));
+            inboxes.push(received);
+        }
+        // Publishes a weather command in the same round as the status message.
+        orch.register_agent(Box::new(Forecaster(Ticker::new("forecaster"))));
+
+        orch.run().await.unwrap();
+        let kinds = |i: usize| -> Vec<&'static str> {
+            inboxes[i].lock().unwrap().iter().map(|m| m.kind.as_str()).collect()
+        };
+        assert_eq!(kinds(0), ["status"]);
+        assert_eq!(kinds(1), ["command"]);
+        let mut all = kinds(2);
+        all.sort();
+        assert_eq!(all, ["command", "status"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn delivered_messages_are_acked_in_the_journal() {
+        use crate::bus::{BusJournal, MemoryJournal};
+        use crate::memory::InMemoryStore;
+
+        let journal = Arc::new(MemoryJournal::new(Arc::new(InMemoryStore::new())));
+        let bus = MessageBus::new(16).with_journal(journal.clone());
+        let mut orch = Orchestrator::new().with_bus(bus);
+        let mut ticker = Ticker::new("ticker");
+        ticker.stop_after = Some((2, orch.shutdown_handle()));
+        orch.register_agent(Box::new(ticker));
+        orch.register_agent(Box::new(Forecaster(Ticker::new("forecaster"))));
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        orch.register_agent(Box::new(Listener {
+            meta: Ticker::new("weather").meta,
+            interests: vec!["weather".into()],
+            received: received.clone(),
+            refuse: true,
+        }));
+
+        let report = orch.run().await.unwrap();
+        assert_eq!(report.total_messages, 4);
+        // Status messages were handled and acked; the refused commands stay
+        // journaled for replay.
+        let pending = journal.pending().await.unwrap();
+        assert!(pending.iter().all(|m| matches!(m.kind, MessageKind::Command)));
+        let refused: Vec<_> = received.lock().unwrap().iter().map(|m| m.id).collect();
+        assert_eq!(refused.len(), 2);
+        assert_eq!(pending.iter().map(|m| m.id).collect::<Vec<_>>(), refused);
+    }
+
+    /// Emits one `Command` on the "weather" topic per tick.
+    struct Forecaster(Ticker);
+
+    #[async_trait]
+    impl Agent for Forecaster {
+        fn metadata(&self) -> &AgentMetadata {
+            &self.0.meta
+        }
+
+        async fn init(&mut self, _: Vec<CapabilityInfo>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn tick(&mut self) -> anyhow::Result<Vec<Message>> {
+            let mut messages = self.0.tick().await?;
+            for m in &mut messages {
+                m.kind = MessageKind::Command;
+                m.payload = serde_json::json!({ "topic": "weather" });
+            }
+            Ok(messages)
+        }
+
+        fn interests(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        async fn on_message(&mut self, _: Message) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Logs its ticks, messages and system events in order; on tick
+    /// `drain_on`, broadcasts `Drain` and stops the orchestrator.
+    struct Recorder {
+        meta: AgentMetadata,
+        ticks: u64,
+        drain_on: Option<(u64, SystemHandle, ShutdownHandle)>,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Agent for Recorder {
+        fn metadata(&self) -> &AgentMetadata {
+            &self.meta
+        }
+
+        async fn init(&mut self, _: Vec<CapabilityInfo>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn tick(&mut self) -> anyhow::Result<Vec<Message>> {
+            self.ticks += 1;
+            self.log.lock().unwrap().push("tick".into());
+            if let Some((n, system, shutdown)) = &self.drain_on
+                && self.ticks == *n
+            {
+                system.broadcast(SystemEvent::Drain);
+                shutdown.shutdown();
+            }
+            Ok(vec![status(self.ticks)])
+        }
+
+        async fn on_message(&mut self, message: Message) -> anyhow::Result<()> {
+            self.log.lock().unwrap().push(format!("message:{}", message.kind.as_str()));
+            Ok(())
+        }
+
+        async fn on_system(&mut self, event: SystemEvent) -> anyhow::Result<()> {
+            self.log.lock().unwrap().push(format!("system:{event:?}"));
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn system_events_reach_every_agent_ahead_of_messages() {
+        let mut orch = Orchestrator::new();
+        let mut logs = Vec::new();
+        for id in ["first", "second"] {
+            let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let drain_on = (id == "first")
+                .then(|| (2, orch.system_handle(), orch.shutdown_handle()));
+            orch.register_agent(Box::new(Recorder {
+                meta: Ticker::new(id).meta,
+                ticks: 0,
+                drain_on,
+                log: log.clone(),
+            }));
+            logs.push(log);
+        }
+        // The quiet agent relies on the default handler.
+        orch.register_agent(Box::new(Ticker::new("quiet")));
+        orch.broadcast_system(SystemEvent::Reload);
+
+        let report = orch.run().await.unwrap();
+        assert_eq!(report.total_ticks, 2);
+        assert!(report.errors.is_empty());
+        for log in logs {
+            let log = log.lock().unwrap().clone();
+            // Reload comes before the first round's ticks and messages;
+            // Drain, broadcast on the last tick, arrives before run returns
+            // and after the final tick.
+            assert_eq!(log.first().map(String::as_str), Some("system:Reload"));
+            assert_eq!(log.last().map(String::as_str), Some("system:Drain"));
+            assert_eq!(log.iter().filter(|e| *e == "tick").count(), 2);
+            assert!(log.contains(&"message:status".to_owned()));
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_before_run_returns_immediately() {
+        let mut orch = Orchestrator::new();
+        orch.register_agent(Box::new(Ticker::new("idle")));
+        orch.shutdown_handle().shutdown();
+        let report = orch.run().await.unwrap();
+        assert_eq!(report.total_ticks, 0);
+        assert!(report.per_agent_ticks.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+//! PhaseGuard — compile-time enforcement of the task lifecycle.
+//!
+//! [`PhaseGuard<S>`] wraps a [`Task`] whose phase is tracked in the type `S`.
+//! Each transition consumes the guard and returns one in the next phase, so
+//! calling them out of order is a type error rather than a runtime
+//! [`TaskError`](crate::task::TaskError):
+//!
+//! ```
+//! use orchestrator_core::phase_guard::PhaseGuard;
+//! use orchestrator_core::protocol::TaskMeta;
+//! use serde_json::json;
+//!
+//! let meta = TaskMeta::new("doc", "query", "");
+//! let result = PhaseGuard::new(meta, json!({"prompt": "hi"}))
+//!     .initialize()
+//!     .begin_execution()
+//!     .validate(json!({"content": "hello"}))
+//!     .complete()
+//!     .result();
+//! assert_eq!(result.output["content"], "hello");
+//! ```
+//!
+//! The dynamic [`Task`] remains the serde/storage representation; convert
+//! with `Task::from(guard)` and `PhaseGuard::<S>::try_from(task)`.
+
+use std::marker::PhantomData;
+
+use crate::protocol::TaskMeta;
+use crate::task::{Task, TaskPhase, TaskResult};
+
+/// Type-level lifecycle phases.
+pub mod phase {
+    use crate::task::TaskPhase;
+
+    mod sealed {
+        pub trait Sealed {}
+    }
+
+    /// A lifecycle phase that can parameterize a [`PhaseGuard`](super::PhaseGuard).
+    pub trait Phase: sealed::Sealed {
+        /// The runtime phase this marker corresponds to.
+        const PHASE: TaskPhase;
+    }
+
+    macro_rules! phases {
+        ($($name:ident),*) => {$(
+            #[derive(Debug, Clone, Copy)]
+            pub struct $name;
+            impl sealed::Sealed for $name {}
+            impl Phase for $name {
+                const PHASE: TaskPhase = TaskPhase::$name;
+            }
+        )*};
+    }
+
+    phases!(Pending, Initialized, Executing, Validated, Completed);
+}
+
+use phase::{Completed, Executing, Initialized, Pending, Phase, Validated};
+
+/// A [`Task`] statically known to be in phase `S`.
+#[derive(Debug, Clone)]
+pub struct PhaseGuard<S: Phase> {
+    task: Task,
+    _phase: PhantomData<S>,
+}
+
+impl<S: Phase> PhaseGuard<S> {
+    fn advance<N: Phase>(self) -> PhaseGuard<N> {
+        PhaseGuard { task: self.task, _phase: PhantomData }
+    }
+
+    /// Read-only view of the underlying task.
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    /// Mark the task as `Failed`, leaving the typestate API.
+    pub fn fail(mut self) -> Task {
+        self.task.fail();
+        self.task
+    }
+
+    /// Mark the task as `Aborted` by the user, leaving the typestate API.
+    pub fn abort(mut self, reason: impl Into<String>) -> Task {
+        self.task.abort(reason);
+        self.task
+    }
+
+    /// Unwrap the dynamic task.
+    pub fn into_inner(self) -> Task {
+        self.task
+    }
+}
+
+impl PhaseGuard<Pending> {
+    /// Create a new task in the `Pending` phase.
+    pub fn new(meta: TaskMeta, input: serde_json::Value) -> Self {
+        Self { task: Task::new(meta, input), _phase: PhantomData }
+    }
+
+    pub fn initialize(mut self) -> PhaseGuard<Initialized> {
+        self.task.initialize().expect("typestate guarantees Pending");
+        self.advance()
+    }
+}
+
+impl PhaseGuard<Initialized> {
+    pub fn begin_execution(mut self) -> PhaseGuard<Executing> {
+        self.task.begin_execution().expect("typestate guarantees Initialized");
+        self.advance()
+    }
+}
+
+impl PhaseGuard<Executing> {
+    pub fn validate(mut self, output: serde_json::Value) -> PhaseGuard<Validated> {
+        self.task.validate(output).expect("typestate guarantees Executing");
+        self.advance()
+    }
+}
+
+impl PhaseGuard<Validated> {
+    pub fn complete(mut self) -> PhaseGuard<Completed> {
+        self.task.complete().expect("typestate guarantees Validated");
+        self.advance()
+    }
+}
+
+impl PhaseGuard<Completed> {
+    /// The outcome of the completed task.
+    pub fn result(&self) -> TaskResult {
+        TaskResult {
+            task_id: self.task.id,
+            output: self.task.output.clone().unwrap_or_default(),
+            phase: self.task.phase,
+            completed_at: self.task.updated_at,
+            total_input_tokens: 0,
+            total_output_tokens: 0,
+            estimated_cost: 0.0,
+        }
+    }
+}
+
+impl<S: Phase> From<PhaseGuard<S>> for Task {
+    fn from(guard: PhaseGuard<S>) -> Self {
+        guard.task
+    }
+}
+
+/// Recover a typed guard from a stored task. Fails, returning the task
+/// unchanged, if its runtime phase is not `S`.
+impl<S: Phase> TryFrom<Task> for PhaseGuard<S> {
+    type Error = Task;
+
+    fn try_from(task: Task) -> Result<Self, Task> {
+        if task.phase == S::PHASE {
+            Ok(Self { task, _phase: PhantomData })
+        } else {
+            Err(task)
+        }
+    }
+}
+
+impl TaskPhase {
+    /// Whether `self` is the phase named by marker `S`.
+    pub fn is<S: Phase>(self) -> bool {
+        self == S::PHASE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn meta() -> TaskMeta {
+        TaskMeta::new("test", "unit_test", "typestate")
+    }
+
+    #[test]
+    fn typed_lifecycle_matches_dynamic_phases() {
+        let pending = PhaseGuard::new(meta(), json!({}));
+        assert_eq!(pending.task().phase, TaskPhase::Pending);
+        let executing = pending.initialize().begin_execution();
+        assert_eq!(executing.task().phase, TaskPhase::Executing);
+        let done = executing.validate(json!({"ok": true})).complete();
+        assert_eq!(done.task().phase, TaskPhase::Completed);
+        assert_eq!(done.result().output, json!({"ok": true}));
+    }
+
+    #[test]
+    fn converts_to_and_from_dynamic_task() {
+        let initialized = PhaseGuard::new(meta(), json!({})).initialize();
+        let task: Task = initialized.into();
+        assert!(task.phase.is::<Initialized>());
+
+        // Wrong phase hands the task back untouched.
+        let task = PhaseGuard::<Pending>::try_from(task).unwrap_err();
+        let guard = PhaseGuard::<Initialized>::try_from(task).unwrap();
+        assert_eq!(guard.begin_execution().task().phase, TaskPhase::Executing);
+    }
+
+    #[test]
+    fn fail_leaves_typestate() {
+        let task = PhaseGuard::new(meta(), json!({})).initialize().fail();
+        assert_eq!(task.phase, TaskPhase::Failed);
+    }
+}
@@ -0,0 +1,225 @@
+//! PooledAdapter — spreads one provider's traffic over several backends.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::adapter::{
+    Adapter, AdapterError, ConfigError, Feature, Message, ModelResponse, ModelTier, Provider,
+};
+use crate::prompt::Prompt;
+
+/// [`Adapter`] that round-robins `chat` calls across backends serving the
+/// same [`Provider`], e.g. one per API key, to raise the rate-limit ceiling.
+///
+/// A backend that answers [`AdapterError::RateLimited`] is skipped until its
+/// `retry_after_ms` has passed, and the call moves on to the next backend.
+/// Only when every backend is limited does `chat` fail, with the shortest
+/// remaining wait.
+pub struct PooledAdapter {
+    provider: Provider,
+    backends: Vec<Arc<dyn Adapter>>,
+    next: AtomicUsize,
+    limited_until: Mutex<Vec<Option<Instant>>>,
+}
+
+impl PooledAdapter {
+    /// Pool `backends`, which must be non-empty and all serve one provider.
+    pub fn new(backends: Vec<Arc<dyn Adapter>>) -> Result<Self, ConfigError> {
+        let provider = backends.first().ok_or(ConfigError::EmptyPool)?.provider();
+        if let Some(other) = backends.iter().find(|b| b.provider() != provider) {
+            return Err(ConfigError::MixedPool { expected: provider, found: other.provider() });
+        }
+        Ok(Self {
+            provider,
+            limited_until: Mutex::new(vec![None; backends.len()]),
+            backends,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Number of pooled backends.
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    /// Always `false`; a pool has at least one backend.
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    /// Backends not currently rate limited.
+    pub fn available(&self) -> usize {
+        let now = Instant::now();
+        self.limited_until.lock().unwrap().iter().filter(|u| !is_limited(**u, now)).count()
+    }
+}
+
+/// Longest a rate-limited backend is skipped, however long it asks for;
+/// after that it is probed again.
+pub const MAX_RATE_LIMIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// When a backend that asked to wait `retry_after_ms` at `now` may be tried
+/// again, capped at [`MAX_RATE_LIMIT`].
+fn limited_until(now: Instant, retry_after_ms: u64) -> Instant {
+    let window = Duration::from_millis(retry_after_ms).min(MAX_RATE_LIMIT);
+    // Only out of range if the clock itself is near its end; try again then.
+    now.checked_add(window).unwrap_or(now)
+}
+
+fn is_limited(until: Option<Instant>, now: Instant) -> bool {
+    until.is_some_and(|until| until > now)
+}
+
+#[async_trait]
+impl Adapter for PooledAdapter {
+    fn provider(&self) -> Provider {
+        self.provider
+    }
+
+    /// The tier of the first backend.
+    fn tier(&self) -> ModelTier {
+        self.backends[0].tier()
+    }
+
+    fn model(&self) -> Option<String> {
+        self.backends[0].model()
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.backends[0].features()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        self.chat_prompt(&Prompt { system: None, messages: messages.to_vec() }).await
+    }
+
+    async fn chat_prompt(&self, prompt: &Prompt) -> Result<ModelResponse, AdapterError> {
+        let n = self.backends.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % n;
+        for i in (0..n).map(|k| (start + k) % n) {
+            if is_limited(self.limited_until.lock().unwrap()[i], Instant::now()) {
+                continue;
+            }
+            match self.backends[i].chat_prompt(prompt).await {
+                Err(AdapterError::RateLimited { retry_after_ms }) => {
+                    let until = limited_until(Instant::now(), retry_after_ms);
+                    self.limited_until.lock().unwrap()[i] = Some(until);
+                }
+                other => return other,
+            }
+        }
+
+        let now = Instant::now();
+        let soonest = self.limited_until.lock().unwrap().iter().flatten().min().copied();
+        let wait = soonest.map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+        Err(AdapterError::RateLimited { retry_after_ms: wait.as_millis() as u64 })
+    }
+
+    /// Healthy when any backend is.
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        let mut last = Ok(());
+        for backend in &self.backends {
+            last = backend.health_check().await;
+            if last.is_ok() {
+                break;
+            }
+        }
+        last
+    }
+
+    /// Warms every backend concurrently; ready when any of them is.
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        let results = futures::future::join_all(self.backends.iter().map(|b| b.warm_up())).await;
+        let mut last = Ok(());
+        for result in results {
+            if result.is_ok() {
+                return Ok(());
+            }
+            last = result;
+        }
+        last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockAdapter;
+
+    fn pool(mocks: &[Arc<MockAdapter>]) -> PooledAdapter {
+        PooledAdapter::new(mocks.iter().map(|m| m.clone() as Arc<dyn Adapter>).collect()).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn traffic_skips_rate_limited_backend() {
+        let a = Arc::new(MockAdapter::new(Provider::Claude));
+        let limited = Arc::new(MockAdapter::new(Provider::Claude).rate_limited(1_000));
+        let c = Arc::new(MockAdapter::new(Provider::Claude));
+        let pool = pool(&[a.clone(), limited.clone(), c.clone()]);
+        assert_eq!(pool.provider(), Provider::Claude);
+
+        for _ in 0..6 {
+            pool.chat(&[]).await.unwrap();
+        }
+        // Probed once, then skipped; its turn went to the next backend.
+        assert_eq!(limited.calls(), 1);
+        assert_eq!((a.calls(), c.calls()), (2, 4));
+        assert_eq!(pool.available(), 2);
+
+        // Once the limit expires the backend is tried again.
+        tokio::time::advance(Duration::from_millis(1_000)).await;
+        assert_eq!(pool.available(), 3);
+        pool.chat(&[]).await.unwrap();
+        pool.chat(&[]).await.unwrap();
+        assert_eq!(limited.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fully_limited_pool_reports_shortest_wait() {
+        let slow = Arc::new(MockAdapter::new(Provider::Claude).rate_limited(5_000));
+        let fast = Arc::new(MockAdapter::new(Provider::Claude).rate_limited(2_000));
+        let pool = pool(&[slow, fast]);
+
+        let err = pool.chat(&[]).await.unwrap_err();
+        assert!(matches!(err, AdapterError::RateLimited { retry_after_ms: 2_000 }));
+        tokio::time::advance(Duration::from_millis(500)).await;
+        let err = pool.chat(&[]).await.unwrap_err();
+        assert!(matches!(err, AdapterError::RateLimited { retry_after_ms: 1_500 }));
+        assert!(pool.health_check().await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn huge_retry_after_is_capped_instead_of_overflowing() {
+        let limited = Arc::new(MockAdapter::new(Provider::Claude).rate_limited(u64::MAX));
+        let pool = pool(std::slice::from_ref(&limited));
+
+        let err = pool.chat(&[]).await.unwrap_err();
+        let cap = MAX_RATE_LIMIT.as_millis() as u64;
+        assert!(
+            matches!(err, AdapterError::RateLimited { retry_after_ms } if retry_after_ms == cap)
+        );
+        assert_eq!(pool.available(), 0);
+
+        tokio::time::advance(MAX_RATE_LIMIT).await;
+        assert_eq!(pool.available(), 1);
+        assert!(pool.chat(&[]).await.is_err());
+        assert_eq!(limited.calls(), 2);
+    }
+
+    #[test]
+    fn pool_requires_one_provider() {
+        assert!(matches!(PooledAdapter::new(Vec::new()), Err(ConfigError::EmptyPool)));
+        let mixed: Vec<Arc<dyn Adapter>> = vec![
+            Arc::new(MockAdapter::new(Provider::Claude)),
+            Arc::new(MockAdapter::new(Provider::Grok)),
+        ];
+        assert!(matches!(
+            PooledAdapter::new(mixed),
+            Err(ConfigError::MixedPool { expected: Provider::Claude, found: Provider::Grok })
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+//! Prompt — provider-aware assembly of the conversation sent for a query.
+//!
+//! Providers disagree on where the system prompt goes: Anthropic and Google
+//! take it as a top-level request field, while OpenAI-compatible APIs expect
+//! a leading `system`-role message. [`PromptAssembler`] knows each
+//! provider's convention and builds a [`Prompt`] in the right shape.
+
+use std::collections::HashMap;
+
+use crate::adapter::{Message, Provider, Role};
+use crate::logic::Query;
+
+/// Where a provider expects the system prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemPlacement {
+    /// A separate request field, alongside the messages.
+    TopLevel,
+    /// A leading [`Role::System`] message.
+    Message,
+}
+
+/// A conversation shaped for one provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    /// The system prompt, when the provider takes it as a top-level field.
+    pub system: Option<String>,
+    pub messages: Vec<Message>,
+}
+
+impl Prompt {
+    /// Split leading system messages of `messages` into a top-level system
+    /// prompt, joined by blank lines — what a [`SystemPlacement::TopLevel`]
+    /// adapter sends.
+    pub fn from_messages(messages: &[Message]) -> Self {
+        let split = messages.iter().position(|m| m.role != Role::System).unwrap_or(messages.len());
+        let (system, rest) = messages.split_at(split);
+        let system = (!system.is_empty())
+            .then(|| system.iter().map(|m| m.content.as_str()).collect::<Vec<_>>().join("\n\n"));
+        Self { system, messages: rest.to_vec() }
+    }
+
+    /// Flatten into the message list taken by
+    /// [`Adapter::chat`](crate::adapter::Adapter::chat), a top-level system
+    /// prompt becoming a leading system message.
+    pub fn into_messages(self) -> Vec<Message> {
+        let system = self.system.map(|content| Message { role: Role::System, content });
+        system.into_iter().chain(self.messages).collect()
+    }
+}
+
+/// Builds each provider's [`Prompt`] for a [`Query`].
+#[derive(Debug, Clone)]
+pub struct PromptAssembler {
+    placements: HashMap<Provider, SystemPlacement>,
+}
+
+impl Default for PromptAssembler {
+    /// Claude and Gemini take a top-level system prompt; the
+    /// OpenAI-compatible providers take a system message.
+    fn default() -> Self {
+        Self::new()
+            .with_placement(Provider::Claude, SystemPlacement::TopLevel)
+            .with_placement(Provider::Gemini, SystemPlacement::TopLevel)
+    }
+}
+
+impl PromptAssembler {
+    /// An assembler that gives every provider a system message.
+    pub fn new() -> Self {
+        Self { placements: HashMap::new() }
+    }
+
+    /// Override where `provider` expects the system prompt.
+    pub fn with_placement(mut self, provider: Provider, placement: SystemPlacement) -> Self {
+        self.placements.insert(provider, placement);
+        self
+    }
+
+    pub fn placement(&self, provider: Provider) -> SystemPlacement {
+        self.placements.get(&provider).copied().unwrap_or(SystemPlacement::Message)
+    }
+
+    /// The prompt for sending `query` to `provider`.
+    pub fn assemble(&self, provider: Provider, query: &Query) -> Prompt {
+        let user = Message { role: Role::User, content: query.content.clone() };
+        let system = query.system_context.clone();
+        match self.placement(provider) {
+            SystemPlacement::TopLevel => Prompt { system, messages: vec![user] },
+            SystemPlacement::Message => {
+                let system = system.map(|content| Message { role: Role::System, content });
+                Prompt { system: None, messages: system.into_iter().chain([user]).collect() }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message { role, content: content.into() }
+    }
+
+    #[test]
+    fn places_system_prompt_per_provider() {
+        let assembler = PromptAssembler::default();
+        let query = Query::new("what is 2+2?").with_system("answer tersely");
+
+        let claude = assembler.assemble(Provider::Claude, &query);
+        assert_eq!(claude.system.as_deref(), Some("answer tersely"));
+        assert_eq!(claude.messages, [message(Role::User, "what is 2+2?")]);
+
+        let grok = assembler.assemble(Provider::Grok, &query);
+        assert_eq!(grok.system, None);
+        assert_eq!(
+            grok.messages,
+            [message(Role::System, "answer tersely"), message(Role::User, "what is 2+2?")]
+        );
+
+        // Both flatten to the same conversation for adapters without a
+        // system field.
+        assert_eq!(claude.clone().into_messages(), grok.into_messages());
+        assert_eq!(Prompt::from_messages(&claude.clone().into_messages()), claude);
+    }
+
+    #[test]
+    fn placement_can_be_overridden() {
+        let assembler =
+            PromptAssembler::default().with_placement(Provider::Claude, SystemPlacement::Message);
+        let query = Query::new("hi");
+        assert_eq!(assembler.placement(Provider::Claude), SystemPlacement::Message);
+        assert_eq!(assembler.placement(Provider::Gemini), SystemPlacement::TopLevel);
+        let prompt = assembler.assemble(Provider::Gemini, &query);
+        assert_eq!((prompt.system, prompt.messages.len()), (None, 1));
+    }
+}
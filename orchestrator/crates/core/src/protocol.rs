@@ -1,6 +1,16 @@
+//! Protocol — wire messages, task metadata, and the coherent logging standard.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use uuid::Uuid;
 
+use crate::text::{from_hex, to_hex};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageKind {
     Intent,
@@ -10,6 +20,19 @@ pub enum MessageKind {
     Error,
 }
 
+impl MessageKind {
+    /// Lowercase name, as used for agent interests.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Intent => "intent",
+            Self::Status => "status",
+            Self::Data => "data",
+            Self::Command => "command",
+            Self::Error => "error",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub id: Uuid,
@@ -18,4 +41,741 @@ pub struct Message {
     pub kind: MessageKind,
     pub payload: serde_json::Value,
     pub timestamp: i64,
-}
\ No newline at end of file
+}
+
+impl Message {
+    /// The `topic` string of the payload, if it has one.
+    pub fn topic(&self) -> Option<&str> {
+        self.payload.get("topic")?.as_str()
+    }
+
+    /// Whether an agent with `interest` should receive this message:
+    /// `interest` is `"*"`, the [kind name](MessageKind::as_str), or the
+    /// payload [topic](Self::topic).
+    pub fn matches_interest(&self, interest: &str) -> bool {
+        interest == crate::agent::ALL_MESSAGES
+            || interest == self.kind.as_str()
+            || self.topic() == Some(interest)
+    }
+}
+
+/// An orchestrator-wide event every agent is told about, ahead of ordinary
+/// messages; see [`Orchestrator::broadcast_system`].
+///
+/// [`Orchestrator::broadcast_system`]: crate::orchestrator::Orchestrator::broadcast_system
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemEvent {
+    /// Reload configuration and resources from their sources.
+    Reload,
+    /// Finish the work in hand and take on no more; a shutdown follows.
+    Drain,
+    /// Apply the given configuration.
+    ConfigUpdate(serde_json::Value),
+}
+
+/// Version of the [`TaskMeta`] and [`LogEntry`] formats this build writes.
+///
+/// Records persisted before versioning carry no `schema_version` and are
+/// version 0. Deserializing migrates older records up to this version;
+/// records from a newer build keep theirs.
+pub const SCHEMA_VERSION: u16 = 1;
+
+/// The version a record of `version` has once migrated.
+fn migrated(version: u16) -> u16 {
+    // v0 -> v1 only added `schema_version` itself; later steps that reshape
+    // fields go in the `From<...Wire>` impls.
+    version.max(SCHEMA_VERSION)
+}
+
+/// Task Metadata Schema — attached to every [`Task`](crate::task::Task).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "TaskMetaWire")]
+pub struct TaskMeta {
+    /// Who or what created the task (agent name, "tui", "api", ...).
+    pub origin: String,
+    /// Task kind used for routing and discovery (e.g. "query").
+    pub kind: String,
+    /// Human-readable description.
+    pub description: String,
+    /// Format version; see [`SCHEMA_VERSION`].
+    pub schema_version: u16,
+}
+
+impl TaskMeta {
+    pub fn new(
+        origin: impl Into<String>,
+        kind: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            origin: origin.into(),
+            kind: kind.into(),
+            description: description.into(),
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+}
+
+/// [`TaskMeta`] as persisted by any schema version.
+#[derive(Deserialize)]
+struct TaskMetaWire {
+    origin: String,
+    kind: String,
+    description: String,
+    #[serde(default)]
+    schema_version: u16,
+}
+
+impl From<TaskMetaWire> for TaskMeta {
+    fn from(wire: TaskMetaWire) -> Self {
+        Self {
+            origin: wire.origin,
+            kind: wire.kind,
+            description: wire.description,
+            schema_version: migrated(wire.schema_version),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Coherent Logging Standard
+// ---------------------------------------------------------------------------
+
+/// Log severity, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Trace => write!(f, "TRACE"),
+            Self::Debug => write!(f, "DEBUG"),
+            Self::Info => write!(f, "INFO"),
+            Self::Warn => write!(f, "WARN"),
+            Self::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// A single structured log entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "LogEntryWire")]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    /// Emitting subsystem (e.g. "app", "task", "logic").
+    pub source: String,
+    pub message: String,
+    /// Optional structured payload.
+    pub data: Option<serde_json::Value>,
+    /// Id of the task this entry belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    /// Format version; see [`SCHEMA_VERSION`].
+    pub schema_version: u16,
+}
+
+/// [`LogEntry`] as persisted by any schema version.
+#[derive(Deserialize)]
+struct LogEntryWire {
+    timestamp: DateTime<Utc>,
+    level: LogLevel,
+    source: String,
+    message: String,
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    correlation_id: Option<Uuid>,
+    #[serde(default)]
+    schema_version: u16,
+}
+
+impl From<LogEntryWire> for LogEntry {
+    fn from(wire: LogEntryWire) -> Self {
+        Self {
+            timestamp: wire.timestamp,
+            level: wire.level,
+            source: wire.source,
+            message: wire.message,
+            data: wire.data,
+            correlation_id: wire.correlation_id,
+            schema_version: migrated(wire.schema_version),
+        }
+    }
+}
+
+impl LogEntry {
+    /// Create a new entry timestamped now.
+    pub fn new(level: LogLevel, source: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            level,
+            source: source.into(),
+            message: message.into(),
+            data: None,
+            correlation_id: None,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+
+    /// Attach a structured payload.
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Tag the entry with a task correlation id.
+    pub fn with_correlation(mut self, id: Uuid) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+}
+
+/// Destination for [`LogEntry`] values.
+pub trait LogSink: Send + Sync {
+    /// Record a single entry.
+    fn emit(&self, entry: &LogEntry);
+}
+
+/// In-memory [`LogSink`] that retains every entry, in emission order.
+///
+/// Clones share the same buffer.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryLogSink {
+    entries: Arc<Mutex<Vec<LogEntry>>>,
+}
+
+impl MemoryLogSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of all entries emitted so far.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Entries emitted after the first `from`, so a reader can follow the
+    /// sink without copying what it has already seen.
+    pub fn entries_since(&self, from: usize) -> Vec<LogEntry> {
+        self.entries.lock().unwrap().get(from..).map(<[_]>::to_vec).unwrap_or_default()
+    }
+
+    /// Number of entries emitted so far.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entries tagged with `correlation_id`, in emission order.
+    pub fn entries_for(&self, correlation_id: Uuid) -> Vec<LogEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.correlation_id == Some(correlation_id))
+            .cloned()
+            .collect()
+    }
+}
+
+impl LogSink for MemoryLogSink {
+    fn emit(&self, entry: &LogEntry) {
+        self.entries.lock().unwrap().push(entry.clone());
+    }
+}
+
+/// [`LogSink`] decorator that tags every entry with a task's correlation id
+/// before forwarding it.
+#[derive(Debug, Clone)]
+pub struct TaskLogSink<S> {
+    inner: S,
+    task_id: Uuid,
+}
+
+impl<S: LogSink> TaskLogSink<S> {
+    pub fn new(inner: S, task_id: Uuid) -> Self {
+        Self { inner, task_id }
+    }
+
+    /// The correlation id applied to forwarded entries.
+    pub fn task_id(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl<S: LogSink> LogSink for TaskLogSink<S> {
+    fn emit(&self, entry: &LogEntry) {
+        self.inner.emit(&entry.clone().with_correlation(self.task_id));
+    }
+}
+
+/// [`LogSink`] decorator that collapses runs of identical entries.
+///
+/// The first of a run of entries with the same `(level, source, message)`
+/// is forwarded at once. Repeats whose timestamps fall within `window` of it
+/// are only counted, and reported as one summary entry once the run ends,
+/// e.g. `"connection refused (repeated 41 more times)"`. A run ends when a
+/// different entry arrives, when a repeat falls outside the window, on
+/// [`flush`](Self::flush), or when the sink is dropped.
+pub struct DedupSink<S: LogSink> {
+    inner: S,
+    window: chrono::Duration,
+    /// The first entry of the current run and how many repeats followed it.
+    run: Mutex<Option<(LogEntry, usize)>>,
+}
+
+impl<S: LogSink> DedupSink<S> {
+    pub fn new(inner: S, window: std::time::Duration) -> Self {
+        Self {
+            inner,
+            window: chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX),
+            run: Mutex::new(None),
+        }
+    }
+
+    /// End the current run, forwarding its summary if it had repeats.
+    pub fn flush(&self) {
+        if let Some(run) = self.run.lock().unwrap().take() {
+            self.summarize(run);
+        }
+    }
+
+    fn summarize(&self, (mut entry, repeats): (LogEntry, usize)) {
+        if repeats > 0 {
+            let times = if repeats == 1 { "time" } else { "times" };
+            entry.message = format!("{} (repeated {repeats} more {times})", entry.message);
+            entry.timestamp = Utc::now();
+            self.inner.emit(&entry);
+        }
+    }
+}
+
+impl<S: LogSink> LogSink for DedupSink<S> {
+    fn emit(&self, entry: &LogEntry) {
+        let mut run = self.run.lock().unwrap();
+        if let Some((first, repeats)) = run.as_mut()
+            && first.level == entry.level
+            && first.source == entry.source
+            && first.message == entry.message
+            && entry.timestamp - first.timestamp < self.window
+        {
+            *repeats += 1;
+            return;
+        }
+        // Still holding the lock, so the summary precedes the new entry.
+        if let Some(ended) = run.replace((entry.clone(), 0)) {
+            self.summarize(ended);
+        }
+        self.inner.emit(entry);
+    }
+}
+
+impl<S: LogSink> Drop for DedupSink<S> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Called by [`AlertSink`] with the entry that first triggers it.
+pub type AlertHook = Arc<dyn Fn(&LogEntry) + Send + Sync>;
+
+/// [`LogSink`] decorator that raises an alert when an entry at or above a
+/// threshold level is logged, e.g. so a CI harness can exit non-zero once an
+/// `ERROR` has been seen.
+///
+/// Every entry is forwarded unchanged. Clones share their counts, so keep
+/// one to check [`triggered`](Self::triggered) after handing another to the
+/// code under test.
+#[derive(Clone)]
+pub struct AlertSink<S> {
+    inner: S,
+    threshold: LogLevel,
+    count: Arc<AtomicUsize>,
+    on_trigger: Option<AlertHook>,
+}
+
+impl<S: LogSink> AlertSink<S> {
+    /// Alert on entries at `threshold` or more severe.
+    pub fn new(inner: S, threshold: LogLevel) -> Self {
+        Self { inner, threshold, count: Arc::default(), on_trigger: None }
+    }
+
+    /// Call `hook` with the first entry that triggers the alert, once.
+    pub fn on_trigger(mut self, hook: impl Fn(&LogEntry) + Send + Sync + 'static) -> Self {
+        self.on_trigger = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn threshold(&self) -> LogLevel {
+        self.threshold
+    }
+
+    /// Whether any entry has reached the threshold.
+    pub fn triggered(&self) -> bool {
+        self.count() > 0
+    }
+
+    /// Entries seen at or above the threshold.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+impl<S: LogSink> LogSink for AlertSink<S> {
+    fn emit(&self, entry: &LogEntry) {
+        if entry.level >= self.threshold
+            && self.count.fetch_add(1, Ordering::SeqCst) == 0
+            && let Some(hook) = &self.on_trigger
+        {
+            hook(entry);
+        }
+        self.inner.emit(entry);
+    }
+}
+
+/// Key under which [`HmacSink`] stores each entry's MAC in `data`.
+pub const MAC_FIELD: &str = "mac";
+
+/// [`LogSink`] decorator that makes a log tamper-evident.
+///
+/// Each forwarded entry carries, under `data.mac`, an HMAC-SHA-256 over the
+/// entry, bar its schema version, and the MAC of the entry before it, so
+/// modifying, reordering, or deleting any entry breaks the chain checked by
+/// [`verify_chain`]. Only the tail can be truncated undetected; compare
+/// against [`last_mac`](Self::last_mac) to catch that. Non-object `data` is wrapped as `{"value": ...}` and any
+/// existing `mac` key is replaced.
+pub struct HmacSink<S> {
+    inner: S,
+    key: Vec<u8>,
+    last: Mutex<[u8; 32]>,
+}
+
+impl<S: LogSink> HmacSink<S> {
+    pub fn new(inner: S, key: impl Into<Vec<u8>>) -> Self {
+        Self { inner, key: key.into(), last: Mutex::new([0; 32]) }
+    }
+
+    /// Hex MAC of the most recent entry, or `None` before the first.
+    pub fn last_mac(&self) -> Option<String> {
+        let last = *self.last.lock().unwrap();
+        (last != [0; 32]).then(|| to_hex(&last))
+    }
+}
+
+impl<S: LogSink> LogSink for HmacSink<S> {
+    fn emit(&self, entry: &LogEntry) {
+        let mut entry = entry.clone();
+        let mut data = match entry.data.take() {
+            None => serde_json::Map::new(),
+            Some(serde_json::Value::Object(map)) => map,
+            Some(other) => serde_json::Map::from_iter([("value".to_string(), other)]),
+        };
+        data.remove(MAC_FIELD);
+        entry.data = Some(data.into());
+
+        // Hold the lock while forwarding so the chain follows emission order.
+        let mut last = self.last.lock().unwrap();
+        *last = entry_mac(&self.key, &last, &entry).finalize().into_bytes().into();
+        if let Some(serde_json::Value::Object(data)) = &mut entry.data {
+            data.insert(MAC_FIELD.into(), to_hex(&*last).into());
+        }
+        self.inner.emit(&entry);
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The part of a [`LogEntry`] its MAC covers, serialized in field order.
+///
+/// `schema_version` is left out: deserializing migrates it, so a chain
+/// persisted at any version, v0 included, still verifies once loaded.
+#[derive(Serialize)]
+struct MacInput<'a> {
+    timestamp: &'a DateTime<Utc>,
+    level: LogLevel,
+    source: &'a str,
+    message: &'a str,
+    data: &'a Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<Uuid>,
+}
+
+/// MAC of `entry` (whose `data` must not yet hold a MAC) chained to `prev`,
+/// ready to finalize or verify.
+fn entry_mac(key: &[u8], prev: &[u8; 32], entry: &LogEntry) -> HmacSha256 {
+    let input = MacInput {
+        timestamp: &entry.timestamp,
+        level: entry.level,
+        source: &entry.source,
+        message: &entry.message,
+        data: &entry.data,
+        correlation_id: entry.correlation_id,
+    };
+    let bytes = serde_json::to_vec(&input).expect("log entries serialize");
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(prev);
+    mac.update(&bytes);
+    mac
+}
+
+/// Check that `entries` are exactly the chain an [`HmacSink`] with `key`
+/// emitted, from its first entry and in order.
+pub fn verify_chain(entries: &[LogEntry], key: &[u8]) -> bool {
+    let mut prev = [0u8; 32];
+    for entry in entries {
+        let mut entry = entry.clone();
+        let Some(serde_json::Value::Object(data)) = &mut entry.data else {
+            return false;
+        };
+        let Some(serde_json::Value::String(mac)) = data.remove(MAC_FIELD) else {
+            return false;
+        };
+        let Some(Ok(tag)) = from_hex(&mac).map(<[u8; 32]>::try_from) else {
+            return false;
+        };
+        // Constant-time comparison.
+        if entry_mac(key, &prev, &entry).verify_slice(&tag).is_err() {
+            return false;
+        }
+        prev = tag;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn log_level_ordering() {
+        assert!(LogLevel::Trace < LogLevel::Debug);
+        assert!(LogLevel::Warn < LogLevel::Error);
+        assert_eq!(LogLevel::Warn.to_string(), "WARN");
+    }
+
+    #[test]
+    fn v0_records_load_at_the_current_schema_version() {
+        let meta: TaskMeta = serde_json::from_value(json!({
+            "origin": "api",
+            "kind": "query",
+            "description": "stored before versioning",
+        }))
+        .unwrap();
+        assert_eq!(meta, TaskMeta::new("api", "query", "stored before versioning"));
+        assert_eq!(meta.schema_version, SCHEMA_VERSION);
+
+        let entry: LogEntry = serde_json::from_value(json!({
+            "timestamp": "2025-01-01T00:00:00Z",
+            "level": "WARN",
+            "source": "task",
+            "message": "retrying",
+            "data": null,
+        }))
+        .unwrap();
+        assert_eq!(entry.schema_version, SCHEMA_VERSION);
+        assert_eq!((entry.level, entry.message.as_str()), (LogLevel::Warn, "retrying"));
+
+        // Written records carry their version, and a newer one is kept.
+        let written = serde_json::to_value(LogEntry::new(LogLevel::Info, "app", "hi")).unwrap();
+        assert_eq!(written["schema_version"], SCHEMA_VERSION);
+        let newer: TaskMeta = serde_json::from_value(json!({
+            "origin": "api",
+            "kind": "query",
+            "description": "",
+            "schema_version": SCHEMA_VERSION + 1,
+        }))
+        .unwrap();
+        assert_eq!(newer.schema_version, SCHEMA_VERSION + 1);
+    }
+
+    #[test]
+    fn memory_sink_collects_entries() {
+        let sink = MemoryLogSink::new();
+        sink.emit(&LogEntry::new(LogLevel::Info, "test", "first"));
+        sink.emit(&LogEntry::new(LogLevel::Error, "test", "second").with_data(json!({"n": 2})));
+
+        let entries = sink.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message, "first");
+        assert_eq!(entries[1].data, Some(json!({"n": 2})));
+
+        assert_eq!(sink.len(), 2);
+        let since = sink.entries_since(1);
+        assert_eq!(since.len(), 1);
+        assert_eq!(since[0].message, "second");
+        assert!(sink.entries_since(2).is_empty());
+        assert!(sink.entries_since(5).is_empty());
+    }
+
+    #[test]
+    fn task_sink_tags_correlation_id() {
+        let sink = MemoryLogSink::new();
+        let id = Uuid::new_v4();
+        let tagged = TaskLogSink::new(sink.clone(), id);
+        tagged.emit(&LogEntry::new(LogLevel::Info, "task", "tagged"));
+        sink.emit(&LogEntry::new(LogLevel::Info, "app", "untagged"));
+
+        let matching = sink.entries_for(id);
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].message, "tagged");
+        assert_eq!(sink.entries().len(), 2);
+    }
+
+    #[test]
+    fn dedup_sink_coalesces_repeats() {
+        let sink = MemoryLogSink::new();
+        let dedup = DedupSink::new(sink.clone(), std::time::Duration::from_secs(60));
+        for _ in 0..42 {
+            dedup.emit(&LogEntry::new(LogLevel::Error, "adapter", "connection refused"));
+        }
+        // The first is seen at once; the repeats wait for the run to end.
+        let messages = |sink: &MemoryLogSink| -> Vec<String> {
+            sink.entries().into_iter().map(|e| e.message).collect()
+        };
+        assert_eq!(messages(&sink), ["connection refused"]);
+        dedup.emit(&LogEntry::new(LogLevel::Info, "adapter", "connected"));
+        dedup.flush();
+
+        assert_eq!(
+            messages(&sink),
+            ["connection refused", "connection refused (repeated 41 more times)", "connected"]
+        );
+    }
+
+    #[test]
+    fn dedup_sink_splits_runs_at_window() {
+        let sink = MemoryLogSink::new();
+        let dedup = DedupSink::new(sink.clone(), std::time::Duration::from_secs(1));
+        let first = LogEntry::new(LogLevel::Warn, "bus", "lagging");
+        let mut late = first.clone();
+        late.timestamp = first.timestamp + chrono::Duration::seconds(5);
+
+        dedup.emit(&first);
+        dedup.emit(&first);
+        dedup.emit(&late);
+        drop(dedup);
+
+        let messages: Vec<String> = sink.entries().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["lagging", "lagging (repeated 1 more time)", "lagging"]);
+    }
+
+    #[test]
+    fn alert_sink_triggers_at_threshold_once() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let sink = AlertSink::new(MemoryLogSink::new(), LogLevel::Error).on_trigger({
+            let fired = fired.clone();
+            move |e: &LogEntry| fired.lock().unwrap().push(e.message.clone())
+        });
+        let handed_out = sink.clone();
+
+        for level in [LogLevel::Debug, LogLevel::Info, LogLevel::Warn] {
+            handed_out.emit(&LogEntry::new(level, "task", "fine"));
+        }
+        assert!(!sink.triggered());
+        assert_eq!(sink.count(), 0);
+
+        handed_out.emit(&LogEntry::new(LogLevel::Error, "task", "first failure"));
+        handed_out.emit(&LogEntry::new(LogLevel::Info, "task", "recovered"));
+        handed_out.emit(&LogEntry::new(LogLevel::Error, "task", "second failure"));
+        assert!(sink.triggered());
+        assert_eq!(sink.count(), 2);
+        assert_eq!(*fired.lock().unwrap(), ["first failure"]);
+        // Every entry still reaches the inner sink.
+        assert_eq!(sink.inner.entries().len(), 6);
+    }
+
+    #[test]
+    fn hmac_chain_verifies_untouched_log() {
+        let sink = MemoryLogSink::new();
+        let signed = HmacSink::new(sink.clone(), "audit-key");
+        assert_eq!(signed.last_mac(), None);
+        signed.emit(&LogEntry::new(LogLevel::Info, "task", "started"));
+        signed.emit(&LogEntry::new(LogLevel::Warn, "task", "slow").with_data(json!({"ms": 900})));
+        signed.emit(&LogEntry::new(LogLevel::Info, "task", "done").with_data(json!("ok")));
+
+        let entries = sink.entries();
+        assert!(verify_chain(&entries, b"audit-key"));
+        assert_eq!(entries[1].data.as_ref().unwrap()["ms"], 900);
+        assert_eq!(entries[2].data.as_ref().unwrap()["value"], "ok");
+        let last = entries[2].data.as_ref().unwrap()[MAC_FIELD].as_str();
+        assert_eq!(signed.last_mac().as_deref(), last);
+        assert!(!verify_chain(&entries, b"wrong-key"));
+        assert!(verify_chain(&[], b"audit-key"));
+    }
+
+    #[test]
+    fn hmac_chain_detects_tampering() {
+        let sink = MemoryLogSink::new();
+        let signed = HmacSink::new(sink.clone(), "audit-key");
+        for message in ["a", "b", "c"] {
+            signed.emit(&LogEntry::new(LogLevel::Info, "task", message));
+        }
+        let entries = sink.entries();
+
+        let mut modified = entries.clone();
+        modified[1].message = "B".into();
+        assert!(!verify_chain(&modified, b"audit-key"));
+
+        let mut deleted = entries.clone();
+        deleted.remove(1);
+        assert!(!verify_chain(&deleted, b"audit-key"));
+
+        let mut reordered = entries.clone();
+        reordered.swap(0, 1);
+        assert!(!verify_chain(&reordered, b"audit-key"));
+
+        for garbled in ["not hex", "abcd", &"0".repeat(64)] {
+            let mut forged = entries.clone();
+            forged[2].data = Some(json!({ MAC_FIELD: garbled }));
+            assert!(!verify_chain(&forged, b"audit-key"), "{garbled}");
+        }
+
+        let mut stripped = entries;
+        stripped[0].data = None;
+        assert!(!verify_chain(&stripped, b"audit-key"));
+    }
+
+    #[test]
+    fn hmac_chain_verifies_after_schema_migration() {
+        let sink = MemoryLogSink::new();
+        let signed = HmacSink::new(sink.clone(), "audit-key");
+        for message in ["a", "b"] {
+            let entry = LogEntry::new(LogLevel::Info, "task", message);
+            signed.emit(&entry.with_correlation(Uuid::nil()));
+        }
+        signed.emit(&LogEntry::new(LogLevel::Warn, "task", "c").with_data(json!([1, 2])));
+
+        // Persist the chain as a v0 writer would have, without versions, and
+        // as a later one would, then load it back at the current version.
+        for version in [None, Some(SCHEMA_VERSION + 1)] {
+            let persisted: Vec<serde_json::Value> = sink
+                .entries()
+                .iter()
+                .map(|entry| {
+                    let mut value = serde_json::to_value(entry).unwrap();
+                    let fields = value.as_object_mut().unwrap();
+                    match version {
+                        None => fields.remove("schema_version"),
+                        Some(v) => fields.insert("schema_version".into(), json!(v)),
+                    };
+                    value
+                })
+                .collect();
+            let loaded: Vec<LogEntry> = serde_json::from_value(json!(persisted)).unwrap();
+            assert!(loaded.iter().all(|e| e.schema_version >= SCHEMA_VERSION));
+            assert!(verify_chain(&loaded, b"audit-key"), "{version:?}");
+        }
+    }
+}
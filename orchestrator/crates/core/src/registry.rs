@@ -0,0 +1,717 @@
+//! AdapterRegistry — provider lookup plus health and latency tracking for routing.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::adapter::{
+    Adapter, AdapterError, Feature, HealthStatus, ModelResponse, ModelTier, Provider,
+};
+use crate::logic::{LogicError, ProviderFailure, Query};
+use crate::metrics::Metrics;
+
+/// Last observed health of a single provider.
+#[derive(Debug, Clone)]
+pub struct HealthEntry {
+    pub healthy: bool,
+    /// What the most recent observation said about the provider.
+    pub status: HealthStatus,
+    /// Reason for the most recent failure, if any.
+    pub last_failure: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Cache of provider health observations shared by routing logic.
+///
+/// Providers that have never been observed are assumed healthy. A provider
+/// marked unhealthy counts as healthy again once its failure is older than
+/// the [unhealthy TTL](Self::with_unhealthy_ttl), so routing sends it the
+/// next query as a probe: success restores it, another failure starts a
+/// new TTL.
+#[derive(Debug)]
+pub struct HealthCache {
+    entries: RwLock<HashMap<Provider, HealthEntry>>,
+    unhealthy_ttl: Duration,
+}
+
+/// How long a failure keeps a provider out of routing by default.
+pub const DEFAULT_UNHEALTHY_TTL: Duration = Duration::from_secs(30);
+
+impl Default for HealthCache {
+    fn default() -> Self {
+        Self { entries: RwLock::default(), unhealthy_ttl: DEFAULT_UNHEALTHY_TTL }
+    }
+}
+
+impl HealthCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep failed providers out of routing for `ttl` instead of
+    /// [`DEFAULT_UNHEALTHY_TTL`].
+    pub fn with_unhealthy_ttl(mut self, ttl: Duration) -> Self {
+        self.unhealthy_ttl = ttl;
+        self
+    }
+
+    /// Mark `provider` healthy.
+    pub fn record_success(&self, provider: Provider) {
+        let mut map = self.entries.write().unwrap();
+        let last_failure = map.get(&provider).and_then(|e| e.last_failure.clone());
+        map.insert(
+            provider,
+            HealthEntry {
+                healthy: true,
+                status: HealthStatus::Healthy,
+                last_failure,
+                checked_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Mark `provider` unreachable because of `reason`.
+    pub fn record_failure(&self, provider: Provider, reason: impl Into<String>) {
+        self.record(provider, HealthStatus::Unreachable, reason.into());
+    }
+
+    /// Mark `provider` unhealthy because of `error`, keeping what kind of
+    /// failure it was (e.g. [`HealthStatus::AuthFailed`]).
+    pub fn record_error(&self, provider: Provider, error: &AdapterError) {
+        self.record(provider, HealthStatus::from(error), error.to_string());
+    }
+
+    fn record(&self, provider: Provider, status: HealthStatus, reason: String) {
+        self.entries.write().unwrap().insert(
+            provider,
+            HealthEntry {
+                healthy: false,
+                status,
+                last_failure: Some(reason),
+                checked_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Latest observation for `provider`, if any.
+    pub fn get(&self, provider: Provider) -> Option<HealthEntry> {
+        self.entries.read().unwrap().get(&provider).cloned()
+    }
+
+    /// Whether `provider` is currently considered healthy.
+    pub fn is_healthy(&self, provider: Provider) -> bool {
+        self.is_healthy_at(provider, Utc::now())
+    }
+
+    /// Like [`is_healthy`](Self::is_healthy), as of `now`.
+    pub fn is_healthy_at(&self, provider: Provider, now: DateTime<Utc>) -> bool {
+        self.get(provider).is_none_or(|e| {
+            e.healthy
+                || chrono::Duration::from_std(self.unhealthy_ttl)
+                    .is_ok_and(|ttl| now.signed_duration_since(e.checked_at) >= ttl)
+        })
+    }
+
+    /// [`HealthStatus::Healthy`] while `provider` [is healthy](Self::is_healthy),
+    /// otherwise the status of its latest failure.
+    pub fn status(&self, provider: Provider) -> HealthStatus {
+        match self.get(provider) {
+            Some(e) if !self.is_healthy(provider) => e.status,
+            _ => HealthStatus::Healthy,
+        }
+    }
+}
+
+/// When each provider's current rate limit expires, remembered from
+/// [`AdapterError::RateLimited`](crate::adapter::AdapterError::RateLimited)
+/// so routing can skip a limited provider instead of sending it a request
+/// that is bound to be rejected.
+#[derive(Debug, Default)]
+pub struct RateLimitState {
+    limited_until: RwLock<HashMap<Provider, DateTime<Utc>>>,
+}
+
+impl RateLimitState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `provider` asked to be retried after `retry_after_ms`.
+    pub fn record(&self, provider: Provider, retry_after_ms: u64) {
+        self.record_at(provider, retry_after_ms, Utc::now());
+    }
+
+    /// Like [`record`](Self::record), for a rejection observed at `now`.
+    /// A later limit extends an earlier one; an earlier one never shortens it.
+    /// A window too long to represent lasts until [`DateTime::<Utc>::MAX_UTC`].
+    pub fn record_at(&self, provider: Provider, retry_after_ms: u64, now: DateTime<Utc>) {
+        let until = i64::try_from(retry_after_ms)
+            .ok()
+            .and_then(chrono::Duration::try_milliseconds)
+            .and_then(|window| now.checked_add_signed(window))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let mut map = self.limited_until.write().unwrap();
+        let entry = map.entry(provider).or_insert(until);
+        *entry = (*entry).max(until);
+    }
+
+    /// When `provider`'s latest rate limit ends, whether or not it has.
+    pub fn limited_until(&self, provider: Provider) -> Option<DateTime<Utc>> {
+        self.limited_until.read().unwrap().get(&provider).copied()
+    }
+
+    /// Whether `provider` is rate limited right now.
+    pub fn is_limited(&self, provider: Provider) -> bool {
+        self.is_limited_at(provider, Utc::now())
+    }
+
+    /// Whether `provider` is rate limited at `now`.
+    pub fn is_limited_at(&self, provider: Provider, now: DateTime<Utc>) -> bool {
+        self.limited_until(provider).is_some_and(|until| until > now)
+    }
+}
+
+/// Per-provider exponentially weighted moving average of response latency.
+///
+/// Each observation moves the estimate by `alpha` toward the new value, so a
+/// larger `alpha` reacts faster and a smaller one smooths out spikes.
+#[derive(Debug)]
+pub struct LatencyTracker {
+    alpha: f64,
+    ewma: RwLock<HashMap<Provider, f64>>,
+}
+
+impl LatencyTracker {
+    /// Default smoothing factor.
+    pub const DEFAULT_ALPHA: f64 = 0.3;
+
+    /// Create a tracker with smoothing factor `alpha` in `(0, 1]`.
+    pub fn new(alpha: f64) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be in (0, 1], got {alpha}");
+        Self {
+            alpha,
+            ewma: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Fold a latency measurement for `provider` into its estimate.
+    pub fn record(&self, provider: Provider, latency_ms: u64) {
+        let sample = latency_ms as f64;
+        let mut map = self.ewma.write().unwrap();
+        let next = match map.get(&provider) {
+            Some(prev) => self.alpha * sample + (1.0 - self.alpha) * prev,
+            None => sample,
+        };
+        map.insert(provider, next);
+    }
+
+    /// Fold a completed response's latency into its provider's estimate.
+    pub fn observe(&self, response: &ModelResponse) {
+        self.record(response.provider, response.latency_ms);
+    }
+
+    /// Current estimate for `provider`, or `None` if never measured.
+    pub fn ewma_ms(&self, provider: Provider) -> Option<f64> {
+        self.ewma.read().unwrap().get(&provider).copied()
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_ALPHA)
+    }
+}
+
+/// A registered provider as routing currently sees it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderInfo {
+    pub provider: Provider,
+    /// Model identifier, if the adapter reports one.
+    pub model: Option<String>,
+    pub tier: ModelTier,
+    /// Per the [`HealthCache`]; unobserved providers count as healthy.
+    pub healthy: bool,
+    pub rate_limited: bool,
+    /// Health and rate limit in one: a rate limit wins over the
+    /// [cached status](HealthCache::status).
+    pub status: HealthStatus,
+    pub features: Vec<Feature>,
+}
+
+/// The set of adapters available for routing, in registration order.
+#[derive(Default)]
+pub struct AdapterRegistry {
+    adapters: Vec<Arc<dyn Adapter>>,
+    health: HealthCache,
+    latency: LatencyTracker,
+    rate_limits: RateLimitState,
+}
+
+impl AdapterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track provider health in `health` (e.g. one with a custom
+    /// [unhealthy TTL](HealthCache::with_unhealthy_ttl)).
+    pub fn with_health_cache(mut self, health: HealthCache) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Use `tracker` for latency estimates (e.g. one with a custom alpha).
+    pub fn with_latency_tracker(mut self, tracker: LatencyTracker) -> Self {
+        self.latency = tracker;
+        self
+    }
+
+    /// Register an adapter, replacing any existing adapter for the same provider.
+    pub fn register(&mut self, adapter: Arc<dyn Adapter>) {
+        let provider = adapter.provider();
+        self.adapters.retain(|a| a.provider() != provider);
+        self.adapters.push(adapter);
+    }
+
+    /// Look up the adapter serving `provider`.
+    pub fn get(&self, provider: Provider) -> Option<Arc<dyn Adapter>> {
+        self.adapters.iter().find(|a| a.provider() == provider).cloned()
+    }
+
+    /// Registered providers, in registration order.
+    pub fn providers(&self) -> Vec<Provider> {
+        self.adapters.iter().map(|a| a.provider()).collect()
+    }
+
+    /// What is known about each registered provider, in registration order.
+    pub fn provider_info(&self) -> Vec<ProviderInfo> {
+        self.adapters
+            .iter()
+            .map(|a| ProviderInfo {
+                provider: a.provider(),
+                model: a.model(),
+                tier: a.tier(),
+                healthy: self.health.is_healthy(a.provider()),
+                rate_limited: self.is_limited(a.provider()),
+                status: if self.is_limited(a.provider()) {
+                    HealthStatus::RateLimited
+                } else {
+                    self.health.status(a.provider())
+                },
+                features: a.features(),
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.adapters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adapters.is_empty()
+    }
+
+    /// The health cache consulted by routing.
+    pub fn health(&self) -> &HealthCache {
+        &self.health
+    }
+
+    /// The latency estimates consulted by routing.
+    pub fn latency(&self) -> &LatencyTracker {
+        &self.latency
+    }
+
+    /// The rate limits consulted by routing.
+    pub fn rate_limits(&self) -> &RateLimitState {
+        &self.rate_limits
+    }
+
+    /// Whether `provider` is currently rate limited.
+    pub fn is_limited(&self, provider: Provider) -> bool {
+        self.rate_limits.is_limited(provider)
+    }
+
+    /// The healthy, unlimited adapter with the lowest latency estimate.
+    ///
+    /// Measured providers are preferred over unmeasured ones; ties keep
+    /// registration order.
+    pub fn best_available(&self) -> Option<Arc<dyn Adapter>> {
+        self.best_where(|_| true)
+    }
+
+    /// The [best available](Self::best_available) adapter of `tier`, or else
+    /// of the next larger tier that has one available.
+    ///
+    /// When no adapter of `tier` or above is available, the best of any
+    /// smaller tier is returned rather than none.
+    pub fn best_available_for(&self, tier: ModelTier) -> Option<Arc<dyn Adapter>> {
+        ModelTier::ALL
+            .into_iter()
+            .filter(|t| *t >= tier)
+            .find_map(|t| self.best_where(|a| a.tier() == t))
+            .or_else(|| self.best_available())
+    }
+
+    fn best_where(&self, pred: impl Fn(&dyn Adapter) -> bool) -> Option<Arc<dyn Adapter>> {
+        self.adapters
+            .iter()
+            .filter(|a| self.health.is_healthy(a.provider()) && !self.is_limited(a.provider()))
+            .filter(|a| pred(a.as_ref()))
+            .min_by(|a, b| {
+                let rank = |p| self.latency.ewma_ms(p).unwrap_or(f64::INFINITY);
+                rank(a.provider()).total_cmp(&rank(b.provider()))
+            })
+            .cloned()
+    }
+
+    /// Choose the adapter for `query`: its pinned provider if it has one,
+    /// otherwise the [best available](Self::best_available), preferring the
+    /// query's [`tier`](Query::tier) when it names one.
+    ///
+    /// Fails with [`LogicError::NoProvidersRegistered`] while the registry
+    /// is empty.
+    pub fn route(&self, query: &Query) -> Result<Arc<dyn Adapter>, LogicError> {
+        if self.is_empty() {
+            return Err(LogicError::NoProvidersRegistered);
+        }
+        if let Some(provider) = query.target_provider()? {
+            return self
+                .get(provider)
+                .ok_or_else(|| LogicError::ProviderUnavailable(provider.to_string()));
+        }
+        self.preflight()?;
+        let best = match query.tier {
+            Some(tier) => self.best_available_for(tier),
+            None => self.best_available(),
+        };
+        best.ok_or_else(|| {
+            let limited = self
+                .adapters
+                .iter()
+                .filter_map(|a| {
+                    let until = self.rate_limits.limited_until(a.provider())?;
+                    Some(ProviderFailure {
+                        provider: a.provider(),
+                        reason: format!("rate limited until {}", until.to_rfc3339()),
+                    })
+                })
+                .collect();
+            LogicError::NoHealthyProviders(limited)
+        })
+    }
+
+    /// Run `health_check` on every adapter and record the outcome.
+    pub async fn check_health(&self) {
+        for adapter in &self.adapters {
+            match adapter.health_check().await {
+                Ok(()) => self.health.record_success(adapter.provider()),
+                Err(e) => self.health.record_error(adapter.provider(), &e),
+            }
+        }
+    }
+
+    /// Warm up every adapter concurrently, once at startup.
+    ///
+    /// Each adapter's warm-up time is recorded as a latency sample, and its
+    /// outcome as a health observation, so routing starts from real data
+    /// instead of assuming every provider is healthy and equally fast.
+    pub async fn warm_up(&self) {
+        let warm_ups = self.adapters.iter().map(|adapter| async move {
+            let started = tokio::time::Instant::now();
+            let result = adapter.warm_up().await;
+            (adapter.provider(), started.elapsed(), result)
+        });
+        for (provider, elapsed, result) in futures::future::join_all(warm_ups).await {
+            match result {
+                Ok(()) => {
+                    self.latency.record(provider, elapsed.as_millis() as u64);
+                    self.health.record_success(provider);
+                }
+                Err(e) => self.health.record_error(provider, &e),
+            }
+        }
+    }
+
+    /// Pre-flight check for routing: fail fast when no registered provider is
+    /// healthy, instead of cycling through doomed requests.
+    pub fn preflight(&self) -> Result<(), LogicError> {
+        if self.is_empty() {
+            return Err(LogicError::NoProvidersRegistered);
+        }
+        if self.adapters.iter().any(|a| self.health.is_healthy(a.provider())) {
+            return Ok(());
+        }
+        let failures = self
+            .adapters
+            .iter()
+            .map(|a| ProviderFailure {
+                provider: a.provider(),
+                reason: self
+                    .health
+                    .get(a.provider())
+                    .and_then(|e| e.last_failure)
+                    .unwrap_or_else(|| "unknown".into()),
+            })
+            .collect();
+        Err(LogicError::NoHealthyProviders(failures))
+    }
+}
+
+impl Metrics for AdapterRegistry {
+    fn subsystem(&self) -> &'static str {
+        "adapters"
+    }
+
+    /// Health, last failure and latency estimate of each registered
+    /// provider, plus how many are healthy.
+    fn snapshot(&self) -> serde_json::Value {
+        let providers: serde_json::Map<String, serde_json::Value> = self
+            .providers()
+            .into_iter()
+            .map(|provider| {
+                let entry = self.health.get(provider);
+                let state = serde_json::json!({
+                    "healthy": self.health.is_healthy(provider),
+                    "last_failure": entry.and_then(|e| e.last_failure),
+                    "latency_ewma_ms": self.latency.ewma_ms(provider),
+                });
+                (provider.to_string(), state)
+            })
+            .collect();
+        let healthy = providers.values().filter(|p| p["healthy"] == true).count();
+        serde_json::json!({ "providers": providers, "healthy": healthy })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockAdapter;
+
+    #[test]
+    fn unobserved_provider_is_healthy() {
+        let cache = HealthCache::new();
+        assert!(cache.is_healthy(Provider::Claude));
+        cache.record_failure(Provider::Claude, "timeout");
+        assert!(!cache.is_healthy(Provider::Claude));
+        cache.record_success(Provider::Claude);
+        assert!(cache.is_healthy(Provider::Claude));
+    }
+
+    #[test]
+    fn failures_keep_their_status_until_they_expire() {
+        let cache = HealthCache::new();
+        assert_eq!(cache.status(Provider::Claude), HealthStatus::Healthy);
+        cache.record_error(Provider::Claude, &AdapterError::Auth("bad key".into()));
+        assert_eq!(cache.status(Provider::Claude), HealthStatus::AuthFailed);
+        cache.record_failure(Provider::Claude, "down");
+        assert_eq!(cache.status(Provider::Claude), HealthStatus::Unreachable);
+
+        let cache = HealthCache::new().with_unhealthy_ttl(Duration::ZERO);
+        cache.record_error(Provider::Claude, &AdapterError::Auth("bad key".into()));
+        assert_eq!(cache.status(Provider::Claude), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn unhealthy_provider_is_retried_after_the_ttl() {
+        let cache = HealthCache::new().with_unhealthy_ttl(Duration::from_secs(10));
+        cache.record_failure(Provider::Claude, "down");
+        let failed_at = cache.get(Provider::Claude).unwrap().checked_at;
+        assert!(!cache.is_healthy_at(Provider::Claude, failed_at + chrono::Duration::seconds(9)));
+        assert!(cache.is_healthy_at(Provider::Claude, failed_at + chrono::Duration::seconds(10)));
+
+        // A zero TTL never keeps a provider out.
+        let cache = HealthCache::new().with_unhealthy_ttl(Duration::ZERO);
+        cache.record_failure(Provider::Claude, "down");
+        assert!(cache.is_healthy(Provider::Claude));
+    }
+
+    #[test]
+    fn ewma_converges_toward_recent_latency() {
+        let tracker = LatencyTracker::new(0.5);
+        assert_eq!(tracker.ewma_ms(Provider::Claude), None);
+
+        tracker.record(Provider::Claude, 1000);
+        assert_eq!(tracker.ewma_ms(Provider::Claude), Some(1000.0));
+        tracker.record(Provider::Claude, 0);
+        assert_eq!(tracker.ewma_ms(Provider::Claude), Some(500.0));
+
+        for _ in 0..20 {
+            tracker.record(Provider::Claude, 100);
+        }
+        let ewma = tracker.ewma_ms(Provider::Claude).unwrap();
+        assert!((ewma - 100.0).abs() < 1.0, "ewma {ewma} should approach 100");
+    }
+
+    #[test]
+    fn best_available_prefers_fastest_healthy() {
+        let mut reg = AdapterRegistry::new();
+        reg.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        reg.register(Arc::new(MockAdapter::new(Provider::Gemini)));
+        reg.register(Arc::new(MockAdapter::new(Provider::Grok)));
+        reg.latency().record(Provider::Claude, 900);
+        reg.latency().record(Provider::Gemini, 200);
+        reg.latency().record(Provider::Grok, 50);
+        reg.health().record_failure(Provider::Grok, "down");
+
+        let best = reg.best_available().unwrap();
+        assert_eq!(best.provider(), Provider::Gemini);
+    }
+
+    #[test]
+    fn tiered_query_routes_to_matching_tier_then_up() {
+        let mut reg = AdapterRegistry::new();
+        let tiered = |provider, tier| Arc::new(MockAdapter::new(provider).with_tier(tier));
+        reg.register(tiered(Provider::Claude, ModelTier::Powerful));
+        reg.register(tiered(Provider::Gemini, ModelTier::Fast));
+        reg.register(tiered(Provider::Grok, ModelTier::Balanced));
+        // Fastest overall, so it would win an untiered query.
+        reg.latency().record(Provider::Claude, 10);
+        let route = |q: Query| reg.route(&q).unwrap().provider();
+
+        assert_eq!(route(Query::new("hi").with_tier(ModelTier::Fast)), Provider::Gemini);
+        assert_eq!(route(Query::new("hi")), Provider::Claude);
+
+        reg.health().record_failure(Provider::Gemini, "down");
+        assert_eq!(route(Query::new("hi").with_tier(ModelTier::Fast)), Provider::Grok);
+        reg.health().record_failure(Provider::Claude, "down");
+        assert_eq!(route(Query::new("hi").with_tier(ModelTier::Powerful)), Provider::Grok);
+    }
+
+    #[test]
+    fn register_replaces_same_provider() {
+        let mut reg = AdapterRegistry::new();
+        reg.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        reg.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        reg.register(Arc::new(MockAdapter::new(Provider::Gemini)));
+        assert_eq!(reg.providers(), vec![Provider::Claude, Provider::Gemini]);
+    }
+
+    #[test]
+    fn pinning_by_enum_routes_like_pinning_by_name() {
+        let mut reg = AdapterRegistry::new();
+        reg.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        reg.register(Arc::new(MockAdapter::new(Provider::Gemini)));
+        reg.latency().record(Provider::Gemini, 10);
+
+        let by_enum = reg.route(&Query::new("hi").with_provider_enum(Provider::Claude)).unwrap();
+        let by_name = reg.route(&Query::new("hi").with_provider("claude")).unwrap();
+        assert_eq!(by_enum.provider(), Provider::Claude);
+        assert_eq!(by_name.provider(), by_enum.provider());
+        // Unpinned queries go to the fastest provider.
+        assert_eq!(reg.route(&Query::new("hi")).unwrap().provider(), Provider::Gemini);
+        assert!(matches!(
+            reg.route(&Query::new("hi").with_provider("cluade")),
+            Err(LogicError::UnknownProvider(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn all_failing_providers_fail_fast() {
+        let mocks: Vec<Arc<MockAdapter>> = [Provider::Claude, Provider::Gemini, Provider::Grok]
+            .into_iter()
+            .map(|p| Arc::new(MockAdapter::new(p).failing(format!("{p} refused"))))
+            .collect();
+        let mut reg = AdapterRegistry::new();
+        for m in &mocks {
+            reg.register(m.clone());
+        }
+
+        reg.check_health().await;
+        let err = reg.preflight().unwrap_err();
+        let LogicError::NoHealthyProviders(failures) = err else {
+            panic!("expected NoHealthyProviders, got {err:?}");
+        };
+        assert_eq!(failures.len(), 3);
+        assert_eq!(failures[0].provider, Provider::Claude);
+        assert!(failures[0].reason.contains("claude refused"));
+        // No chat request was attempted.
+        assert!(mocks.iter().all(|m| m.calls() == 0));
+    }
+
+    #[tokio::test]
+    async fn one_healthy_provider_passes_preflight() {
+        let mut reg = AdapterRegistry::new();
+        reg.register(Arc::new(MockAdapter::new(Provider::Claude).failing("down")));
+        reg.register(Arc::new(MockAdapter::new(Provider::Gemini)));
+        reg.check_health().await;
+        assert!(reg.preflight().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn warm_up_records_latency_and_health() {
+        let claude = Arc::new(
+            MockAdapter::new(Provider::Claude).with_warm_up(std::time::Duration::from_millis(250)),
+        );
+        let grok = Arc::new(MockAdapter::new(Provider::Grok).failing("bad key"));
+        let mut reg = AdapterRegistry::new();
+        reg.register(claude.clone());
+        reg.register(grok.clone());
+
+        reg.warm_up().await;
+        assert_eq!((claude.warm_ups(), grok.warm_ups()), (1, 1));
+        assert_eq!(reg.latency().ewma_ms(Provider::Claude), Some(250.0));
+        assert_eq!(reg.latency().ewma_ms(Provider::Grok), None);
+        assert!(!reg.health().is_healthy(Provider::Grok));
+        // Warming up is not a chat request.
+        assert_eq!(claude.calls(), 0);
+    }
+
+    #[test]
+    fn rate_limit_lasts_until_window_passes() {
+        let limits = RateLimitState::new();
+        let now = Utc::now();
+        assert!(!limits.is_limited_at(Provider::Claude, now));
+
+        limits.record_at(Provider::Claude, 30_000, now);
+        assert!(limits.is_limited_at(Provider::Claude, now));
+        assert!(limits.is_limited_at(Provider::Claude, now + chrono::Duration::seconds(29)));
+        assert!(!limits.is_limited_at(Provider::Claude, now + chrono::Duration::seconds(30)));
+        assert!(!limits.is_limited_at(Provider::Gemini, now));
+
+        // A shorter limit reported later does not cut the window short.
+        limits.record_at(Provider::Claude, 1_000, now);
+        assert_eq!(
+            limits.limited_until(Provider::Claude),
+            Some(now + chrono::Duration::seconds(30))
+        );
+    }
+
+    #[test]
+    fn huge_retry_after_is_clamped_instead_of_overflowing() {
+        let limits = RateLimitState::new();
+        let now = Utc::now();
+        limits.record_at(Provider::Claude, u64::MAX, now);
+        assert_eq!(limits.limited_until(Provider::Claude), Some(DateTime::<Utc>::MAX_UTC));
+        assert!(limits.is_limited_at(Provider::Claude, now));
+
+        // Representable as a duration, but not once added to `now`.
+        limits.record_at(Provider::Gemini, i64::MAX as u64 / 2, now);
+        assert_eq!(limits.limited_until(Provider::Gemini), Some(DateTime::<Utc>::MAX_UTC));
+    }
+
+    #[test]
+    fn routing_skips_rate_limited_provider() {
+        let mut reg = AdapterRegistry::new();
+        reg.register(Arc::new(MockAdapter::new(Provider::Claude)));
+        reg.register(Arc::new(MockAdapter::new(Provider::Gemini)));
+        reg.latency().record(Provider::Claude, 50);
+        reg.latency().record(Provider::Gemini, 200);
+
+        reg.rate_limits().record(Provider::Claude, 60_000);
+        assert!(reg.is_limited(Provider::Claude));
+        assert_eq!(reg.route(&Query::new("hi")).unwrap().provider(), Provider::Gemini);
+
+        reg.rate_limits().record(Provider::Gemini, 60_000);
+        let Err(err) = reg.route(&Query::new("hi")) else {
+            panic!("routed to a rate-limited provider");
+        };
+        assert!(err.to_string().contains("claude (rate limited until"), "{err}");
+    }
+}
@@ -0,0 +1,742 @@
+//! TaskRunner — drives a [`Task`] through the four-stage lifecycle.
+//!
+//! The runner turns the task input into a [`Query`], executes it through
+//! [`CoreLogic`], validates the output, and persists the completed record —
+//! result plus every log line emitted for the task — in [`MemorySystem`].
+//!
+//! A task input may also hold a `queries` list, which runs each query in
+//! turn. Tokens used by every answered query are summed into the
+//! [`TaskResult`] and priced against the runner's [`CostTable`].
+//!
+//! Transient failures in the initialization checkpoint and in execution are
+//! retried against a single [`RetryBudget`] per task, so retries in one phase
+//! leave fewer for the next. Each retry waits twice as long as the one before,
+//! so a provider that is down is not hammered. A task that fails leaves no
+//! checkpoint behind.
+//!
+//! A [`Retention`] policy bounds how many completed records stay in memory,
+//! so a long-running orchestrator does not accumulate them forever.
+//!
+//! An overall timeout bounds a whole run, across every phase and retry; a
+//! task that outlasts it is failed and its record persisted.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::adapter::{Provider, UnknownProvider};
+use crate::cost::CostTable;
+use crate::id::{IdGenerator, RandomIdGenerator};
+use crate::logic::{CoreLogic, Query, QueryResult};
+use crate::memory::{MemoryError, MemorySystem};
+use crate::protocol::{LogEntry, LogLevel, LogSink, MemoryLogSink, TaskLogSink};
+use crate::schema::SchemaRegistry;
+use crate::task::{Task, TaskError, TaskResult};
+
+/// Memory key under which a task's record is persisted.
+pub fn task_key(id: Uuid) -> String {
+    format!("task:{id}")
+}
+
+/// Longest wait before a retry, however many came before it.
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Retries remaining for one task, shared by all of its phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBudget {
+    max: u32,
+    remaining: u32,
+}
+
+impl RetryBudget {
+    /// A budget of `max` retries in total. Zero disables retries.
+    pub fn new(max: u32) -> Self {
+        Self { max, remaining: max }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Spend one retry after `error`.
+    ///
+    /// With retries disabled `error` is returned unchanged; once a non-zero
+    /// budget is used up the task fails with "retry budget exhausted".
+    pub fn spend(&mut self, error: TaskError) -> Result<(), TaskError> {
+        if self.max == 0 {
+            return Err(error);
+        }
+        if self.remaining == 0 {
+            return Err(TaskError::InitFailed("retry budget exhausted".into()));
+        }
+        self.remaining -= 1;
+        Ok(())
+    }
+}
+
+/// Which completed task records a [`TaskRunner`] keeps in memory.
+///
+/// The default keeps every record. Records are evicted oldest first, by the
+/// time the runner completed them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Keep at most this many completed records.
+    pub max_results: Option<usize>,
+    /// Evict records completed longer ago than this.
+    pub max_age: Option<Duration>,
+}
+
+impl Retention {
+    pub fn with_max_results(mut self, n: usize) -> Self {
+        self.max_results = Some(n);
+        self
+    }
+
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Whether any record is ever evicted.
+    fn is_bounded(&self) -> bool {
+        self.max_results.is_some() || self.max_age.is_some()
+    }
+
+    /// Whether the oldest of `len` records, completed at `oldest`, must go.
+    fn evicts(&self, len: usize, oldest: Instant, now: Instant) -> bool {
+        self.max_results.is_some_and(|max| len > max)
+            || self.max_age.is_some_and(|age| now.duration_since(oldest) > age)
+    }
+}
+
+/// Executes tasks against a [`CoreLogic`] and persists them in a [`MemorySystem`].
+pub struct TaskRunner<L, M> {
+    logic: L,
+    memory: M,
+    log_sink: MemoryLogSink,
+    max_retries: u32,
+    retry_backoff: Duration,
+    costs: CostTable,
+    retention: Retention,
+    overall_timeout: Option<Duration>,
+    schemas: Arc<SchemaRegistry>,
+    ids: Arc<dyn IdGenerator>,
+    /// Completed tasks still in memory, oldest first; empty unless
+    /// `retention` is bounded.
+    completed: Mutex<VecDeque<(Uuid, Instant)>>,
+}
+
+impl<L: CoreLogic, M: MemorySystem> TaskRunner<L, M> {
+    pub fn new(logic: L, memory: M) -> Self {
+        Self {
+            logic,
+            memory,
+            log_sink: MemoryLogSink::new(),
+            max_retries: 0,
+            retry_backoff: Duration::from_millis(100),
+            costs: CostTable::new(),
+            retention: Retention::default(),
+            overall_timeout: None,
+            schemas: Arc::new(SchemaRegistry::new()),
+            ids: Arc::new(RandomIdGenerator),
+            completed: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Evict completed task records from memory according to `retention`.
+    ///
+    /// Only tasks completed by this runner are counted and evicted.
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Fail any task whose run takes longer than `limit` in total.
+    ///
+    /// The run is abandoned when the limit passes, cancelling any provider
+    /// call still in flight, and the failed task is persisted.
+    pub fn with_overall_timeout(mut self, limit: Duration) -> Self {
+        self.overall_timeout = Some(limit);
+        self
+    }
+
+    /// Price the tokens each task uses with `costs`. Without rates a task's
+    /// `estimated_cost` is zero.
+    pub fn with_cost_table(mut self, costs: CostTable) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Allow up to `max` retries in total across all phases of each task.
+    pub fn with_retry_budget(mut self, max: u32) -> Self {
+        self.max_retries = max;
+        self
+    }
+
+    /// Wait `base` before a task's first retry, doubling for each further
+    /// one up to [`MAX_RETRY_BACKOFF`]. Default: 100 ms.
+    pub fn with_retry_backoff(mut self, base: Duration) -> Self {
+        self.retry_backoff = base;
+        self
+    }
+
+    /// Check each task's input against the schema `schemas` holds for its
+    /// kind. Default: an empty registry, accepting any input.
+    pub fn with_schemas(mut self, schemas: Arc<SchemaRegistry>) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    /// Draw the ids of the queries built from task inputs from `ids`, e.g. a
+    /// [`SequentialIdGenerator`](crate::id::SequentialIdGenerator) for
+    /// reproducible runs. Default: random ids.
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Use `sink` to collect task logs (e.g. one shared with the TUI).
+    pub fn with_log_sink(mut self, sink: MemoryLogSink) -> Self {
+        self.log_sink = sink;
+        self
+    }
+
+    pub fn logic(&self) -> &L {
+        &self.logic
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    pub fn log_sink(&self) -> &MemoryLogSink {
+        &self.log_sink
+    }
+
+    pub fn schemas(&self) -> &SchemaRegistry {
+        &self.schemas
+    }
+
+    /// Where this runner's ids come from; pass it to
+    /// [`Task::fork_with_ids`] so forks share the sequence.
+    pub fn ids(&self) -> &dyn IdGenerator {
+        self.ids.as_ref()
+    }
+
+    /// A sink that tags entries with `task`'s correlation id, so that they
+    /// are persisted with the task record on completion.
+    pub fn task_log(&self, task: &Task) -> TaskLogSink<MemoryLogSink> {
+        TaskLogSink::new(self.log_sink.clone(), task.id)
+    }
+
+    /// Validate `task` without executing it.
+    ///
+    /// Runs `initialize` and the input/schema checks on a copy of the task,
+    /// then logs the query that would be sent. No provider is called and
+    /// nothing is written to memory; `task` itself is left untouched.
+    pub fn dry_run(&self, task: &Task) -> Result<(), TaskError> {
+        let mut probe = task.clone();
+        let log = self.task_log(task);
+        for query in prepare(&mut probe, &self.schemas, self.ids())? {
+            log.emit(
+                &LogEntry::new(
+                    LogLevel::Info,
+                    "task",
+                    format!(
+                        "dry run: would query {}",
+                        query.provider.as_deref().unwrap_or("best available provider")
+                    ),
+                )
+                .with_data(json!({ "query": query })),
+            );
+        }
+        Ok(())
+    }
+
+    /// Run `task` through every phase. On error the task is marked `Failed`.
+    pub async fn run(&self, task: &mut Task) -> Result<TaskResult, TaskError> {
+        let log = self.task_log(task);
+        let outcome = match self.overall_timeout {
+            Some(limit) => tokio::time::timeout(limit, self.run_phases(task, &log))
+                .await
+                .unwrap_or(Err(TaskError::OverallTimeout)),
+            None => self.run_phases(task, &log).await,
+        };
+        match outcome {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                task.fail_with(e.to_string());
+                log.emit(&LogEntry::new(LogLevel::Error, "task", e.to_string()));
+                if matches!(e, TaskError::OverallTimeout) {
+                    self.persist_failure(task, &log).await;
+                } else {
+                    self.discard_checkpoint(task, &log).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Store `task`, already failed, with its logs. A storage error is only
+    /// logged, so the caller still sees the original failure.
+    async fn persist_failure(&self, task: &Task, log: &impl LogSink) {
+        let record = json!({
+            "task": task,
+            "logs": self.log_sink.entries_for(task.id),
+        });
+        if let Err(e) = self.memory.store(&task_key(task.id), record).await {
+            let message = format!("could not persist failed task: {e}");
+            log.emit(&LogEntry::new(LogLevel::Warn, "task", message));
+        }
+    }
+
+    /// Remove the initialization checkpoint of `task`, which failed before
+    /// completing. A storage error is only logged.
+    async fn discard_checkpoint(&self, task: &Task, log: &impl LogSink) {
+        match self.memory.remove(&task_key(task.id)).await {
+            Ok(()) | Err(MemoryError::NotFound(_)) => {}
+            Err(e) => {
+                let message = format!("could not remove checkpoint of failed task: {e}");
+                log.emit(&LogEntry::new(LogLevel::Warn, "task", message));
+            }
+        }
+    }
+
+    async fn run_phases(
+        &self,
+        task: &mut Task,
+        log: &TaskLogSink<MemoryLogSink>,
+    ) -> Result<TaskResult, TaskError> {
+        let mut budget = RetryBudget::new(self.max_retries);
+        let queries = prepare(task, &self.schemas, self.ids())?;
+        let single = queries.len() == 1;
+        loop {
+            match self.memory.store(&task_key(task.id), json!({ "task": &*task })).await {
+                Ok(_) => break,
+                Err(e @ MemoryError::Backend(_)) => {
+                    self.retry(&mut budget, log, TaskError::InitFailed(e.to_string())).await?
+                }
+                Err(e) => return Err(TaskError::InitFailed(e.to_string())),
+            }
+        }
+        log.emit(&LogEntry::new(LogLevel::Info, "task", "initialized"));
+
+        task.begin_execution()?;
+        log.emit(&LogEntry::new(LogLevel::Info, "task", "executing"));
+        let mut answers = Vec::with_capacity(queries.len());
+        for query in queries {
+            let mut answer = loop {
+                match self.logic.query(query.clone()).await {
+                    Ok(answer) => break answer,
+                    Err(e) if e.is_retryable() => {
+                        self.retry(&mut budget, log, TaskError::ExecFailed(e.to_string())).await?
+                    }
+                    Err(e) => return Err(TaskError::ExecFailed(e.to_string())),
+                }
+            };
+            // Whatever the logic did with them, the query's tags reach the result.
+            for (key, value) in query.metadata {
+                answer.metadata.entry(key).or_insert(value);
+            }
+            let mut entry = LogEntry::new(
+                LogLevel::Info,
+                "task",
+                format!("answered by {} in {} ms", answer.provider_used, answer.latency_ms),
+            );
+            if !answer.metadata.is_empty() {
+                entry = entry.with_data(json!({ "metadata": answer.metadata }));
+            }
+            log.emit(&entry);
+            answers.push(answer);
+        }
+
+        let output = if single {
+            serde_json::to_value(&answers[0])
+        } else {
+            serde_json::to_value(&answers)
+        }
+        .map_err(|e| TaskError::ValidationFailed(e.to_string()))?;
+        task.validate(output)?;
+
+        let mut result = task.complete()?;
+        self.account(&mut result, &answers);
+        log.emit(&LogEntry::new(LogLevel::Info, "task", "completed"));
+
+        let record = json!({
+            "result": result,
+            "logs": self.log_sink.entries_for(task.id),
+        });
+        self.memory
+            .store(&task_key(task.id), record)
+            .await
+            .map_err(|e| TaskError::CompletionFailed(e.to_string()))?;
+        self.retain(task.id, log).await;
+        self.memory.flush().await.map_err(|e| TaskError::CompletionFailed(e.to_string()))?;
+        Ok(result)
+    }
+
+    /// Note that task `id` completed, then remove the records that fall
+    /// outside the retention policy. A failed removal is logged and retried
+    /// on the next completion. Under unbounded retention nothing is tracked.
+    async fn retain(&self, id: Uuid, log: &impl LogSink) {
+        if !self.retention.is_bounded() {
+            return;
+        }
+        let evicted: Vec<(Uuid, Instant)> = {
+            let mut completed = self.completed.lock().unwrap();
+            let now = Instant::now();
+            completed.push_back((id, now));
+            let mut evicted = Vec::new();
+            while let Some(&(oldest, at)) = completed.front()
+                && self.retention.evicts(completed.len(), at, now)
+            {
+                completed.pop_front();
+                evicted.push((oldest, at));
+            }
+            evicted
+        };
+        // Newest first, so failures go back to the queue in order.
+        for (old, at) in evicted.into_iter().rev() {
+            match self.memory.remove(&task_key(old)).await {
+                Ok(()) | Err(MemoryError::NotFound(_)) => {}
+                Err(e) => {
+                    self.completed.lock().unwrap().push_front((old, at));
+                    let message = format!("could not evict task {old}: {e}");
+                    log.emit(&LogEntry::new(LogLevel::Warn, "retention", message));
+                }
+            }
+        }
+    }
+
+    /// Spend a retry on `error`, logging the attempt and waiting out its
+    /// backoff, or fail the task.
+    async fn retry(
+        &self,
+        budget: &mut RetryBudget,
+        log: &impl LogSink,
+        error: TaskError,
+    ) -> Result<(), TaskError> {
+        let message = error.to_string();
+        budget.spend(error)?;
+        let spent = budget.max - budget.remaining;
+        let delay = self
+            .retry_backoff
+            .saturating_mul(2u32.saturating_pow(spent - 1))
+            .min(MAX_RETRY_BACKOFF);
+        log.emit(&LogEntry::new(
+            LogLevel::Warn,
+            "task",
+            format!(
+                "retrying after {message} in {} ms ({} retries left)",
+                delay.as_millis(),
+                budget.remaining()
+            ),
+        ));
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+
+    /// Add the tokens and cost of `answers` to `result`.
+    fn account(&self, result: &mut TaskResult, answers: &[QueryResult]) {
+        for answer in answers {
+            result.total_input_tokens += u64::from(answer.input_tokens);
+            result.total_output_tokens += u64::from(answer.output_tokens);
+            if let Ok(provider) = answer.provider_used.parse::<Provider>() {
+                result.estimated_cost +=
+                    self.costs.cost(provider, answer.input_tokens, answer.output_tokens);
+            }
+        }
+    }
+}
+
+/// Initialize `task` against `schemas` and check it against the Task Metadata
+/// Schema, returning the queries its input describes, with ids from `ids`.
+fn prepare(
+    task: &mut Task,
+    schemas: &SchemaRegistry,
+    ids: &dyn IdGenerator,
+) -> Result<Vec<Query>, TaskError> {
+    task.initialize_with(schemas)?;
+    if task.meta.origin.trim().is_empty() || task.meta.kind.trim().is_empty() {
+        return Err(TaskError::InitFailed(
+            "task metadata requires a non-empty origin and kind".into(),
+        ));
+    }
+    match task.input.get("queries") {
+        Some(serde_json::Value::Array(inputs)) if !inputs.is_empty() => {
+            inputs.iter().map(|input| query_from_input(input, ids)).collect()
+        }
+        Some(_) => Err(TaskError::InitFailed("`queries` must be a non-empty array".into())),
+        None => Ok(vec![query_from_input(&task.input, ids)?]),
+    }
+}
+
+/// Build a [`Query`] from a task input of the form
+/// `{"prompt": "...", "system": "...", "provider": "...", "metadata": {...}}`.
+fn query_from_input(input: &serde_json::Value, ids: &dyn IdGenerator) -> Result<Query, TaskError> {
+    let prompt = input
+        .get("prompt")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| TaskError::InitFailed("input is missing a string `prompt`".into()))?;
+    let mut query = Query::new_with_ids(prompt, ids);
+    if let Some(system) = input.get("system").and_then(|v| v.as_str()) {
+        query = query.with_system(system);
+    }
+    if let Some(provider) = input.get("provider").and_then(|v| v.as_str()) {
+        let provider = provider
+            .parse()
+            .map_err(|e: UnknownProvider| TaskError::InitFailed(e.to_string()))?;
+        query = query.with_provider_enum(provider);
+    }
+    if let Some(metadata) = input.get("metadata").and_then(|v| v.as_object()) {
+        for (key, value) in metadata {
+            query = query.with_meta(key.clone(), value.clone());
+        }
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use crate::mock::MockLogic;
+    use crate::protocol::TaskMeta;
+    use crate::task::TaskPhase;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Fails the first `failures` writes with a backend error.
+    struct FlakyMemory {
+        inner: InMemoryStore,
+        failures: usize,
+        writes: AtomicUsize,
+    }
+
+    impl MemorySystem for FlakyMemory {
+        async fn store(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
+            if self.writes.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(MemoryError::Backend("disk busy".into()));
+            }
+            self.inner.store(key, value).await
+        }
+
+        async fn load(&self, key: &str) -> Result<crate::memory::Record, MemoryError> {
+            self.inner.load(key).await
+        }
+
+        async fn remove(&self, key: &str) -> Result<(), MemoryError> {
+            self.inner.remove(key).await
+        }
+
+        async fn keys(&self) -> Result<Vec<String>, MemoryError> {
+            self.inner.keys().await
+        }
+    }
+
+    fn flaky_runner(
+        init_failures: usize,
+        exec_failures: usize,
+    ) -> TaskRunner<MockLogic, FlakyMemory> {
+        TaskRunner::new(
+            MockLogic::new().with_failures(exec_failures),
+            FlakyMemory {
+                inner: InMemoryStore::new(),
+                failures: init_failures,
+                writes: AtomicUsize::new(0),
+            },
+        )
+    }
+
+    fn query_task(input: serde_json::Value) -> Task {
+        Task::new(
+            TaskMeta::new("test", "query", "runner test"),
+            input,
+        )
+    }
+
+    #[tokio::test]
+    async fn completed_record_includes_logs() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new());
+        let mut task = query_task(json!({"prompt": "hello"}));
+
+        // Entries emitted by the caller during the run are captured too.
+        runner
+            .task_log(&task)
+            .emit(&LogEntry::new(LogLevel::Debug, "agent", "custom note"));
+        let result = runner.run(&mut task).await.unwrap();
+        assert_eq!(result.output["content"], "hello");
+
+        let record = runner.memory().load(&task_key(task.id)).await.unwrap();
+        assert_eq!(record.value["result"]["task_id"], json!(task.id));
+        let messages: Vec<&str> = record.value["logs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["message"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            vec!["custom note", "initialized", "executing", "answered by claude in 0 ms", "completed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn query_metadata_reaches_result_and_log() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new());
+        let mut task = query_task(json!({
+            "prompt": "hello",
+            "metadata": { "tenant": "acme", "trace_id": 42 },
+        }));
+        let result = runner.run(&mut task).await.unwrap();
+        let tags = json!({ "tenant": "acme", "trace_id": 42 });
+        assert_eq!(result.output["metadata"], tags);
+
+        let answered = runner
+            .log_sink()
+            .entries_for(task.id)
+            .into_iter()
+            .find(|e| e.message.starts_with("answered by"))
+            .unwrap();
+        assert_eq!(answered.data, Some(json!({ "metadata": tags })));
+    }
+
+    #[tokio::test]
+    async fn retention_evicts_oldest_results() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new())
+            .with_retention(Retention::default().with_max_results(2));
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let mut task = query_task(json!({"prompt": format!("q{i}")}));
+            runner.run(&mut task).await.unwrap();
+            ids.push(task.id);
+        }
+        let mut kept = runner.memory().keys().await.unwrap();
+        kept.sort();
+        let mut expected = vec![task_key(ids[2]), task_key(ids[3])];
+        expected.sort();
+        assert_eq!(kept, expected);
+    }
+
+    #[tokio::test]
+    async fn id_generator_numbers_queries_and_forks() {
+        use crate::id::SequentialIdGenerator;
+
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new())
+            .with_id_generator(Arc::new(SequentialIdGenerator::new()));
+        let mut task = query_task(json!({"queries": [{"prompt": "a"}, {"prompt": "b"}]}));
+        let result = runner.run(&mut task).await.unwrap();
+        let answers = result.output.as_array().unwrap();
+        let query_ids: Vec<_> = answers.iter().map(|a| a["query_id"].clone()).collect();
+        assert_eq!(query_ids, [json!(Uuid::from_u128(1)), json!(Uuid::from_u128(2))]);
+
+        let mut fork = task.fork_with_ids(json!({"prompt": "c"}), runner.ids());
+        assert_eq!(fork.id, Uuid::from_u128(3));
+        let result = runner.run(&mut fork).await.unwrap();
+        assert_eq!(result.output["query_id"], json!(Uuid::from_u128(4)));
+    }
+
+    #[tokio::test]
+    async fn unbounded_retention_tracks_nothing() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new());
+        for i in 0..3 {
+            runner.run(&mut query_task(json!({"prompt": format!("q{i}")}))).await.unwrap();
+        }
+        assert!(runner.completed.lock().unwrap().is_empty());
+        assert_eq!(runner.memory().keys().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retention_evicts_expired_results() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new())
+            .with_retention(Retention::default().with_max_age(Duration::from_secs(60)));
+        let mut old = query_task(json!({"prompt": "old"}));
+        runner.run(&mut old).await.unwrap();
+        tokio::time::advance(Duration::from_secs(61)).await;
+        let mut new = query_task(json!({"prompt": "new"}));
+        runner.run(&mut new).await.unwrap();
+        assert_eq!(runner.memory().keys().await.unwrap(), vec![task_key(new.id)]);
+    }
+
+    #[tokio::test]
+    async fn missing_prompt_fails_task() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new());
+        let mut task = query_task(json!({}));
+        let err = runner.run(&mut task).await.unwrap_err();
+        assert!(matches!(err, TaskError::InitFailed(_)));
+        assert_eq!(task.phase, TaskPhase::Failed);
+        assert!(runner.memory().keys().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_catches_schema_violation() {
+        let schemas = Arc::new(SchemaRegistry::new());
+        schemas.register("query", json!({ "properties": { "prompt": { "type": "string" } } }));
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new()).with_schemas(schemas);
+        let task = query_task(json!({"prompt": 42}));
+
+        let err = runner.dry_run(&task).unwrap_err();
+        assert!(matches!(err, TaskError::InitFailed(ref m) if m.contains("`query` schema")));
+        assert_eq!(task.phase, TaskPhase::Pending);
+        let task = query_task(json!({"prompt": "hi", "provider": "cluade"}));
+        assert!(matches!(runner.dry_run(&task), Err(TaskError::InitFailed(_))));
+        assert!(runner.memory().keys().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_without_side_effects() {
+        let runner = TaskRunner::new(MockLogic::new(), InMemoryStore::new());
+        let task = query_task(json!({"prompt": "hi", "provider": "gemini"}));
+
+        runner.dry_run(&task).unwrap();
+        assert_eq!(task.phase, TaskPhase::Pending);
+        assert!(runner.memory().keys().await.unwrap().is_empty());
+        let logs = runner.log_sink().entries_for(task.id);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].message, "dry run: would query gemini");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_budget_is_shared_across_phases() {
+        // Two init retries plus one execute retry use up a budget of three;
+        // the second execute failure then fails the task.
+        let runner = flaky_runner(2, 2).with_retry_budget(3);
+        let mut task = query_task(json!({"prompt": "hi"}));
+        let started = Instant::now();
+        let err = runner.run(&mut task).await.unwrap_err();
+        assert!(matches!(err, TaskError::InitFailed(ref m) if m == "retry budget exhausted"));
+        assert_eq!(task.phase, TaskPhase::Failed);
+        // Each retry waited twice as long as the one before.
+        assert_eq!(started.elapsed(), Duration::from_millis(100 + 200 + 400));
+        // The checkpoint written on initialization is gone.
+        assert!(runner.memory().keys().await.unwrap().is_empty());
+        assert_eq!(runner.memory().writes.load(Ordering::SeqCst), 3);
+        assert_eq!(runner.logic().calls(), 2);
+        let retries = runner
+            .log_sink()
+            .entries_for(task.id)
+            .into_iter()
+            .filter(|e| e.level == LogLevel::Warn)
+            .count();
+        assert_eq!(retries, 3);
+
+        // The same failures fit in a budget of four.
+        let runner = flaky_runner(2, 2).with_retry_budget(4);
+        let mut task = query_task(json!({"prompt": "hi"}));
+        runner.run(&mut task).await.unwrap();
+        assert_eq!(task.phase, TaskPhase::Completed);
+    }
+
+    #[tokio::test]
+    async fn without_budget_errors_surface_unchanged() {
+        let runner = flaky_runner(0, 1);
+        let mut task = query_task(json!({"prompt": "hi"}));
+        let err = runner.run(&mut task).await.unwrap_err();
+        assert!(matches!(err, TaskError::ExecFailed(ref m) if m.contains("mock failure")));
+        assert_eq!(task.failed_in_phase, Some(TaskPhase::Executing));
+        assert_eq!(task.failure_reason, Some(err.to_string()));
+    }
+}
@@ -0,0 +1,150 @@
+//! Schema — input schemas for task kinds and a small JSON Schema validator.
+//!
+//! Each task kind can advertise the JSON shape of its `input`, the same way
+//! capabilities advertise theirs, and [`Task::initialize_with`] rejects
+//! inputs that do not conform. A [`TaskRunner`] checks the tasks it runs
+//! against the registry it was given with
+//! [`with_schemas`](crate::runner::TaskRunner::with_schemas).
+//!
+//! The validator understands the subset of JSON Schema used by this crate:
+//! `type` (a name or a list of names), `enum`, `properties`, `required` and
+//! `items`. Other keywords are ignored.
+//!
+//! [`Task::initialize_with`]: crate::task::Task::initialize_with
+//! [`TaskRunner`]: crate::runner::TaskRunner
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use serde_json::Value;
+
+/// Input schemas keyed by [`TaskMeta::kind`](crate::protocol::TaskMeta::kind).
+#[derive(Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: RwLock<HashMap<String, Value>>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the input schema for tasks of `kind`, replacing any previous one.
+    pub fn register(&self, kind: impl Into<String>, schema: Value) {
+        self.schemas.write().unwrap().insert(kind.into(), schema);
+    }
+
+    pub fn get(&self, kind: &str) -> Option<Value> {
+        self.schemas.read().unwrap().get(kind).cloned()
+    }
+
+    /// Every registered `(kind, schema)` pair, sorted by kind.
+    pub fn list(&self) -> Vec<(String, Value)> {
+        let mut schemas: Vec<_> =
+            self.schemas.read().unwrap().iter().map(|(k, s)| (k.clone(), s.clone())).collect();
+        schemas.sort_by(|a, b| a.0.cmp(&b.0));
+        schemas
+    }
+}
+
+/// Check `value` against `schema`, naming the offending location on failure,
+/// e.g. `"/prompt: expected string, got number"`.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), String> {
+    check(schema, value, "")
+}
+
+fn check(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let at = if path.is_empty() { "/" } else { path };
+    if let Some(expected) = schema.get("type") {
+        let names: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|name| has_type(value, name)) {
+            return Err(format!("{at}: expected {}, got {}", names.join(" or "), type_name(value)));
+        }
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum")
+        && !allowed.contains(value)
+    {
+        return Err(format!("{at}: {value} is not one of {}", Value::Array(allowed.clone())));
+    }
+    if let Value::Object(fields) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    return Err(format!("{at}: missing required field `{name}`"));
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, field_schema) in properties {
+                if let Some(field) = fields.get(name) {
+                    check(field_schema, field, &format!("{path}/{name}"))?;
+                }
+            }
+        }
+    }
+    if let (Value::Array(elements), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, element) in elements.iter().enumerate() {
+            check(item_schema, element, &format!("{path}/{i}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.is_i64() || value.is_u64(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validates_nested_shapes() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "prompt": { "type": "string" },
+                "tags": { "type": "array", "items": { "enum": ["a", "b"] } },
+                "limit": { "type": ["integer", "null"] }
+            },
+            "required": ["prompt"]
+        });
+        assert_eq!(validate(&schema, &json!({"prompt": "hi", "tags": ["a"], "limit": 3})), Ok(()));
+        assert_eq!(validate(&schema, &json!({"prompt": "hi", "limit": null})), Ok(()));
+        assert_eq!(
+            validate(&schema, &json!({"prompt": 1})),
+            Err("/prompt: expected string, got number".into())
+        );
+        assert_eq!(
+            validate(&schema, &json!({"tags": []})),
+            Err("/: missing required field `prompt`".into())
+        );
+        assert_eq!(
+            validate(&schema, &json!({"prompt": "hi", "tags": ["a", "c"]})),
+            Err(r#"/tags/1: "c" is not one of ["a","b"]"#.into())
+        );
+        assert_eq!(
+            validate(&schema, &json!({"prompt": "hi", "limit": 1.5})),
+            Err("/limit: expected integer or null, got number".into())
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! SemanticCache — answer paraphrased queries from a cache of embeddings.
+//!
+//! [`CachingLogic`](crate::logic::CachingLogic) only hits when a query's text
+//! matches a cached one (up to its key strategy). [`SemanticCache`] embeds
+//! each query with an [`Embedder`] and serves a cached result whenever a
+//! new query lands close enough to a cached one, so "What is a braid?" can
+//! answer "Explain what a braid is".
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+
+use crate::adapter::{AdapterError, ModelTier};
+use crate::logic::{CoreLogic, LogicError, Query, QueryResult, QueryStream};
+use crate::registry::ProviderInfo;
+
+/// Turns text into an embedding vector, typically through a provider's
+/// embeddings endpoint.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, AdapterError>;
+}
+
+/// Cosine similarity of `a` and `b`, in `[-1, 1]`. Vectors of different
+/// lengths, or with no magnitude, are not similar at all.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+struct Entry {
+    embedding: Vec<f32>,
+    /// Queries only match entries made under the same system context,
+    /// provider pin and tier.
+    system: Option<String>,
+    provider: Option<String>,
+    tier: Option<ModelTier>,
+    result: QueryResult,
+}
+
+/// Logic decorator that answers queries similar to earlier ones from a
+/// bounded, in-process cache.
+///
+/// A query hits when the [cosine similarity](cosine_similarity) of its
+/// content's embedding to a cached query's reaches the
+/// [threshold](Self::with_threshold); the most similar entry wins. Once the
+/// cache holds [`capacity`](Self::with_capacity) entries, the least recently
+/// used one makes room. As with `CachingLogic`, a cached answer carries the
+/// new query's id and metadata, errors are never cached, and a failing
+/// embedder is bypassed.
+pub struct SemanticCache<L, E> {
+    inner: L,
+    embedder: E,
+    threshold: f32,
+    capacity: usize,
+    /// Least recently used first.
+    entries: Mutex<VecDeque<Entry>>,
+}
+
+impl<L: CoreLogic, E: Embedder> SemanticCache<L, E> {
+    /// Default similarity a query needs to hit.
+    pub const DEFAULT_THRESHOLD: f32 = 0.95;
+    /// Default number of cached results.
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    pub fn new(inner: L, embedder: E) -> Self {
+        Self {
+            inner,
+            embedder,
+            threshold: Self::DEFAULT_THRESHOLD,
+            capacity: Self::DEFAULT_CAPACITY,
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Hit on queries at least `threshold` similar to a cached one. Lower
+    /// values hit more often, at the risk of answering a different question.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Keep at most `capacity` results. Zero disables caching.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// Number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cached result closest to `query`, marked as recently used.
+    fn lookup(&self, query: &Query, embedding: &[f32]) -> Option<QueryResult> {
+        let mut entries = self.entries.lock().unwrap();
+        let (at, _) = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| {
+                e.system == query.system_context
+                    && e.provider == query.provider
+                    && e.tier == query.tier
+            })
+            .map(|(i, e)| (i, cosine_similarity(&e.embedding, embedding)))
+            .filter(|&(_, similarity)| similarity >= self.threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let entry = entries.remove(at)?;
+        let result = entry.result.clone();
+        entries.push_back(entry);
+        Some(result)
+    }
+
+    fn insert(&self, entry: Entry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        while entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+impl<L: CoreLogic, E: Embedder> CoreLogic for SemanticCache<L, E> {
+    async fn query(&self, query: Query) -> Result<QueryResult, LogicError> {
+        let Ok(embedding) = self.embedder.embed(&query.content).await else {
+            return self.inner.query(query).await;
+        };
+        if let Some(hit) = self.lookup(&query, &embedding) {
+            return Ok(QueryResult { query_id: query.id, metadata: query.metadata, ..hit });
+        }
+        let (system, provider) = (query.system_context.clone(), query.provider.clone());
+        let tier = query.tier;
+        let result = self.inner.query(query).await?;
+        self.insert(Entry { embedding, system, provider, tier, result: result.clone() });
+        Ok(result)
+    }
+
+    /// A cached answer arrives as one chunk; otherwise the inner logic's
+    /// stream is passed through, and not cached.
+    fn query_stream(&self, query: Query) -> QueryStream<'_> {
+        let stream = async move {
+            let hit = match self.embedder.embed(&query.content).await {
+                Ok(embedding) => self.lookup(&query, &embedding),
+                Err(_) => None,
+            };
+            match hit {
+                Some(hit) => futures::stream::once(async { Ok(hit.content) }).boxed(),
+                None => self.inner.query_stream(query),
+            }
+        };
+        Box::pin(futures::stream::once(stream).flatten())
+    }
+
+    fn available_providers(&self) -> Vec<ProviderInfo> {
+        self.inner.available_providers()
+    }
+
+    fn resolves_timeouts(&self) -> bool {
+        self.inner.resolves_timeouts()
+    }
+
+    async fn query_batch(&self, queries: Vec<Query>) -> Vec<Result<QueryResult, LogicError>> {
+        queries
+            .into_iter()
+            .map(|q| self.query(q))
+            .collect::<futures::stream::FuturesUnordered<_>>()
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockLogic;
+    use std::collections::HashMap;
+
+    /// Embeds known texts as fixed vectors.
+    struct StubEmbedder(HashMap<&'static str, Vec<f32>>);
+
+    #[async_trait]
+    impl Embedder for StubEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>, AdapterError> {
+            self.0.get(text).cloned().ok_or_else(|| AdapterError::Request(text.into()))
+        }
+    }
+
+    fn cache() -> SemanticCache<MockLogic, StubEmbedder> {
+        let embedder = StubEmbedder(HashMap::from([
+            ("what is a braid?", vec![1.0, 0.0, 0.0]),
+            ("explain what a braid is", vec![0.99, 0.1, 0.0]),
+            ("how do I bake bread?", vec![0.0, 1.0, 0.0]),
+            ("what is sourdough?", vec![0.0, 0.0, 1.0]),
+        ]));
+        SemanticCache::new(MockLogic::new(), embedder).with_threshold(0.9)
+    }
+
+    #[tokio::test]
+    async fn similar_queries_hit_and_dissimilar_miss() {
+        let cache = cache();
+        let first = cache.query(Query::new("what is a braid?")).await.unwrap();
+
+        let paraphrase = Query::new("explain what a braid is").with_meta("tenant", "acme");
+        let id = paraphrase.id;
+        let hit = cache.query(paraphrase).await.unwrap();
+        assert_eq!(hit.content, first.content);
+        assert_eq!((hit.query_id, &hit.metadata["tenant"]), (id, &"acme".into()));
+        assert_eq!(cache.inner().calls(), 1);
+
+        let miss = cache.query(Query::new("how do I bake bread?")).await.unwrap();
+        assert_eq!(miss.content, "how do I bake bread?");
+        assert_eq!(cache.inner().calls(), 2);
+
+        // A different system context does not share entries.
+        let other = Query::new("what is a braid?").with_system("Answer in French.");
+        cache.query(other).await.unwrap();
+        assert_eq!(cache.inner().calls(), 3);
+
+        // Nor does a different tier: a fast answer must not stand in for a
+        // powerful one.
+        let powerful = Query::new("explain what a braid is").with_tier(ModelTier::Powerful);
+        cache.query(powerful).await.unwrap();
+        assert_eq!(cache.inner().calls(), 4);
+        let powerful = Query::new("what is a braid?").with_tier(ModelTier::Powerful);
+        cache.query(powerful).await.unwrap();
+        assert_eq!(cache.inner().calls(), 4);
+
+        // Streams are answered from the cache too, or by the inner logic.
+        let hit: Vec<_> = cache.query_stream(Query::new("explain what a braid is")).collect().await;
+        assert_eq!(hit.into_iter().map(Result::unwrap).collect::<Vec<_>>(), [first.content]);
+        assert_eq!(cache.inner().calls(), 4);
+        let miss: Vec<_> = cache.query_stream(Query::new("what is sourdough?")).collect().await;
+        assert_eq!(miss.len(), 1);
+        assert_eq!(cache.inner().calls(), 5);
+    }
+
+    #[tokio::test]
+    async fn capacity_evicts_least_recently_used() {
+        let cache = cache().with_capacity(2);
+        cache.query(Query::new("what is a braid?")).await.unwrap();
+        cache.query(Query::new("how do I bake bread?")).await.unwrap();
+        // Touch the braid entry so bread is the oldest, then push it out.
+        cache.query(Query::new("explain what a braid is")).await.unwrap();
+        cache.query(Query::new("what is sourdough?")).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.inner().calls(), 3);
+
+        cache.query(Query::new("what is a braid?")).await.unwrap();
+        assert_eq!(cache.inner().calls(), 3);
+        cache.query(Query::new("how do I bake bread?")).await.unwrap();
+        assert_eq!(cache.inner().calls(), 4);
+
+        // Text the embedder cannot handle goes straight through, uncached.
+        cache.query(Query::new("unembeddable")).await.unwrap();
+        assert_eq!(cache.inner().calls(), 5);
+    }
+
+    #[test]
+    fn cosine_similarity_handles_degenerate_vectors() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
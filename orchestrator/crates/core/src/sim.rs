@@ -0,0 +1,252 @@
+//! SimulatedOrchestrator — drive agents step by step in tests (feature
+//! `test-util`).
+//!
+//! Each [`step`](SimulatedOrchestrator::step) runs one round of the real
+//! orchestration loop without its timer: queued system events first, then
+//! scripted messages, then every agent's tick, then the messages those ticks
+//! returned. Agents run in registration order, so a script always produces
+//! the same outputs.
+
+use std::any::Any;
+use std::collections::VecDeque;
+
+use crate::agent::Agent;
+use crate::orchestrator::TickFailure;
+use crate::protocol::{Message, SystemEvent};
+
+/// An [`Agent`] that can be handed back as its concrete type.
+trait AnyAgent: Agent {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<A: Agent + 'static> AnyAgent for A {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct Registered {
+    id: String,
+    interests: Vec<String>,
+    agent: Box<dyn AnyAgent>,
+}
+
+/// A deterministic, manually stepped stand-in for
+/// [`Orchestrator`](crate::orchestrator::Orchestrator).
+#[derive(Default)]
+pub struct SimulatedOrchestrator {
+    agents: Vec<Registered>,
+    system_events: VecDeque<SystemEvent>,
+    inbox: VecDeque<Message>,
+    emitted: Vec<Message>,
+    failures: Vec<TickFailure>,
+    ticks: u64,
+}
+
+impl SimulatedOrchestrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_agent<A: Agent + 'static>(&mut self, agent: A) {
+        self.agents.push(Registered {
+            id: agent.metadata().id.clone(),
+            interests: agent.interests(),
+            agent: Box::new(agent),
+        });
+    }
+
+    /// Deliver `message` to interested agents at the start of the next step.
+    pub fn enqueue(&mut self, message: Message) {
+        self.inbox.push_back(message);
+    }
+
+    /// Deliver `event` to every agent at the start of the next step.
+    pub fn broadcast_system(&mut self, event: SystemEvent) {
+        self.system_events.push_back(event);
+    }
+
+    /// Run one round and return the messages the agents' ticks produced.
+    pub async fn step(&mut self) -> Vec<Message> {
+        self.ticks += 1;
+        while let Some(event) = self.system_events.pop_front() {
+            for Registered { id, agent, .. } in &mut self.agents {
+                let result = agent.on_system(event.clone()).await;
+                record(&mut self.failures, id, self.ticks, result);
+            }
+        }
+        let scripted: Vec<Message> = self.inbox.drain(..).collect();
+        self.deliver(&scripted).await;
+
+        let mut round = Vec::new();
+        for Registered { id, agent, .. } in &mut self.agents {
+            match agent.tick().await {
+                Ok(messages) => round.extend(messages),
+                Err(e) => record(&mut self.failures, id, self.ticks, Err(e)),
+            }
+        }
+        self.deliver(&round).await;
+        self.emitted.extend(round.iter().cloned());
+        round
+    }
+
+    /// Run `n` rounds, returning every message they produced.
+    pub async fn run_steps(&mut self, n: usize) -> Vec<Message> {
+        let mut messages = Vec::new();
+        for _ in 0..n {
+            messages.extend(self.step().await);
+        }
+        messages
+    }
+
+    async fn deliver(&mut self, messages: &[Message]) {
+        for message in messages {
+            for Registered { id, interests, agent } in &mut self.agents {
+                if interests.iter().any(|i| message.matches_interest(i)) {
+                    let result = agent.on_message(message.clone()).await;
+                    record(&mut self.failures, id, self.ticks, result);
+                }
+            }
+        }
+    }
+
+    /// The registered agent `id`, if it is an `A`.
+    pub fn agent<A: Agent + 'static>(&self, id: &str) -> Option<&A> {
+        let registered = self.agents.iter().find(|r| r.id == id)?;
+        registered.agent.as_any().downcast_ref()
+    }
+
+    /// Every message produced by ticks so far, in order.
+    pub fn emitted(&self) -> &[Message] {
+        &self.emitted
+    }
+
+    /// Errors returned by agents so far; like the real loop, they do not
+    /// stop the simulation.
+    pub fn failures(&self) -> &[TickFailure] {
+        &self.failures
+    }
+
+    /// Steps taken so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+}
+
+fn record(failures: &mut Vec<TickFailure>, id: &str, tick: u64, result: anyhow::Result<()>) {
+    if let Err(e) = result {
+        failures.push(TickFailure { agent_id: id.to_owned(), tick, error: e.to_string() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentMetadata;
+    use crate::capability::CapabilityInfo;
+    use crate::protocol::MessageKind;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    /// Sums the `n` of every "add" command and reports each new total once.
+    struct Adder {
+        meta: AgentMetadata,
+        total: i64,
+        reported: i64,
+        draining: bool,
+    }
+
+    #[async_trait]
+    impl Agent for Adder {
+        fn metadata(&self) -> &AgentMetadata {
+            &self.meta
+        }
+
+        async fn init(&mut self, _: Vec<CapabilityInfo>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn tick(&mut self) -> anyhow::Result<Vec<Message>> {
+            if self.total == self.reported {
+                return Ok(Vec::new());
+            }
+            self.reported = self.total;
+            Ok(vec![message(MessageKind::Status, json!({ "total": self.total }))])
+        }
+
+        fn interests(&self) -> Vec<String> {
+            vec!["add".into()]
+        }
+
+        async fn on_message(&mut self, message: Message) -> anyhow::Result<()> {
+            match message.payload["n"].as_i64() {
+                Some(n) => self.total += n,
+                None => anyhow::bail!("add without n"),
+            }
+            Ok(())
+        }
+
+        async fn on_system(&mut self, event: SystemEvent) -> anyhow::Result<()> {
+            self.draining |= event == SystemEvent::Drain;
+            Ok(())
+        }
+    }
+
+    fn message(kind: MessageKind, payload: serde_json::Value) -> Message {
+        Message {
+            id: uuid::Uuid::new_v4(),
+            source: uuid::Uuid::nil(),
+            target: None,
+            kind,
+            payload,
+            timestamp: 0,
+        }
+    }
+
+    fn add(n: i64) -> Message {
+        message(MessageKind::Command, json!({ "topic": "add", "n": n }))
+    }
+
+    #[tokio::test]
+    async fn scripted_messages_drive_agent_outputs() {
+        let mut sim = SimulatedOrchestrator::new();
+        sim.register_agent(Adder {
+            meta: AgentMetadata {
+                id: "adder".into(),
+                name: "adder".into(),
+                version: "0.1.0".into(),
+                capabilities: Vec::new(),
+            },
+            total: 0,
+            reported: 0,
+            draining: false,
+        });
+
+        sim.enqueue(add(2));
+        let out = sim.step().await;
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].payload, json!({ "total": 2 }));
+        assert!(sim.step().await.is_empty());
+
+        sim.enqueue(add(3));
+        sim.enqueue(add(4));
+        // Not an "add": ignored by the agent's interests.
+        sim.enqueue(message(MessageKind::Command, json!({ "topic": "sub", "n": 1 })));
+        sim.enqueue(message(MessageKind::Command, json!({ "topic": "add" })));
+        sim.broadcast_system(SystemEvent::Drain);
+        let out = sim.run_steps(2).await;
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].payload, json!({ "total": 9 }));
+
+        let totals: Vec<&serde_json::Value> =
+            sim.emitted().iter().map(|m| &m.payload["total"]).collect();
+        assert_eq!(totals, [2, 9]);
+        assert_eq!(sim.ticks(), 4);
+        let [failure] = sim.failures() else { panic!("expected one failure") };
+        assert_eq!((failure.tick, failure.error.as_str()), (3, "add without n"));
+        let adder = sim.agent::<Adder>("adder").unwrap();
+        assert_eq!(adder.total, 9);
+        assert!(adder.draining);
+        assert!(sim.agent::<Adder>("missing").is_none());
+    }
+}
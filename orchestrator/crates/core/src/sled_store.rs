@@ -0,0 +1,382 @@
+//! SledStore — embedded, durable [`MemorySystem`] backed by `sled`.
+//!
+//! Intended for single-node deployments that want persistence without an
+//! external database. Each key maps to a [`Record`] encoded in the store's
+//! [`SerializationFormat`] (JSON unless chosen otherwise); sled's blocking
+//! calls run on tokio's blocking pool.
+//!
+//! sled buffers writes and flushes them in the background and again when the
+//! last handle on the database is dropped, so they survive a normal exit.
+//! [`close`](SledStore::close) flushes eagerly and reports any error.
+
+use std::ops::Bound;
+use std::path::Path;
+
+use crate::memory::{
+    MemoryError, MemorySystem, Record, SerializationFormat, check_version, paginate,
+};
+
+/// [`MemorySystem`] persisted in a sled database directory.
+#[derive(Debug, Clone)]
+pub struct SledStore {
+    db: sled::Db,
+    format: SerializationFormat,
+}
+
+impl SledStore {
+    /// Open (or create) the database at `path`, storing records as JSON.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MemoryError> {
+        Self::open_with_format(path, SerializationFormat::Json)
+    }
+
+    /// Open (or create) the database at `path`, storing records in `format`.
+    pub fn open_with_format(
+        path: impl AsRef<Path>,
+        format: SerializationFormat,
+    ) -> Result<Self, MemoryError> {
+        let db = sled::open(path).map_err(backend)?;
+        Ok(Self { db, format })
+    }
+
+    pub fn format(&self) -> SerializationFormat {
+        self.format
+    }
+
+    /// Flush buffered writes to disk and close this handle.
+    pub async fn close(self) -> Result<(), MemoryError> {
+        self.flush().await
+    }
+
+    /// Run a blocking sled operation on the blocking pool.
+    async fn blocking<T, F>(&self, f: F) -> Result<T, MemoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(sled::Db) -> Result<T, MemoryError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || f(db))
+            .await
+            .map_err(|e| MemoryError::Backend(e.to_string()))?
+    }
+}
+
+fn backend(e: sled::Error) -> MemoryError {
+    MemoryError::Backend(e.to_string())
+}
+
+impl MemorySystem for SledStore {
+    async fn store(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
+        let key = key.to_owned();
+        let format = self.format;
+        self.blocking(move |db| loop {
+            // Compare-and-swap so concurrent writers never reuse a version.
+            let current = db.get(&key).map_err(backend)?;
+            let version = match &current {
+                Some(bytes) => format.decode(bytes)?.version + 1,
+                None => 1,
+            };
+            let record = Record {
+                key: key.clone(),
+                value: value.clone(),
+                version,
+                updated_at: chrono::Utc::now(),
+            };
+            let swapped = db
+                .compare_and_swap(&key, current, Some(format.encode(&record)?))
+                .map_err(backend)?;
+            if swapped.is_ok() {
+                return Ok(version);
+            }
+        })
+        .await
+    }
+
+    /// Compares and writes in one compare-and-swap, retried if a writer
+    /// gets in between.
+    async fn store_dedup(&self, key: &str, value: serde_json::Value) -> Result<u64, MemoryError> {
+        let key = key.to_owned();
+        let format = self.format;
+        self.blocking(move |db| loop {
+            let current = db.get(&key).map_err(backend)?;
+            let version = match &current {
+                Some(bytes) => {
+                    let record = format.decode(bytes)?;
+                    if record.value == value {
+                        return Ok(record.version);
+                    }
+                    record.version + 1
+                }
+                None => 1,
+            };
+            let record = Record {
+                key: key.clone(),
+                value: value.clone(),
+                version,
+                updated_at: chrono::Utc::now(),
+            };
+            let swapped = db
+                .compare_and_swap(&key, current, Some(format.encode(&record)?))
+                .map_err(backend)?;
+            if swapped.is_ok() {
+                return Ok(version);
+            }
+        })
+        .await
+    }
+
+    async fn store_if_version(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        expected_version: u64,
+    ) -> Result<u64, MemoryError> {
+        let key = key.to_owned();
+        let format = self.format;
+        self.blocking(move |db| loop {
+            let current = db.get(&key).map_err(backend)?;
+            let actual = match &current {
+                Some(bytes) => format.decode(bytes)?.version,
+                None => 0,
+            };
+            check_version(&key, actual, expected_version)?;
+            let record = Record {
+                key: key.clone(),
+                value: value.clone(),
+                version: actual + 1,
+                updated_at: chrono::Utc::now(),
+            };
+            // Retry if a writer got in between the check and the swap; the
+            // check then fails unless it left the version unchanged.
+            let swapped = db
+                .compare_and_swap(&key, current, Some(format.encode(&record)?))
+                .map_err(backend)?;
+            if swapped.is_ok() {
+                return Ok(record.version);
+            }
+        })
+        .await
+    }
+
+    async fn load(&self, key: &str) -> Result<Record, MemoryError> {
+        let key = key.to_owned();
+        let format = self.format;
+        self.blocking(move |db| match db.get(&key).map_err(backend)? {
+            Some(bytes) => format.decode(&bytes),
+            None => Err(MemoryError::NotFound(key)),
+        })
+        .await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), MemoryError> {
+        let key = key.to_owned();
+        self.blocking(move |db| match db.remove(&key).map_err(backend)? {
+            Some(_) => Ok(()),
+            None => Err(MemoryError::NotFound(key)),
+        })
+        .await
+    }
+
+    async fn remove_if_version(&self, key: &str, expected_version: u64) -> Result<(), MemoryError> {
+        let key = key.to_owned();
+        let format = self.format;
+        self.blocking(move |db| loop {
+            let Some(current) = db.get(&key).map_err(backend)? else {
+                return Err(MemoryError::NotFound(key));
+            };
+            check_version(&key, format.decode(&current)?.version, expected_version)?;
+            // Only delete the exact bytes checked; retry if a writer got in first.
+            let swapped =
+                db.compare_and_swap(&key, Some(current), None::<Vec<u8>>).map_err(backend)?;
+            if swapped.is_ok() {
+                return Ok(());
+            }
+        })
+        .await
+    }
+
+    /// Write sled's buffered changes to disk and fsync them.
+    ///
+    /// sled also flushes in the background, so this only narrows the window
+    /// in which a crash can lose recent writes.
+    async fn flush(&self) -> Result<(), MemoryError> {
+        self.db.flush_async().await.map_err(backend)?;
+        Ok(())
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, MemoryError> {
+        self.blocking(|db| {
+            db.iter()
+                .keys()
+                .map(|k| {
+                    let k = k.map_err(backend)?;
+                    String::from_utf8(k.to_vec()).map_err(|e| MemoryError::Backend(e.to_string()))
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn keys_paginated(
+        &self,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> Result<(Vec<String>, Option<String>), MemoryError> {
+        self.blocking(move |db| {
+            let start = cursor.map_or(Bound::Unbounded, |c| Bound::Excluded(c.into_bytes()));
+            let keys = db
+                .range::<Vec<u8>, _>((start, Bound::Unbounded))
+                .keys()
+                .take(limit.saturating_add(1))
+                .map(|k| {
+                    let k = k.map_err(backend)?;
+                    String::from_utf8(k.to_vec()).map_err(|e| MemoryError::Backend(e.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(paginate(keys.into_iter(), limit))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Reopen `path`, waiting for sled's background flusher from a dropped
+    /// handle to release the directory lock.
+    async fn reopen(path: &Path) -> SledStore {
+        for _ in 0..50 {
+            if let Ok(store) = SledStore::open(path) {
+                return store;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        SledStore::open(path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn store_load_and_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        assert_eq!(store.store("k1", json!({"a": 1})).await.unwrap(), 1);
+        assert_eq!(store.store("k1", json!({"a": 2})).await.unwrap(), 2);
+
+        let rec = store.load("k1").await.unwrap();
+        assert_eq!(rec.value, json!({"a": 2}));
+        assert_eq!(rec.version, 2);
+    }
+
+    #[tokio::test]
+    async fn remove_and_missing_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        store.store("k1", json!(1)).await.unwrap();
+        store.remove("k1").await.unwrap();
+        assert!(matches!(store.load("k1").await, Err(MemoryError::NotFound(_))));
+        assert!(matches!(store.remove("k1").await, Err(MemoryError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn remove_if_version_checks_current_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        store.store("k1", json!(1)).await.unwrap();
+        let v2 = store.store("k1", json!(2)).await.unwrap();
+        assert!(matches!(
+            store.remove_if_version("k1", 1).await,
+            Err(MemoryError::VersionConflict { expected: 1, actual: 2, .. })
+        ));
+        store.remove_if_version("k1", v2).await.unwrap();
+        assert!(matches!(store.remove_if_version("k1", v2).await, Err(MemoryError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn store_if_version_checks_current_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        assert_eq!(store.store_if_version("k1", json!(1), 0).await.unwrap(), 1);
+        assert!(matches!(
+            store.store_if_version("k1", json!(2), 0).await,
+            Err(MemoryError::VersionConflict { expected: 0, actual: 1, .. })
+        ));
+        assert_eq!(store.store_if_version("k1", json!(2), 1).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn store_dedup_writes_only_changed_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        assert_eq!(store.store_dedup("k1", json!({"a": 1, "b": 2})).await.unwrap(), 1);
+        assert_eq!(store.store_dedup("k1", json!({"b": 2, "a": 1})).await.unwrap(), 1);
+        assert_eq!(store.store_dedup("k1", json!({"a": 2})).await.unwrap(), 2);
+        assert_eq!(store.load("k1").await.unwrap().value, json!({"a": 2}));
+    }
+
+    #[tokio::test]
+    async fn keys_lists_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        store.store("b", json!(2)).await.unwrap();
+        store.store("a", json!(1)).await.unwrap();
+        assert_eq!(store.keys().await.unwrap(), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn keys_paginated_uses_range_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        for i in 0..25 {
+            store.store(&format!("key{i:02}"), json!(i)).await.unwrap();
+        }
+        let (first, cursor) = store.keys_paginated(None, 10).await.unwrap();
+        assert_eq!(first.first().map(String::as_str), Some("key00"));
+        assert_eq!(cursor.as_deref(), Some("key09"));
+        let (second, cursor) = store.keys_paginated(cursor, 10).await.unwrap();
+        assert_eq!(second.first().map(String::as_str), Some("key10"));
+        let (third, cursor) = store.keys_paginated(cursor, 10).await.unwrap();
+        assert_eq!(third.len(), 5);
+        assert_eq!(cursor, None);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn msgpack_store_roundtrips() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open_with_format(dir.path(), SerializationFormat::MessagePack)
+            .unwrap();
+        store.store("k1", json!({"bytes": [1, 2, 3]})).await.unwrap();
+        assert_eq!(store.store("k1", json!({"bytes": [4]})).await.unwrap(), 2);
+        assert_eq!(store.load("k1").await.unwrap().value, json!({"bytes": [4]}));
+    }
+
+    #[tokio::test]
+    async fn persists_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = SledStore::open(dir.path()).unwrap();
+            store.store("k1", json!("durable")).await.unwrap();
+            store.store("k1", json!("durable v2")).await.unwrap();
+            store.db.flush_async().await.unwrap();
+        }
+        let store = reopen(dir.path()).await;
+        let rec = store.load("k1").await.unwrap();
+        assert_eq!(rec.value, json!("durable v2"));
+        assert_eq!(rec.version, 2);
+        assert_eq!(store.store("k1", json!("v3")).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn writes_survive_drop_without_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path()).unwrap();
+        store.store("k1", json!("unflushed")).await.unwrap();
+        drop(store);
+
+        let store = reopen(dir.path()).await;
+        assert_eq!(store.load("k1").await.unwrap().value, json!("unflushed"));
+        store.store("k2", json!("closed")).await.unwrap();
+        store.close().await.unwrap();
+        assert_eq!(reopen(dir.path()).await.keys().await.unwrap().len(), 2);
+    }
+}
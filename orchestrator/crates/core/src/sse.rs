@@ -0,0 +1,181 @@
+//! SSE — parse a `text/event-stream` response body into events.
+//!
+//! Streaming provider APIs send their replies as server-sent events. The
+//! body arrives in arbitrary chunks, so [`parse_sse`] buffers bytes until a
+//! line is complete and lines until a blank one ends the event. A `[DONE]`
+//! event, as sent by OpenAI-compatible APIs, ends the stream.
+
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::adapter::AdapterError;
+
+/// Data of the event that marks the end of a stream.
+pub const DONE: &str = "[DONE]";
+
+/// One dispatched server-sent event.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SseEvent {
+    /// The `event:` type, if the event named one.
+    pub event: Option<String>,
+    /// The `id:` of the event, if it set one.
+    pub id: Option<String>,
+    /// Every `data:` line of the event, joined with newlines.
+    pub data: String,
+}
+
+impl SseEvent {
+    /// Whether this is the `[DONE]` sentinel.
+    pub fn is_done(&self) -> bool {
+        self.data == DONE
+    }
+}
+
+/// The events of an event-stream body, ending at `[DONE]` or with the body.
+///
+/// Comments, `retry:` and unknown fields are skipped, as are events without
+/// data. A body that is not UTF-8 or that ends partway through an event
+/// yields [`AdapterError::InvalidResponse`] and nothing further.
+pub fn parse_sse<S>(bytes: S) -> impl Stream<Item = Result<SseEvent, AdapterError>>
+where
+    S: Stream<Item = Bytes>,
+{
+    let state = (Box::pin(bytes), Parser::default(), VecDeque::<SseEvent>::new());
+    futures::stream::unfold(Some(state), |state| async move {
+        let (mut bytes, mut parser, mut ready) = state?;
+        loop {
+            if let Some(event) = ready.pop_front() {
+                if event.is_done() {
+                    return None;
+                }
+                return Some((Ok(event), Some((bytes, parser, ready))));
+            }
+            match bytes.next().await {
+                Some(chunk) => match parser.feed(&chunk) {
+                    Ok(events) => ready.extend(events),
+                    Err(e) => return Some((Err(e), None)),
+                },
+                None => return parser.finish().err().map(|e| (Err(e), None)),
+            }
+        }
+    })
+}
+
+/// Line and event state carried between chunks.
+#[derive(Debug, Default)]
+struct Parser {
+    /// Bytes of the current, unfinished line.
+    line: Vec<u8>,
+    event: Option<String>,
+    id: Option<String>,
+    data: Option<String>,
+}
+
+impl Parser {
+    /// Consume `chunk`, returning the events it completes.
+    fn feed(&mut self, chunk: &[u8]) -> Result<Vec<SseEvent>, AdapterError> {
+        self.line.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.line.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.line.drain(..=end).collect();
+            let line = std::str::from_utf8(&raw).map_err(|e| {
+                AdapterError::InvalidResponse(format!("event stream is not UTF-8: {e}"))
+            })?;
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            events.extend(self.field(line));
+        }
+        Ok(events)
+    }
+
+    /// Apply one complete line, returning the event a blank line ends.
+    fn field(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            let data = self.data.take();
+            let (event, id) = (self.event.take(), self.id.take());
+            return data.map(|data| SseEvent { event, id, data });
+        }
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match name {
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_owned()),
+            },
+            "event" => self.event = Some(value.to_owned()),
+            "id" => self.id = Some(value.to_owned()),
+            // Comments (empty name), `retry` and unknown fields.
+            _ => {}
+        }
+        None
+    }
+
+    /// Check that the body did not end partway through an event.
+    fn finish(&self) -> Result<(), AdapterError> {
+        if self.line.is_empty() && self.data.is_none() {
+            Ok(())
+        } else {
+            Err(AdapterError::InvalidResponse("event stream ended mid-event".into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse `body` delivered in chunks of `size` bytes.
+    async fn parse_chunked(body: &[u8], size: usize) -> Vec<Result<SseEvent, AdapterError>> {
+        let chunks: Vec<Bytes> = body.chunks(size).map(Bytes::copy_from_slice).collect();
+        parse_sse(futures::stream::iter(chunks)).collect().await
+    }
+
+    fn data(data: &str) -> SseEvent {
+        SseEvent { data: data.into(), ..SseEvent::default() }
+    }
+
+    #[tokio::test]
+    async fn reassembles_events_split_across_chunks() {
+        let body = "event: delta\r\nid: 1\r\ndata: {\"text\":\"Hé\"}\r\n\r\n\
+                    : keep-alive\n\n\
+                    data:first\ndata: second\n\n\
+                    data: [DONE]\n\n\
+                    data: ignored\n\n";
+        let expected = vec![
+            SseEvent {
+                event: Some("delta".into()),
+                id: Some("1".into()),
+                data: r#"{"text":"Hé"}"#.into(),
+            },
+            data("first\nsecond"),
+        ];
+        // From single bytes, which also split the two-byte "é", to one chunk.
+        for size in 1..=body.len() {
+            let events = parse_chunked(body.as_bytes(), size).await;
+            let events: Vec<SseEvent> = events.into_iter().map(Result::unwrap).collect();
+            assert_eq!(events, expected, "chunk size {size}");
+        }
+    }
+
+    #[tokio::test]
+    async fn body_ending_mid_event_is_an_error() {
+        let events = parse_chunked(b"data: whole\n\ndata: cut", 4).await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap(), &data("whole"));
+        assert!(matches!(events[1], Err(AdapterError::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8_is_an_error() {
+        let events = parse_chunked(b"data: \xff\n\n", 3).await;
+        assert!(matches!(events[..], [Err(AdapterError::InvalidResponse(_))]));
+    }
+}
@@ -0,0 +1,129 @@
+//! Stream — consume a streamed reply without holding all of it in memory.
+//!
+//! [`write_stream`] copies each chunk of a [`ChunkStream`] to an
+//! [`io::Write`] sink as it arrives, and [`store_stream`] persists each one
+//! under its own [`MemorySystem`] key. Only one chunk is buffered at a time;
+//! both return a [`StreamSummary`] instead of the content.
+
+use std::io;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::adapter::{AdapterError, ChunkStream};
+use crate::memory::{MemoryError, MemorySystem};
+
+/// Errors produced while draining a [`ChunkStream`].
+#[derive(Debug, Error)]
+pub enum StreamError {
+    #[error(transparent)]
+    Adapter(#[from] AdapterError),
+    #[error("write failed: {0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+}
+
+/// What was streamed, without the content itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamSummary {
+    pub chunks: usize,
+    pub bytes: usize,
+    /// Size of the biggest chunk — the most content held at once.
+    pub largest_chunk: usize,
+}
+
+impl StreamSummary {
+    fn add(&mut self, chunk: &str) {
+        self.chunks += 1;
+        self.bytes += chunk.len();
+        self.largest_chunk = self.largest_chunk.max(chunk.len());
+    }
+}
+
+/// Write every chunk of `stream` to `writer` as it arrives, then flush.
+///
+/// On error the chunks received so far have already been written.
+pub async fn write_stream(
+    mut stream: ChunkStream<'_>,
+    writer: &mut impl io::Write,
+) -> Result<StreamSummary, StreamError> {
+    let mut summary = StreamSummary::default();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        writer.write_all(chunk.as_bytes())?;
+        summary.add(&chunk);
+    }
+    writer.flush()?;
+    Ok(summary)
+}
+
+/// Key of chunk `index` of a stream stored under `key`.
+pub fn chunk_key(key: &str, index: usize) -> String {
+    format!("{key}:chunk:{index:06}")
+}
+
+/// Store each chunk of `stream` under [`chunk_key`]`(key, i)` as it arrives,
+/// then the [`StreamSummary`] under `key`.
+///
+/// The summary is written last, so its presence marks a complete stream.
+pub async fn store_stream<M: MemorySystem>(
+    mut stream: ChunkStream<'_>,
+    memory: &M,
+    key: &str,
+) -> Result<StreamSummary, StreamError> {
+    let mut summary = StreamSummary::default();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        memory.store(&chunk_key(key, summary.chunks), chunk.as_str().into()).await?;
+        summary.add(&chunk);
+    }
+    let value = serde_json::to_value(summary).expect("summary serializes");
+    memory.store(key, value).await?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::{Adapter, Provider};
+    use crate::memory::InMemoryStore;
+    use crate::mock::MockAdapter;
+
+    #[tokio::test]
+    async fn writes_chunks_straight_to_writer() {
+        let reply = "0123456789abcdef".repeat(4_096);
+        let mock =
+            MockAdapter::new(Provider::Claude).with_reply(reply.clone()).with_chunk_size(1024);
+
+        let mut out = Vec::new();
+        let summary = write_stream(mock.chat_stream(&[]), &mut out).await.unwrap();
+        assert_eq!(out, reply.as_bytes());
+        assert_eq!(summary, StreamSummary { chunks: 64, bytes: reply.len(), largest_chunk: 1024 });
+    }
+
+    #[tokio::test]
+    async fn stores_chunks_under_separate_keys() {
+        let mock = MockAdapter::new(Provider::Claude).with_reply("abcdefg").with_chunk_size(3);
+        let memory = InMemoryStore::new();
+
+        let summary = store_stream(mock.chat_stream(&[]), &memory, "reply").await.unwrap();
+        assert_eq!(summary.chunks, 3);
+        let mut parts = Vec::new();
+        for i in 0..summary.chunks {
+            let record = memory.load(&chunk_key("reply", i)).await.unwrap();
+            parts.push(record.value.as_str().unwrap().to_owned());
+        }
+        assert_eq!(parts, ["abc", "def", "g"]);
+        let stored = memory.load("reply").await.unwrap().value;
+        assert_eq!(serde_json::from_value::<StreamSummary>(stored).unwrap(), summary);
+    }
+
+    #[tokio::test]
+    async fn adapter_errors_end_the_stream() {
+        let mock = MockAdapter::new(Provider::Claude).failing("reset");
+        let err = write_stream(mock.chat_stream(&[]), &mut Vec::new()).await.unwrap_err();
+        assert!(matches!(err, StreamError::Adapter(AdapterError::Network(_))));
+    }
+}
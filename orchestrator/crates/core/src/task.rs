@@ -7,14 +7,27 @@
 //! 3. **Validate** — check outputs against the Task Metadata Schema.
 //! 4. **Complete** — persist state in [`MemorySystem`] and release resources.
 //!
-//! A task that fails any phase is rejected immediately.
+//! A task that fails any phase is rejected immediately. A task cancelled by
+//! its user is [aborted](Task::abort) instead, so it is not counted as a
+//! failure.
+//!
+//! A task given a [log sink](Task::with_log_sink) reports every phase
+//! transition to it.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::protocol::TaskMeta;
+use crate::id::{IdGenerator, RandomIdGenerator};
+use crate::metrics::Metrics;
+use crate::protocol::{LogEntry, LogLevel, LogSink, TaskMeta};
+use crate::schema::{self, SchemaRegistry};
 
 /// Errors that can occur during the task lifecycle.
 #[derive(Debug, Error)]
@@ -27,6 +40,9 @@ pub enum TaskError {
     ValidationFailed(String),
     #[error("completion failed: {0}")]
     CompletionFailed(String),
+    /// The whole run outlasted the runner's overall timeout.
+    #[error("overall timeout")]
+    OverallTimeout,
 }
 
 /// The four lifecycle phases.
@@ -39,6 +55,52 @@ pub enum TaskPhase {
     Validated,
     Completed,
     Failed,
+    /// Cancelled by the user before it could finish.
+    Aborted,
+}
+
+impl TaskPhase {
+    /// Every phase, in lifecycle order.
+    pub const ALL: [TaskPhase; 7] = [
+        Self::Pending,
+        Self::Initialized,
+        Self::Executing,
+        Self::Validated,
+        Self::Completed,
+        Self::Failed,
+        Self::Aborted,
+    ];
+
+    /// Whether a task in this phase is done for good.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Aborted)
+    }
+
+    /// Whether a task may move from this phase to `next`: one step along
+    /// `Pending → Initialized → Executing → Validated → Completed`, or from
+    /// any unfinished phase to `Failed` or `Aborted`.
+    pub fn can_transition_to(self, next: TaskPhase) -> bool {
+        use TaskPhase::*;
+        match (self, next) {
+            (Pending, Initialized)
+            | (Initialized, Executing)
+            | (Executing, Validated)
+            | (Validated, Completed) => true,
+            (from, Failed | Aborted) => !from.is_terminal(),
+            _ => false,
+        }
+    }
+
+    /// Display priority: `0` for tasks in flight (`Executing`, `Validated`),
+    /// `1` for tasks not yet running (`Pending`, `Initialized`), and `2` for
+    /// terminal states.
+    pub fn priority(self) -> u8 {
+        match self {
+            Self::Executing | Self::Validated => 0,
+            Self::Pending | Self::Initialized => 1,
+            Self::Completed | Self::Failed | Self::Aborted => 2,
+        }
+    }
 }
 
 /// Outcome of a successfully completed task.
@@ -48,6 +110,15 @@ pub struct TaskResult {
     pub output: serde_json::Value,
     pub phase: TaskPhase,
     pub completed_at: DateTime<Utc>,
+    /// Input tokens summed over every query the task made.
+    #[serde(default)]
+    pub total_input_tokens: u64,
+    /// Output tokens summed over every query the task made.
+    #[serde(default)]
+    pub total_output_tokens: u64,
+    /// Cost of those tokens at the runner's configured rates.
+    #[serde(default)]
+    pub estimated_cost: f64,
 }
 
 /// A single unit of work moving through the lifecycle.
@@ -60,100 +131,302 @@ pub struct Task {
     pub output: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// The phase the task was in when it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_in_phase: Option<TaskPhase>,
+    /// Why the task failed, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+    /// Why the user [aborted](Self::abort) the task, when given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_reason: Option<String>,
+    /// The task this one was [forked](Self::fork) from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<Uuid>,
+    #[serde(skip)]
+    log: PhaseLog,
+}
+
+/// Where a [`Task`] reports its phase transitions, if anywhere.
+#[derive(Clone, Default)]
+struct PhaseLog(Option<Arc<dyn LogSink>>);
+
+impl fmt::Debug for PhaseLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "PhaseLog(Some(..))" } else { "PhaseLog(None)" })
+    }
 }
 
 impl Task {
     /// Create a new task in the `Pending` phase.
     pub fn new(meta: TaskMeta, input: serde_json::Value) -> Self {
+        Self::new_with_ids(meta, input, &RandomIdGenerator)
+    }
+
+    /// Like [`new`](Self::new), taking the id from `ids`.
+    pub fn new_with_ids(meta: TaskMeta, input: serde_json::Value, ids: &dyn IdGenerator) -> Self {
         let now = Utc::now();
         Self {
-            id: Uuid::new_v4(),
+            id: ids.next_id(),
             meta,
             phase: TaskPhase::Pending,
             input,
             output: None,
             created_at: now,
             updated_at: now,
+            failed_in_phase: None,
+            failure_reason: None,
+            abort_reason: None,
+            forked_from: None,
+            log: PhaseLog::default(),
+        }
+    }
+
+    /// Report every phase transition to `sink`, as an entry from source
+    /// `"task"` correlated with the task's id. Its data holds the task id,
+    /// the `from` and `to` phases, and `elapsed_ms` spent in `from`.
+    pub fn with_log_sink(mut self, sink: Arc<dyn LogSink>) -> Self {
+        self.log = PhaseLog(Some(sink));
+        self
+    }
+
+    /// A new `Pending` task with the same metadata and input as this one,
+    /// linked back to it through [`forked_from`](Self::forked_from).
+    ///
+    /// Works from any phase, e.g. to reprocess a completed or failed task.
+    pub fn fork(&self) -> Task {
+        self.fork_with_input(self.input.clone())
+    }
+
+    /// Like [`fork`](Self::fork), but with `input` in place of the original.
+    /// The fork reports to the same log sink.
+    pub fn fork_with_input(&self, input: serde_json::Value) -> Task {
+        self.fork_with_ids(input, &RandomIdGenerator)
+    }
+
+    /// Like [`fork_with_input`](Self::fork_with_input), taking the fork's id
+    /// from `ids`.
+    pub fn fork_with_ids(&self, input: serde_json::Value, ids: &dyn IdGenerator) -> Task {
+        Task {
+            forked_from: Some(self.id),
+            log: self.log.clone(),
+            ..Task::new_with_ids(self.meta.clone(), input, ids)
         }
     }
 
     /// Advance to `Initialized` after verifying inputs.
     pub fn initialize(&mut self) -> Result<(), TaskError> {
-        if self.phase != TaskPhase::Pending {
-            return Err(TaskError::InitFailed(format!(
-                "expected Pending, got {:?}",
-                self.phase
-            )));
+        self.initialize_against(None)
+    }
+
+    /// Like [`initialize`](Self::initialize), but when `schemas` has an input
+    /// schema for the task's kind, the input must conform to it.
+    pub fn initialize_with(&mut self, schemas: &SchemaRegistry) -> Result<(), TaskError> {
+        self.initialize_against(schemas.get(&self.meta.kind))
+    }
+
+    fn initialize_against(&mut self, schema: Option<serde_json::Value>) -> Result<(), TaskError> {
+        self.check_transition(TaskPhase::Initialized, TaskError::InitFailed)?;
+        if let Some(schema) = schema {
+            schema::validate(&schema, &self.input).map_err(|e| {
+                let kind = &self.meta.kind;
+                TaskError::InitFailed(format!("input does not match `{kind}` schema: {e}"))
+            })?;
         }
-        self.phase = TaskPhase::Initialized;
-        self.updated_at = Utc::now();
+        self.enter(TaskPhase::Initialized);
         Ok(())
     }
 
     /// Begin execution — marks the task as `Executing`.
     pub fn begin_execution(&mut self) -> Result<(), TaskError> {
-        if self.phase != TaskPhase::Initialized {
-            return Err(TaskError::ExecFailed(format!(
-                "expected Initialized, got {:?}",
-                self.phase
-            )));
-        }
-        self.phase = TaskPhase::Executing;
-        self.updated_at = Utc::now();
+        self.check_transition(TaskPhase::Executing, TaskError::ExecFailed)?;
+        self.enter(TaskPhase::Executing);
         Ok(())
     }
 
     /// Attach output and mark as `Validated`.
     pub fn validate(&mut self, output: serde_json::Value) -> Result<(), TaskError> {
-        if self.phase != TaskPhase::Executing {
-            return Err(TaskError::ValidationFailed(format!(
-                "expected Executing, got {:?}",
-                self.phase
-            )));
-        }
+        self.check_transition(TaskPhase::Validated, TaskError::ValidationFailed)?;
         self.output = Some(output);
-        self.phase = TaskPhase::Validated;
-        self.updated_at = Utc::now();
+        self.enter(TaskPhase::Validated);
         Ok(())
     }
 
     /// Finalize and mark as `Completed`.
     pub fn complete(&mut self) -> Result<TaskResult, TaskError> {
-        if self.phase != TaskPhase::Validated {
-            return Err(TaskError::CompletionFailed(format!(
-                "expected Validated, got {:?}",
-                self.phase
-            )));
-        }
-        self.phase = TaskPhase::Completed;
-        self.updated_at = Utc::now();
+        self.check_transition(TaskPhase::Completed, TaskError::CompletionFailed)?;
+        self.enter(TaskPhase::Completed);
         Ok(TaskResult {
             task_id: self.id,
             output: self.output.clone().unwrap_or_default(),
             phase: self.phase,
             completed_at: self.updated_at,
+            total_input_tokens: 0,
+            total_output_tokens: 0,
+            estimated_cost: 0.0,
         })
     }
 
-    /// Mark the task as `Failed` from any phase.
+    /// Fail with `error` unless the task may move to `next`.
+    fn check_transition(
+        &self,
+        next: TaskPhase,
+        error: fn(String) -> TaskError,
+    ) -> Result<(), TaskError> {
+        if self.phase.can_transition_to(next) {
+            Ok(())
+        } else {
+            Err(error(format!("cannot move from {:?} to {next:?}", self.phase)))
+        }
+    }
+
+    /// Move to `next`, reporting the transition to the log sink.
+    fn enter(&mut self, next: TaskPhase) {
+        let (from, now) = (self.phase, Utc::now());
+        let elapsed_ms = (now - self.updated_at).num_milliseconds().max(0);
+        self.phase = next;
+        self.updated_at = now;
+        let Some(sink) = &self.log.0 else { return };
+        let level = match next {
+            TaskPhase::Failed => LogLevel::Error,
+            TaskPhase::Aborted => LogLevel::Warn,
+            _ => LogLevel::Info,
+        };
+        let entry = LogEntry::new(level, "task", format!("{from:?} -> {next:?}"))
+            .with_data(serde_json::json!({
+                "task_id": self.id,
+                "from": from,
+                "to": next,
+                "elapsed_ms": elapsed_ms,
+            }))
+            .with_correlation(self.id);
+        sink.emit(&entry);
+    }
+
+    /// Key that orders active tasks first, then by most recent update.
+    ///
+    /// ```
+    /// # use orchestrator_core::task::Task;
+    /// # fn sort(tasks: &mut [Task]) {
+    /// tasks.sort_by_key(Task::sort_key);
+    /// # }
+    /// ```
+    pub fn sort_key(&self) -> (u8, Reverse<DateTime<Utc>>) {
+        (self.phase.priority(), Reverse(self.updated_at))
+    }
+
+    /// Mark the task as `Failed` from any unfinished phase, recording that
+    /// phase in `failed_in_phase`. Failing a finished task, including one
+    /// already failed, changes nothing.
     pub fn fail(&mut self) {
-        self.phase = TaskPhase::Failed;
-        self.updated_at = Utc::now();
+        self.try_fail();
+    }
+
+    /// Like [`fail`](Self::fail), also recording `reason`.
+    pub fn fail_with(&mut self, reason: impl Into<String>) {
+        if self.try_fail() {
+            self.failure_reason = Some(reason.into());
+        }
+    }
+
+    /// Move to `Failed` if allowed, returning whether the task moved.
+    fn try_fail(&mut self) -> bool {
+        if !self.phase.can_transition_to(TaskPhase::Failed) {
+            return false;
+        }
+        self.failed_in_phase = Some(self.phase);
+        self.enter(TaskPhase::Failed);
+        true
+    }
+
+    /// Mark the task as `Aborted` on behalf of the user, recording `reason`.
+    ///
+    /// Unlike [`fail`](Self::fail) this leaves finished tasks alone: aborting
+    /// a completed, failed or already aborted task changes nothing.
+    pub fn abort(&mut self, reason: impl Into<String>) {
+        if !self.phase.can_transition_to(TaskPhase::Aborted) {
+            return;
+        }
+        self.abort_reason = Some(reason.into());
+        self.enter(TaskPhase::Aborted);
+    }
+}
+
+/// Latest known phase of each task, for monitoring.
+#[derive(Debug, Default)]
+pub struct TaskTracker {
+    phases: RwLock<HashMap<Uuid, TaskPhase>>,
+}
+
+impl TaskTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `task`'s current phase, replacing any earlier one.
+    pub fn track(&self, task: &Task) {
+        self.phases.write().unwrap().insert(task.id, task.phase);
+    }
+
+    /// Stop tracking task `id`.
+    pub fn forget(&self, id: Uuid) {
+        self.phases.write().unwrap().remove(&id);
+    }
+
+    /// Number of tracked tasks in `phase`.
+    pub fn count(&self, phase: TaskPhase) -> usize {
+        self.phases.read().unwrap().values().filter(|p| **p == phase).count()
+    }
+
+    /// Share of finished tasks that failed, or `None` before any finished.
+    ///
+    /// Aborted tasks were cancelled, not broken, so they count neither as
+    /// failures nor as finished.
+    pub fn failure_rate(&self) -> Option<f64> {
+        failure_rate(&self.phases.read().unwrap())
+    }
+}
+
+fn failure_rate(phases: &HashMap<Uuid, TaskPhase>) -> Option<f64> {
+    let count = |phase| phases.values().filter(|p| **p == phase).count();
+    let failed = count(TaskPhase::Failed);
+    let finished = failed + count(TaskPhase::Completed);
+    (finished > 0).then(|| failed as f64 / finished as f64)
+}
+
+impl Metrics for TaskTracker {
+    fn subsystem(&self) -> &'static str {
+        "tasks"
+    }
+
+    /// Tracked task count overall and in each phase, zeros included, plus
+    /// the [`failure_rate`](TaskTracker::failure_rate).
+    fn snapshot(&self) -> serde_json::Value {
+        let phases = self.phases.read().unwrap();
+        let counts: serde_json::Map<String, serde_json::Value> = TaskPhase::ALL
+            .iter()
+            .map(|phase| {
+                let name = serde_json::to_value(phase).expect("phase serializes");
+                let count = phases.values().filter(|p| *p == phase).count();
+                (name.as_str().unwrap_or_default().to_owned(), count.into())
+            })
+            .collect();
+        serde_json::json!({
+            "total": phases.len(),
+            "phases": counts,
+            "failure_rate": failure_rate(&phases),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::TaskMeta;
+    use crate::protocol::{MemoryLogSink, TaskMeta};
     use serde_json::json;
 
     fn sample_meta() -> TaskMeta {
-        TaskMeta {
-            origin: "test".into(),
-            kind: "unit_test".into(),
-            description: "sample task".into(),
-        }
+        TaskMeta::new("test", "unit_test", "sample task")
     }
 
     #[test]
@@ -175,6 +448,110 @@ mod tests {
         assert_eq!(result.output, json!({"response": "world"}));
     }
 
+    #[test]
+    fn lifecycle_logs_each_transition() {
+        let sink = MemoryLogSink::new();
+        let mut task = Task::new(sample_meta(), json!({"prompt": "hello"}))
+            .with_log_sink(Arc::new(sink.clone()));
+        task.initialize().unwrap();
+        task.begin_execution().unwrap();
+        task.validate(json!("world")).unwrap();
+        task.complete().unwrap();
+        // Refused transitions and no-op failures log nothing.
+        assert!(task.begin_execution().is_err());
+        task.fail();
+
+        let entries = sink.entries();
+        let moves: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|e| {
+                let data = e.data.as_ref().unwrap();
+                (data["from"].as_str().unwrap(), data["to"].as_str().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                ("pending", "initialized"),
+                ("initialized", "executing"),
+                ("executing", "validated"),
+                ("validated", "completed"),
+            ]
+        );
+        for entry in &entries {
+            assert_eq!(entry.source, "task");
+            assert_eq!(entry.level, LogLevel::Info);
+            assert_eq!(entry.correlation_id, Some(task.id));
+            assert_eq!(entry.data.as_ref().unwrap()["task_id"], json!(task.id));
+            assert!(entry.data.as_ref().unwrap()["elapsed_ms"].is_u64());
+        }
+
+        let mut failing = task.fork();
+        failing.fail_with("boom");
+        let last = sink.entries().pop().unwrap();
+        assert_eq!((last.level, last.message.as_str()), (LogLevel::Error, "Pending -> Failed"));
+        assert_eq!(last.correlation_id, Some(failing.id));
+    }
+
+    #[test]
+    fn fork_starts_a_linked_pending_task() {
+        let mut task = Task::new(sample_meta(), json!({"prompt": "hello"}));
+        task.initialize().unwrap();
+        task.begin_execution().unwrap();
+        task.validate(json!({"response": "world"})).unwrap();
+        task.complete().unwrap();
+
+        let fork = task.fork();
+        assert_eq!(fork.phase, TaskPhase::Pending);
+        assert_ne!(fork.id, task.id);
+        assert_eq!(fork.forked_from, Some(task.id));
+        assert_eq!((&fork.meta, &fork.input), (&task.meta, &task.input));
+        assert_eq!(fork.output, None);
+        assert!(fork.created_at >= task.updated_at);
+
+        let tweaked = task.fork_with_input(json!({"prompt": "hello again"}));
+        assert_eq!(tweaked.input, json!({"prompt": "hello again"}));
+        assert_eq!(tweaked.forked_from, Some(task.id));
+        assert_eq!(task.forked_from, None);
+
+        let ids = crate::id::SequentialIdGenerator::starting_at(7);
+        let numbered = task.fork_with_ids(json!({}), &ids);
+        assert_eq!((numbered.id, numbered.forked_from), (Uuid::from_u128(7), Some(task.id)));
+    }
+
+    #[test]
+    fn initialize_rejects_input_violating_kind_schema() {
+        let schemas = SchemaRegistry::new();
+        schemas.register(
+            "query",
+            json!({
+                "type": "object",
+                "properties": {
+                    "prompt": { "type": "string" },
+                    "system": { "type": "string" },
+                    "provider": { "type": "string" },
+                    "queries": { "type": "array", "items": { "type": "object" } }
+                }
+            }),
+        );
+        let meta = TaskMeta { kind: "query".into(), ..sample_meta() };
+
+        let mut task = Task::new(meta.clone(), json!({"prompt": 42}));
+        let err = task.initialize_with(&schemas).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "initialization failed: input does not match `query` schema: \
+             /prompt: expected string, got number"
+        );
+        assert_eq!(task.phase, TaskPhase::Pending);
+
+        let mut task = Task::new(meta.clone(), json!({"prompt": "hello", "provider": "claude"}));
+        task.initialize_with(&schemas).unwrap();
+        // Kinds without a schema, and plain `initialize`, accept any input.
+        Task::new(sample_meta(), json!(null)).initialize_with(&schemas).unwrap();
+        Task::new(meta, json!({"prompt": 42})).initialize().unwrap();
+    }
+
     #[test]
     fn out_of_order_rejected() {
         let mut task = Task::new(sample_meta(), json!({}));
@@ -188,6 +565,136 @@ mod tests {
         task.initialize().unwrap();
         task.fail();
         assert_eq!(task.phase, TaskPhase::Failed);
+        assert_eq!(task.failed_in_phase, Some(TaskPhase::Initialized));
+    }
+
+    #[test]
+    fn fail_records_active_phase_and_reason() {
+        let mut task = Task::new(sample_meta(), json!({}));
+        task.initialize().unwrap();
+        task.begin_execution().unwrap();
+        task.fail_with("provider timed out");
+        assert_eq!(task.failed_in_phase, Some(TaskPhase::Executing));
+        assert_eq!(task.failure_reason.as_deref(), Some("provider timed out"));
+
+        // A second failure does not overwrite the phase.
+        task.fail();
+        assert_eq!(task.failed_in_phase, Some(TaskPhase::Executing));
+
+        let back: Task = serde_json::from_value(serde_json::to_value(&task).unwrap()).unwrap();
+        assert_eq!(back.failed_in_phase, Some(TaskPhase::Executing));
+    }
+
+    #[test]
+    fn transition_matrix() {
+        use TaskPhase::*;
+        let allowed = [
+            (Pending, Initialized),
+            (Initialized, Executing),
+            (Executing, Validated),
+            (Validated, Completed),
+            (Pending, Failed),
+            (Initialized, Failed),
+            (Executing, Failed),
+            (Validated, Failed),
+            (Pending, Aborted),
+            (Initialized, Aborted),
+            (Executing, Aborted),
+            (Validated, Aborted),
+        ];
+        for from in TaskPhase::ALL {
+            for to in TaskPhase::ALL {
+                let expected = allowed.contains(&(from, to));
+                assert_eq!(from.can_transition_to(to), expected, "{from:?} -> {to:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn illegal_moves_name_both_phases() {
+        let mut task = Task::new(sample_meta(), json!({}));
+        let err = task.validate(json!({})).unwrap_err();
+        assert_eq!(err.to_string(), "validation failed: cannot move from Pending to Validated");
+        assert_eq!(task.phase, TaskPhase::Pending);
+    }
+
+    #[test]
+    fn abort_is_a_distinct_terminal_state() {
+        let mut task = Task::new(sample_meta(), json!({}));
+        task.initialize().unwrap();
+        task.begin_execution().unwrap();
+        task.abort("cancelled from the TUI");
+        assert_eq!(task.phase, TaskPhase::Aborted);
+        assert_eq!(task.abort_reason.as_deref(), Some("cancelled from the TUI"));
+        assert_eq!((task.failed_in_phase, task.failure_reason.as_deref()), (None, None));
+        assert!(task.validate(json!({})).is_err());
+
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["phase"], "aborted");
+        let back: Task = serde_json::from_value(json).unwrap();
+        assert_eq!(back.phase, TaskPhase::Aborted);
+
+        // Finished tasks stay as they are.
+        let mut failed = Task::new(sample_meta(), json!({}));
+        failed.fail();
+        failed.abort("too late");
+        assert_eq!(failed.phase, TaskPhase::Failed);
+        assert_eq!(failed.abort_reason, None);
+    }
+
+    #[test]
+    fn failure_rate_ignores_aborted_tasks() {
+        let tracker = TaskTracker::new();
+        assert_eq!(tracker.failure_rate(), None);
+        for phase in [TaskPhase::Completed, TaskPhase::Completed, TaskPhase::Completed] {
+            let mut task = Task::new(sample_meta(), json!({}));
+            task.phase = phase;
+            tracker.track(&task);
+        }
+        let mut failed = Task::new(sample_meta(), json!({}));
+        failed.fail();
+        tracker.track(&failed);
+        let mut aborted = Task::new(sample_meta(), json!({}));
+        aborted.abort("user cancelled");
+        tracker.track(&aborted);
+
+        assert_eq!(tracker.failure_rate(), Some(0.25));
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot["failure_rate"], 0.25);
+        assert_eq!(snapshot["phases"]["aborted"], 1);
+        assert_eq!(snapshot["phases"]["failed"], 1);
+    }
+
+    #[test]
+    fn sort_key_puts_active_tasks_first() {
+        let at = |phase, secs| {
+            let mut task = Task::new(sample_meta(), json!({}));
+            task.phase = phase;
+            task.updated_at = DateTime::from_timestamp(secs, 0).unwrap();
+            task
+        };
+        let mut tasks = [
+            at(TaskPhase::Completed, 50),
+            at(TaskPhase::Pending, 10),
+            at(TaskPhase::Executing, 20),
+            at(TaskPhase::Failed, 60),
+            at(TaskPhase::Initialized, 30),
+            at(TaskPhase::Validated, 40),
+        ];
+        tasks.sort_by_key(Task::sort_key);
+
+        let order: Vec<TaskPhase> = tasks.iter().map(|t| t.phase).collect();
+        assert_eq!(
+            order,
+            vec![
+                TaskPhase::Validated,
+                TaskPhase::Executing,
+                TaskPhase::Initialized,
+                TaskPhase::Pending,
+                TaskPhase::Failed,
+                TaskPhase::Completed,
+            ]
+        );
     }
 
     #[test]
@@ -0,0 +1,84 @@
+//! Text helpers — cut strings for display without splitting a character.
+//!
+//! Slicing a `&str` by byte offset panics when the offset falls inside a
+//! multi-byte character, so previews of model output, log messages or ids
+//! go through these instead. Digests are shown and stored as hex.
+
+use std::borrow::Cow;
+
+/// The first `max_chars` characters of `s`, or all of `s` if it is shorter.
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// `s` cut to at most `max_chars` characters, ending in `…` when anything
+/// was cut. The ellipsis counts toward the limit.
+pub fn preview(s: &str, max_chars: usize) -> Cow<'_, str> {
+    if s.char_indices().nth(max_chars).is_none() {
+        Cow::Borrowed(s)
+    } else if max_chars == 0 {
+        Cow::Borrowed("")
+    } else {
+        Cow::Owned(format!("{}…", truncate_chars(s, max_chars - 1)))
+    }
+}
+
+/// Lowercase hex encoding of `bytes`.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The bytes of a hex string, or `None` if it is not one.
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncation_counts_characters_not_bytes() {
+        assert_eq!(truncate_chars("héllo wörld", 4), "héll");
+        assert_eq!(truncate_chars("🦀🦀🦀", 2), "🦀🦀");
+        assert_eq!(truncate_chars("naïve", 10), "naïve");
+        assert_eq!(truncate_chars("abc", 0), "");
+        assert_eq!(truncate_chars("", 3), "");
+        // Every cut point of a string mixing one- to four-byte characters.
+        let mixed = "aé€🦀";
+        for n in 0..6 {
+            assert_eq!(truncate_chars(mixed, n).chars().count(), n.min(4));
+        }
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(to_hex(&[0x00, 0xab, 0x7f]), "00ab7f");
+        assert_eq!(from_hex("00ab7f"), Some(vec![0x00, 0xab, 0x7f]));
+        assert_eq!(from_hex("00AB"), Some(vec![0x00, 0xab]));
+        assert_eq!(from_hex(""), Some(Vec::new()));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+        assert_eq!(from_hex("é1"), None);
+    }
+
+    #[test]
+    fn preview_marks_cuts_with_an_ellipsis() {
+        assert_eq!(preview("short", 5), "short");
+        assert_eq!(preview("crème brûlée", 6), "crème…");
+        assert_eq!(preview("🦀🦀🦀🦀", 3), "🦀🦀…");
+        assert_eq!(preview("🦀🦀🦀🦀", 3).chars().count(), 3);
+        assert_eq!(preview("abc", 1), "…");
+        assert_eq!(preview("abc", 0), "");
+        assert!(matches!(preview("fits", 10), Cow::Borrowed(_)));
+    }
+}
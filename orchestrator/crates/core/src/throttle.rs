@@ -0,0 +1,207 @@
+//! ThrottledAdapter — keep traffic under an account-wide request rate.
+//!
+//! A [`TokenBucket`] refills at a fixed number of requests per second. Every
+//! [`ThrottledAdapter`] built from clones of one bucket draws from it, so
+//! adapters used by different tasks, or wrapping different backends of the
+//! same account, share a single limit.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::adapter::{
+    Adapter, AdapterError, Feature, Message, ModelResponse, ModelTier, Provider,
+};
+use crate::prompt::Prompt;
+
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    per_second: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+}
+
+/// A shared request-rate limit. Clones draw from the same bucket.
+///
+/// Waiters are served first come, first served: the bucket's lock is a FIFO
+/// queue and a waiter holds it until its token arrives, so a steady stream
+/// of new callers cannot starve an earlier one.
+#[derive(Clone)]
+pub struct TokenBucket {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl TokenBucket {
+    /// Allow `per_second` requests a second, one at a time.
+    ///
+    /// # Panics
+    ///
+    /// If `per_second` is not positive and finite.
+    pub fn new(per_second: f64) -> Self {
+        Self::with_burst(per_second, 1)
+    }
+
+    /// Like [`new`](Self::new), but let up to `burst` requests through at
+    /// once after an idle spell. The bucket starts full.
+    pub fn with_burst(per_second: f64, burst: u32) -> Self {
+        assert!(
+            per_second.is_finite() && per_second > 0.0,
+            "requests per second must be positive, got {per_second}"
+        );
+        let capacity = f64::from(burst.max(1));
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: capacity,
+                capacity,
+                per_second,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// Wait for a token and take it.
+    pub async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        bucket.refill(Instant::now());
+        if bucket.tokens < 1.0 {
+            let wait = (1.0 - bucket.tokens) / bucket.per_second;
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            bucket.refill(Instant::now());
+        }
+        bucket.tokens -= 1.0;
+    }
+}
+
+/// Adapter decorator that waits for a [`TokenBucket`] token before each
+/// `chat`.
+///
+/// Health checks and warm-up bypass the bucket, so probing a provider never
+/// delays real traffic.
+pub struct ThrottledAdapter<A> {
+    inner: A,
+    bucket: TokenBucket,
+}
+
+impl<A: Adapter> ThrottledAdapter<A> {
+    /// Wrap `inner`, drawing from `bucket` (pass a clone to share it).
+    pub fn new(inner: A, bucket: TokenBucket) -> Self {
+        Self { inner, bucket }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    pub fn bucket(&self) -> &TokenBucket {
+        &self.bucket
+    }
+}
+
+#[async_trait]
+impl<A: Adapter> Adapter for ThrottledAdapter<A> {
+    fn provider(&self) -> Provider {
+        self.inner.provider()
+    }
+
+    fn tier(&self) -> ModelTier {
+        self.inner.tier()
+    }
+
+    fn model(&self) -> Option<String> {
+        self.inner.model()
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.inner.features()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<ModelResponse, AdapterError> {
+        self.bucket.acquire().await;
+        self.inner.chat(messages).await
+    }
+
+    async fn chat_prompt(&self, prompt: &Prompt) -> Result<ModelResponse, AdapterError> {
+        self.bucket.acquire().await;
+        self.inner.chat_prompt(prompt).await
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        self.inner.health_check().await
+    }
+
+    async fn warm_up(&self) -> Result<(), AdapterError> {
+        self.inner.warm_up().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Role;
+    use crate::mock::MockAdapter;
+
+    fn ask(n: usize) -> Vec<Message> {
+        vec![Message { role: Role::User, content: format!("request {n}") }]
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_burst_stays_under_the_rate_in_arrival_order() {
+        const QPS: f64 = 10.0;
+        let bucket = TokenBucket::new(QPS);
+        // Two adapters, as two tasks would hold, sharing one limit.
+        let a = ThrottledAdapter::new(MockAdapter::new(Provider::Claude), bucket.clone());
+        let b = ThrottledAdapter::new(MockAdapter::new(Provider::Claude), bucket);
+        let start = Instant::now();
+
+        let calls = (0..35).map(|n| {
+            let adapter = if n % 2 == 0 { &a } else { &b };
+            async move {
+                adapter.chat(&ask(n)).await.unwrap();
+                (n, Instant::now())
+            }
+        });
+        let done = futures::future::join_all(calls).await;
+
+        let mut by_time = done.clone();
+        by_time.sort_by_key(|&(_, at)| at);
+        let order: Vec<usize> = by_time.iter().map(|&(n, _)| n).collect();
+        assert_eq!(order, (0..35).collect::<Vec<_>>(), "served first come, first served");
+
+        let times: Vec<Instant> = by_time.iter().map(|&(_, at)| at).collect();
+        for (i, &from) in times.iter().enumerate() {
+            let in_window = times[i..].iter().take_while(|&&t| t < from + Duration::from_secs(1));
+            assert!(in_window.count() <= QPS as usize, "window starting at call {i}");
+        }
+        assert!(start.elapsed() >= Duration::from_millis(3400));
+        assert_eq!(a.inner().calls() + b.inner().calls(), 35);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_is_served_at_once_after_idling() {
+        let bucket = TokenBucket::with_burst(2.0, 3);
+        let adapter = ThrottledAdapter::new(MockAdapter::new(Provider::Grok), bucket);
+        let start = Instant::now();
+        for n in 0..3 {
+            adapter.chat(&ask(n)).await.unwrap();
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        adapter.chat(&ask(3)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(500));
+
+        // Health checks never wait for a token.
+        let before = Instant::now();
+        adapter.health_check().await.unwrap();
+        assert_eq!(before.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,347 @@
+//! WorkerPool — identical workers draining a shared [`TaskQueue`].
+//!
+//! Each worker is a tokio task that pops the next task and runs it through a
+//! shared [`TaskRunner`], so at most `workers` tasks run at once. The pool is
+//! itself an [`Agent`]: registered with an [`Orchestrator`], every tick
+//! spawns any worker not yet started and restarts any that died, e.g. by
+//! panicking mid-task.
+//!
+//! [`Orchestrator`]: crate::orchestrator::Orchestrator
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::agent::{Agent, AgentMetadata};
+use crate::capability::CapabilityInfo;
+use crate::logic::CoreLogic;
+use crate::memory::MemorySystem;
+use crate::metrics::Metrics;
+use crate::protocol::Message;
+use crate::runner::TaskRunner;
+use crate::task::Task;
+
+/// First-in, first-out queue of tasks shared by the workers of a
+/// [`WorkerPool`]. Clones share the same queue.
+#[derive(Debug, Clone, Default)]
+pub struct TaskQueue {
+    inner: Arc<QueueInner>,
+}
+
+#[derive(Debug, Default)]
+struct QueueInner {
+    tasks: Mutex<VecDeque<Task>>,
+    closed: AtomicBool,
+    ready: Notify,
+}
+
+impl TaskQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enqueue `task`, waking one waiting worker.
+    pub fn push(&self, task: Task) {
+        self.inner.tasks.lock().unwrap().push_back(task);
+        self.inner.ready.notify_one();
+    }
+
+    /// The next task, waiting for one if the queue is empty. `None` once the
+    /// queue is closed and drained.
+    pub async fn pop(&self) -> Option<Task> {
+        loop {
+            let ready = self.inner.ready.notified();
+            if let Some(task) = self.inner.tasks.lock().unwrap().pop_front() {
+                return Some(task);
+            }
+            if self.is_closed() {
+                return None;
+            }
+            ready.await;
+        }
+    }
+
+    /// Close the queue: workers finish the tasks still queued, then exit.
+    pub fn close(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        self.inner.ready.notify_waiters();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::SeqCst)
+    }
+
+    /// Tasks waiting for a worker.
+    pub fn len(&self) -> usize {
+        self.inner.tasks.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Live counters of a [`WorkerPool`], readable while its workers run.
+/// Clones share the same counters.
+#[derive(Debug, Clone)]
+pub struct PoolStats {
+    counters: Arc<Counters>,
+    queue: TaskQueue,
+    size: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    alive: AtomicUsize,
+    busy: AtomicUsize,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    restarts: AtomicU64,
+}
+
+impl PoolStats {
+    /// Workers currently running a task.
+    pub fn busy(&self) -> usize {
+        self.counters.busy.load(Ordering::SeqCst)
+    }
+
+    /// Running workers waiting for a task.
+    pub fn idle(&self) -> usize {
+        self.counters.alive.load(Ordering::SeqCst).saturating_sub(self.busy())
+    }
+
+    /// Tasks the runner completed successfully.
+    pub fn completed(&self) -> u64 {
+        self.counters.completed.load(Ordering::SeqCst)
+    }
+
+    /// Tasks the runner failed.
+    pub fn failed(&self) -> u64 {
+        self.counters.failed.load(Ordering::SeqCst)
+    }
+
+    /// Workers replaced after dying.
+    pub fn restarts(&self) -> u64 {
+        self.counters.restarts.load(Ordering::SeqCst)
+    }
+}
+
+impl Metrics for PoolStats {
+    fn subsystem(&self) -> &'static str {
+        "workers"
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "workers": self.size,
+            "busy": self.busy(),
+            "idle": self.idle(),
+            "queued": self.queue.len(),
+            "completed": self.completed(),
+            "failed": self.failed(),
+            "restarts": self.restarts(),
+        })
+    }
+}
+
+/// Increments a gauge for as long as it is held, even if the holder panics.
+struct Gauge<'a>(&'a AtomicUsize);
+
+impl<'a> Gauge<'a> {
+    fn hold(gauge: &'a AtomicUsize) -> Self {
+        gauge.fetch_add(1, Ordering::SeqCst);
+        Self(gauge)
+    }
+}
+
+impl Drop for Gauge<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A fixed number of workers running tasks from a [`TaskQueue`].
+///
+/// Workers start on the pool's first [`tick`](Agent::tick). Tasks are
+/// counted in [`PoolStats`] as completed or failed by the outcome of
+/// [`TaskRunner::run`]; the runner persists them as usual.
+pub struct WorkerPool<L, M> {
+    meta: AgentMetadata,
+    runner: Arc<TaskRunner<L, M>>,
+    queue: TaskQueue,
+    size: usize,
+    workers: Vec<JoinHandle<()>>,
+    counters: Arc<Counters>,
+}
+
+impl<L, M> WorkerPool<L, M>
+where
+    L: CoreLogic + 'static,
+    M: MemorySystem + 'static,
+{
+    /// Default number of workers.
+    pub const DEFAULT_WORKERS: usize = 4;
+
+    pub fn new(id: impl Into<String>, runner: TaskRunner<L, M>, queue: TaskQueue) -> Self {
+        let id = id.into();
+        Self {
+            meta: AgentMetadata {
+                name: id.clone(),
+                id,
+                version: env!("CARGO_PKG_VERSION").into(),
+                capabilities: Vec::new(),
+            },
+            runner: Arc::new(runner),
+            queue,
+            size: Self::DEFAULT_WORKERS,
+            workers: Vec::new(),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Run up to `n` tasks at once. At least one worker is always kept.
+    pub fn with_workers(mut self, n: usize) -> Self {
+        self.size = n.max(1);
+        self
+    }
+
+    pub fn queue(&self) -> &TaskQueue {
+        &self.queue
+    }
+
+    pub fn runner(&self) -> &TaskRunner<L, M> {
+        &self.runner
+    }
+
+    /// A handle on the pool's counters that outlives handing the pool to
+    /// an orchestrator.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats { counters: self.counters.clone(), queue: self.queue.clone(), size: self.size }
+    }
+
+    /// Start missing workers and replace dead ones. Once the queue is closed
+    /// and drained, finished workers are left to rest.
+    fn supervise(&mut self) {
+        let drained = self.queue.is_closed() && self.queue.is_empty();
+        let Self { runner, queue, workers, counters, .. } = self;
+        for worker in workers.iter_mut() {
+            if worker.is_finished() && !drained {
+                *worker = spawn_worker(runner, queue, counters);
+                counters.restarts.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        while workers.len() < self.size {
+            workers.push(spawn_worker(runner, queue, counters));
+        }
+    }
+}
+
+/// Spawn a worker that runs tasks from `queue` until it is closed and drained.
+fn spawn_worker<L, M>(
+    runner: &Arc<TaskRunner<L, M>>,
+    queue: &TaskQueue,
+    counters: &Arc<Counters>,
+) -> JoinHandle<()>
+where
+    L: CoreLogic + 'static,
+    M: MemorySystem + 'static,
+{
+    let runner = runner.clone();
+    let queue = queue.clone();
+    let counters = counters.clone();
+    tokio::spawn(async move {
+        let _alive = Gauge::hold(&counters.alive);
+        while let Some(mut task) = queue.pop().await {
+            let outcome = {
+                let _busy = Gauge::hold(&counters.busy);
+                runner.run(&mut task).await
+            };
+            let counter = match outcome {
+                Ok(_) => &counters.completed,
+                Err(_) => &counters.failed,
+            };
+            counter.fetch_add(1, Ordering::SeqCst);
+        }
+    })
+}
+
+#[async_trait]
+impl<L, M> Agent for WorkerPool<L, M>
+where
+    L: CoreLogic + 'static,
+    M: MemorySystem + 'static,
+{
+    fn metadata(&self) -> &AgentMetadata {
+        &self.meta
+    }
+
+    async fn init(&mut self, _: Vec<CapabilityInfo>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn tick(&mut self) -> anyhow::Result<Vec<Message>> {
+        self.supervise();
+        Ok(Vec::new())
+    }
+
+    fn interests(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    async fn on_message(&mut self, _: Message) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Busy while any task is running or queued.
+    fn is_busy(&self) -> bool {
+        self.counters.busy.load(Ordering::SeqCst) > 0 || !self.queue.is_empty()
+    }
+}
+
+impl<L, M> Drop for WorkerPool<L, M> {
+    fn drop(&mut self) {
+        for worker in &self.workers {
+            worker.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::TaskMeta;
+
+    fn task(n: u32) -> Task {
+        let meta = TaskMeta::new("test", "queue", format!("task {n}"));
+        Task::new(meta, json!({ "n": n }))
+    }
+
+    #[tokio::test]
+    async fn queue_is_fifo_and_ends_when_closed() {
+        let queue = TaskQueue::new();
+        queue.push(task(1));
+        queue.push(task(2));
+        assert_eq!(queue.len(), 2);
+
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let mut seen = Vec::new();
+                while let Some(task) = queue.pop().await {
+                    seen.push(task.input["n"].as_u64().unwrap());
+                }
+                seen
+            }
+        });
+        tokio::task::yield_now().await;
+        queue.push(task(3));
+        queue.close();
+        assert_eq!(waiter.await.unwrap(), [1, 2, 3]);
+        assert!(queue.is_empty());
+    }
+}
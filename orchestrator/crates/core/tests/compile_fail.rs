@@ -0,0 +1,7 @@
+//! Compile-fail checks for the typestate task lifecycle.
+
+#[test]
+fn phase_guard_rejects_out_of_order_transitions() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,86 @@
+//! End-to-end: a `CompositeTask` running three child tasks and aggregating
+//! their outputs into the parent's.
+
+use orchestrator_core::memory::InMemoryStore;
+use orchestrator_core::mock::MockLogic;
+use orchestrator_core::protocol::TaskMeta;
+use orchestrator_core::{CompositeTask, Task, TaskError, TaskPhase, TaskRunner};
+use serde_json::{Value, json};
+
+fn task(description: &str, input: Value) -> Task {
+    Task::new(TaskMeta::new("test", "query", description), input)
+}
+
+/// Joins the `content` of each child's answer.
+fn concatenate(outputs: Vec<Value>) -> Value {
+    let parts: Vec<&str> = outputs.iter().map(|o| o["content"].as_str().unwrap_or("")).collect();
+    Value::String(parts.join(" "))
+}
+
+#[tokio::test]
+async fn children_outputs_are_concatenated_into_the_parent() {
+    let runner = TaskRunner::new(MockLogic::new().with_usage(2, 3), InMemoryStore::new());
+    let mut composite = CompositeTask::new(task("greeting", json!({})))
+        .with_child(task("first", json!({ "prompt": "hello" })))
+        .with_child(task("second", json!({ "prompt": "composite" })))
+        .with_child(task("third", json!({ "prompt": "world" })))
+        .with_dependency(2, 0)
+        .with_dependency(2, 1)
+        .with_concurrency(2)
+        .with_aggregator(concatenate);
+
+    let result = composite.run(&runner).await.unwrap();
+    assert_eq!(result.output, json!("hello composite world"));
+    assert_eq!(result.task_id, composite.parent.id);
+    assert_eq!(composite.parent.phase, TaskPhase::Completed);
+    assert_eq!((result.total_input_tokens, result.total_output_tokens), (6, 9));
+    assert!(composite.children().all(|c| c.phase == TaskPhase::Completed));
+}
+
+#[tokio::test]
+async fn default_aggregator_collects_outputs_and_tolerates_optional_failures() {
+    let runner = TaskRunner::new(MockLogic::new().with_usage(2, 3), InMemoryStore::new());
+    let mut composite = CompositeTask::new(task("collect", json!({})))
+        .with_child(task("first", json!({ "prompt": "a" })))
+        // No prompt, so this child fails to initialize.
+        .with_optional_child(task("broken", json!({})));
+
+    let result = composite.run(&runner).await.unwrap();
+    let outputs = result.output.as_array().unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[0]["content"], "a");
+    assert_eq!(outputs[1], Value::Null);
+    assert!(matches!(composite.child_result(1), Some(Err(TaskError::InitFailed(_)))));
+}
+
+#[tokio::test]
+async fn failed_required_child_fails_the_parent_and_its_dependents() {
+    let runner = TaskRunner::new(MockLogic::new().with_usage(2, 3), InMemoryStore::new());
+    let mut composite = CompositeTask::new(task("parent", json!({})))
+        .with_child(task("broken", json!({})))
+        .with_child(task("dependent", json!({ "prompt": "never asked" })))
+        .with_child(task("independent", json!({ "prompt": "still runs" })))
+        .with_dependency(1, 0);
+
+    let error = composite.run(&runner).await.unwrap_err();
+    assert!(matches!(error, TaskError::ExecFailed(ref m) if m.contains("required child 0")));
+    assert_eq!(composite.parent.phase, TaskPhase::Failed);
+    let phases: Vec<TaskPhase> = composite.children().map(|c| c.phase).collect();
+    assert_eq!(phases, [TaskPhase::Failed, TaskPhase::Failed, TaskPhase::Completed]);
+    let dependent = composite.children().nth(1).unwrap();
+    assert_eq!(dependent.failed_in_phase, Some(TaskPhase::Pending));
+}
+
+#[tokio::test]
+async fn dependency_cycles_are_rejected_before_anything_runs() {
+    let runner = TaskRunner::new(MockLogic::new().with_usage(2, 3), InMemoryStore::new());
+    let mut composite = CompositeTask::new(task("cyclic", json!({})))
+        .with_child(task("a", json!({ "prompt": "a" })))
+        .with_child(task("b", json!({ "prompt": "b" })))
+        .with_dependency(0, 1)
+        .with_dependency(1, 0);
+
+    let error = composite.run(&runner).await.unwrap_err();
+    assert!(matches!(error, TaskError::InitFailed(ref m) if m.contains("cycle")));
+    assert!(composite.children().all(|c| c.phase == TaskPhase::Pending));
+}
@@ -0,0 +1,339 @@
+//! End-to-end: `DefaultLogic` routing queries through `MockAdapter`s.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use orchestrator_core::adapter::Role;
+use orchestrator_core::logic::DefaultLogic;
+use orchestrator_core::mock::MockAdapter;
+use orchestrator_core::{
+    AdapterRegistry, CoreLogic, Feature, LogicError, ModelTier, Provider, Query,
+};
+
+fn logic_with(mocks: &[Arc<MockAdapter>]) -> DefaultLogic {
+    let mut registry = AdapterRegistry::new();
+    for mock in mocks {
+        registry.register(mock.clone());
+    }
+    DefaultLogic::new(registry)
+}
+
+#[tokio::test]
+async fn query_reaches_pinned_adapter_with_mapped_messages() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_reply("bonjour le monde"));
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini));
+    let logic = logic_with(&[claude.clone(), gemini.clone()]);
+
+    let query = Query::new("translate hello world")
+        .with_system("You translate to French.")
+        .with_provider_enum(Provider::Claude);
+    let id = query.id;
+    let result = logic.query(query).await.unwrap();
+
+    assert_eq!(result.query_id, id);
+    assert_eq!(result.provider_used, "claude");
+    assert_eq!(result.content, "bonjour le monde");
+    assert_eq!(result.input_tokens, 7);
+    assert_eq!(result.output_tokens, 3);
+
+    let requests = claude.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0][0].role, Role::System);
+    assert_eq!(requests[0][1].role, Role::User);
+    assert_eq!(requests[0][1].content, "translate hello world");
+    assert_eq!(gemini.calls(), 0);
+    // The response fed the latency tracker.
+    assert!(logic.registry().latency().ewma_ms(Provider::Claude).is_some());
+}
+
+#[tokio::test]
+async fn adapters_receive_the_system_prompt_where_their_provider_expects_it() {
+    use futures::StreamExt;
+
+    let claude = Arc::new(MockAdapter::new(Provider::Claude));
+    let grok = Arc::new(MockAdapter::new(Provider::Grok));
+    let logic = logic_with(&[claude.clone(), grok.clone()]);
+    let ask = |provider| Query::new("hi").with_system("be brief").with_provider_enum(provider);
+
+    logic.query(ask(Provider::Claude)).await.unwrap();
+    let _: Vec<_> = logic.query_stream(ask(Provider::Claude)).collect().await;
+    for prompt in claude.prompts() {
+        assert_eq!(prompt.system.as_deref(), Some("be brief"));
+        assert_eq!(prompt.messages.len(), 1);
+    }
+    assert_eq!(claude.prompts().len(), 2);
+
+    logic.query(ask(Provider::Grok)).await.unwrap();
+    let prompt = &grok.prompts()[0];
+    assert_eq!(prompt.system, None);
+    assert_eq!(prompt.messages[0].role, Role::System);
+}
+
+#[tokio::test]
+async fn failures_mark_provider_unhealthy_and_reroute() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).failing("overloaded"));
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini).with_reply("fallback"));
+    let logic = logic_with(&[claude.clone(), gemini.clone()]);
+
+    let err = logic.query(Query::new("hi")).await.unwrap_err();
+    assert!(matches!(err, LogicError::ProviderUnavailable(ref m) if m.contains("overloaded")));
+    assert!(!logic.registry().health().is_healthy(Provider::Claude));
+
+    let result = logic.query(Query::new("hi again")).await.unwrap();
+    assert_eq!(result.provider_used, "gemini");
+    assert_eq!(result.content, "fallback");
+}
+
+#[tokio::test]
+async fn available_providers_reflect_health_and_features() {
+    let claude = Arc::new(
+        MockAdapter::new(Provider::Claude)
+            .with_tier(ModelTier::Powerful)
+            .with_features([Feature::Streaming, Feature::ToolUse])
+            .failing("overloaded"),
+    );
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini).with_features([Feature::Vision]));
+    let logic = logic_with(&[claude, gemini]);
+    logic.query(Query::new("hi").with_provider_enum(Provider::Claude)).await.unwrap_err();
+
+    let providers = logic.available_providers();
+    assert_eq!(providers.len(), 2);
+    let (claude, gemini) = (&providers[0], &providers[1]);
+    assert_eq!(claude.provider, Provider::Claude);
+    assert_eq!(claude.model.as_deref(), Some("claude-mock"));
+    assert_eq!(claude.tier, ModelTier::Powerful);
+    assert!(!claude.healthy);
+    assert_eq!(claude.features, [Feature::Streaming, Feature::ToolUse]);
+    assert_eq!(gemini.provider, Provider::Gemini);
+    assert!(gemini.healthy && !gemini.rate_limited);
+    assert_eq!(gemini.features, [Feature::Vision]);
+}
+
+#[tokio::test]
+async fn rate_limited_provider_is_skipped_not_marked_unhealthy() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).rate_limited(60_000));
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini).with_reply("fallback"));
+    let logic = logic_with(&[claude.clone(), gemini.clone()]);
+
+    assert!(logic.query(Query::new("hi")).await.is_err());
+    assert!(logic.registry().is_limited(Provider::Claude));
+    assert!(logic.registry().health().is_healthy(Provider::Claude));
+
+    let result = logic.query(Query::new("hi again")).await.unwrap();
+    assert_eq!(result.provider_used, "gemini");
+    assert_eq!(claude.calls(), 1);
+}
+
+#[tokio::test]
+async fn unbounded_retry_after_does_not_crash_the_query() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).rate_limited(u64::MAX));
+    let logic = logic_with(&[claude]);
+
+    let err = logic.query(Query::new("hi")).await.unwrap_err();
+    assert!(matches!(err, LogicError::ProviderUnavailable(_)), "{err}");
+    assert!(logic.registry().is_limited(Provider::Claude));
+}
+
+#[tokio::test]
+async fn batch_fans_out_over_query() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude));
+    let logic = logic_with(std::slice::from_ref(&claude));
+
+    let results = logic
+        .query_batch((0..4).map(|i| Query::new(format!("q{i}"))).collect())
+        .await;
+    assert_eq!(results.len(), 4);
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(claude.calls(), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn hedge_wins_when_first_provider_is_slow() {
+    let claude = Arc::new(
+        MockAdapter::new(Provider::Claude).with_reply("slow").with_delay(Duration::from_secs(5)),
+    );
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini).with_reply("fast"));
+    let logic = logic_with(&[claude.clone(), gemini.clone()]);
+
+    let started = tokio::time::Instant::now();
+    let result = logic
+        .query_hedged(
+            Query::new("hi"),
+            &[Provider::Claude, Provider::Gemini],
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.provider_used, "gemini");
+    assert_eq!(result.content, "fast");
+    assert_eq!(started.elapsed(), Duration::from_millis(50));
+    assert_eq!((claude.calls(), gemini.calls()), (1, 1));
+}
+
+#[tokio::test(start_paused = true)]
+async fn no_hedge_when_first_provider_answers_in_time() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_delay(Duration::from_millis(10)));
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini));
+    let logic = logic_with(&[claude.clone(), gemini.clone()]);
+
+    let providers = [Provider::Claude, Provider::Gemini];
+    let result = logic
+        .query_hedged(Query::new("hi"), &providers, Duration::from_millis(50))
+        .await
+        .unwrap();
+    assert_eq!(result.provider_used, "claude");
+    assert_eq!(gemini.calls(), 0);
+}
+
+#[tokio::test]
+async fn hedge_fails_over_immediately_on_error() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).failing("down"));
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini).failing("also down"));
+    let logic = logic_with(&[claude.clone(), gemini.clone()]);
+
+    let providers = [Provider::Claude, Provider::Gemini];
+    let err = logic
+        .query_hedged(Query::new("hi"), &providers, Duration::from_secs(60))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, LogicError::ProviderUnavailable(ref m) if m.contains("also down")));
+    assert_eq!((claude.calls(), gemini.calls()), (1, 1));
+}
+
+#[tokio::test(start_paused = true)]
+async fn provider_limits_cap_each_provider_independently() {
+    let delay = Duration::from_millis(100);
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_delay(delay));
+    let gemini = Arc::new(MockAdapter::new(Provider::Gemini).with_delay(delay));
+    let logic = logic_with(&[claude.clone(), gemini.clone()])
+        .with_provider_limit(Provider::Claude, 2)
+        .with_provider_limit(Provider::Gemini, 3);
+
+    let queries = (0..8)
+        .flat_map(|i| {
+            [
+                Query::new(format!("c{i}")).with_provider_enum(Provider::Claude),
+                Query::new(format!("g{i}")).with_provider_enum(Provider::Gemini),
+            ]
+        })
+        .collect();
+    let started = tokio::time::Instant::now();
+    let results = logic.query_batch(queries).await;
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!((claude.calls(), gemini.calls()), (8, 8));
+    assert_eq!((claude.peak_in_flight(), gemini.peak_in_flight()), (2, 3));
+    // Claude's four waves bound the batch; Gemini's three ran alongside.
+    assert_eq!(started.elapsed(), delay * 4);
+}
+
+#[tokio::test]
+async fn query_metadata_is_carried_onto_the_result() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude));
+    let logic = logic_with(&[claude]);
+
+    let query = Query::new("hi").with_meta("tenant", "acme").with_meta("user_id", 7);
+    let result = logic.query(query).await.unwrap();
+    assert_eq!(result.metadata["tenant"], "acme");
+    assert_eq!(result.metadata["user_id"], 7);
+}
+
+#[tokio::test(start_paused = true)]
+async fn running_query_can_be_cancelled_by_id() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_delay(Duration::from_secs(60)));
+    let logic = logic_with(&[claude]);
+    let in_flight = logic.in_flight().clone();
+
+    let query = Query::new("a long one");
+    let id = query.id;
+    let started = tokio::time::Instant::now();
+    let (result, cancelled) = tokio::join!(logic.query(query), async {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(in_flight.ids(), [id]);
+        in_flight.cancel(id)
+    });
+
+    assert!(cancelled);
+    assert!(matches!(result, Err(LogicError::Cancelled)), "{result:?}");
+    assert_eq!(started.elapsed(), Duration::from_secs(1));
+    assert!(in_flight.is_empty());
+    assert!(!in_flight.cancel(id));
+}
+
+#[tokio::test]
+async fn query_stream_yields_the_adapters_chunks() {
+    use futures::StreamExt;
+
+    let claude = MockAdapter::new(Provider::Claude).with_reply("streamed reply").with_chunk_size(4);
+    let logic = logic_with(&[Arc::new(claude)]);
+    let chunks: Vec<String> =
+        logic.query_stream(Query::new("hi")).map(Result::unwrap).collect().await;
+    assert_eq!(chunks, ["stre", "amed", " rep", "ly"]);
+    assert!(logic.registry().health().is_healthy(Provider::Claude));
+
+    let failing = logic_with(&[Arc::new(MockAdapter::new(Provider::Grok).failing("down"))]);
+    let results: Vec<_> = failing.query_stream(Query::new("hi")).collect().await;
+    assert!(matches!(
+        &results[..],
+        [Err(LogicError::ProviderUnavailable(m))] if m.contains("down")
+    ));
+    assert!(!failing.registry().health().is_healthy(Provider::Grok));
+}
+
+#[tokio::test]
+async fn empty_registry_fails_queries_with_a_dedicated_error() {
+    use futures::StreamExt;
+    use orchestrator_core::protocol::{LogLevel, MemoryLogSink};
+
+    let logic = logic_with(&[]);
+    let log = MemoryLogSink::new();
+    assert!(matches!(logic.validate(&log), Err(LogicError::NoProvidersRegistered)));
+    let warnings = log.entries();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].level, LogLevel::Warn);
+
+    let err = logic.query(Query::new("hi")).await.unwrap_err();
+    assert!(matches!(err, LogicError::NoProvidersRegistered), "{err}");
+    assert!(!err.is_retryable());
+    let pinned = Query::new("hi").with_provider_enum(Provider::Claude);
+    assert!(matches!(logic.query(pinned).await, Err(LogicError::NoProvidersRegistered)));
+    let streamed: Vec<_> = logic.query_stream(Query::new("hi")).collect().await;
+    assert!(matches!(&streamed[..], [Err(LogicError::NoProvidersRegistered)]));
+
+    let log = MemoryLogSink::new();
+    assert!(logic_with(&[Arc::new(MockAdapter::new(Provider::Claude))]).validate(&log).is_ok());
+    assert!(log.is_empty());
+}
+
+#[tokio::test]
+async fn client_errors_do_not_take_the_provider_out_of_routing() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_rejections(1, 400));
+    let logic = logic_with(std::slice::from_ref(&claude));
+
+    let err = logic.query(Query::new("malformed")).await.unwrap_err();
+    assert!(matches!(err, LogicError::ProviderUnavailable(ref m) if m.contains("400")), "{err}");
+    assert!(logic.registry().health().is_healthy(Provider::Claude));
+
+    let result = logic.query(Query::new("fine")).await.unwrap();
+    assert_eq!(result.provider_used, "claude");
+    assert_eq!(claude.calls(), 2);
+}
+
+#[tokio::test]
+async fn failed_provider_is_probed_again_once_its_ttl_expires() {
+    use orchestrator_core::registry::HealthCache;
+
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_failures(1, "blip"));
+    let mut registry = AdapterRegistry::new()
+        .with_health_cache(HealthCache::new().with_unhealthy_ttl(Duration::ZERO));
+    registry.register(claude.clone());
+    let logic = DefaultLogic::new(registry);
+
+    assert!(logic.query(Query::new("hi")).await.is_err());
+    assert!(logic.registry().health().get(Provider::Claude).is_some_and(|e| !e.healthy));
+    // The expired failure lets the next query through, and its success
+    // restores the provider.
+    let result = logic.query(Query::new("hi again")).await.unwrap();
+    assert_eq!(result.provider_used, "claude");
+    assert!(logic.registry().health().get(Provider::Claude).unwrap().healthy);
+}
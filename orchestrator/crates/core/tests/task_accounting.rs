@@ -0,0 +1,52 @@
+//! End-to-end: `TaskRunner` accounting for the tokens and cost of a task's
+//! queries.
+
+use std::sync::Arc;
+
+use orchestrator_core::adapter::FinishReason;
+use orchestrator_core::logic::DefaultLogic;
+use orchestrator_core::memory::InMemoryStore;
+use orchestrator_core::mock::MockAdapter;
+use orchestrator_core::protocol::TaskMeta;
+use orchestrator_core::runner::task_key;
+use orchestrator_core::{
+    AdapterRegistry, CostTable, MemorySystem, Provider, Rates, Task, TaskRunner,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn task_accounts_for_every_query() {
+    let claude = Arc::new(MockAdapter::new(Provider::Claude).with_script([
+        ("a four word reply", FinishReason::Stop),
+        ("two words", FinishReason::Stop),
+    ]));
+    let mut registry = AdapterRegistry::new();
+    registry.register(claude.clone());
+    let runner = TaskRunner::new(DefaultLogic::new(registry), InMemoryStore::new())
+        .with_cost_table(CostTable::new().with_rates(Provider::Claude, Rates::new(3.0, 15.0)));
+
+    let mut task = Task::new(
+        TaskMeta::new("test", "query", "two sub-queries"),
+        json!({ "queries": [
+            { "prompt": "summarise the report", "provider": "claude" },
+            { "prompt": "now shorter", "system": "Be brief.", "provider": "claude" },
+        ]}),
+    );
+    let result = runner.run(&mut task).await.unwrap();
+    assert_eq!(claude.calls(), 2);
+
+    let answers = result.output.as_array().unwrap();
+    let sum = |field: &str| -> u64 { answers.iter().map(|a| a[field].as_u64().unwrap()).sum() };
+    assert_eq!(result.total_input_tokens, sum("input_tokens"));
+    assert_eq!(result.total_output_tokens, sum("output_tokens"));
+    assert_eq!(
+        (result.total_input_tokens, result.total_output_tokens),
+        (7, 6)
+    );
+    let expected = Rates::new(3.0, 15.0).cost(7, 6);
+    assert!((result.estimated_cost - expected).abs() < 1e-12);
+
+    let record = runner.memory().load(&task_key(task.id)).await.unwrap();
+    assert_eq!(record.value["result"]["total_input_tokens"], 7);
+    assert_eq!(record.value["result"]["total_output_tokens"], 6);
+}
@@ -0,0 +1,37 @@
+//! End-to-end: a `TaskRunner` overall timeout failing a task whose provider
+//! call never returns in time.
+
+use std::time::Duration;
+
+use orchestrator_core::memory::InMemoryStore;
+use orchestrator_core::mock::MockLogic;
+use orchestrator_core::protocol::TaskMeta;
+use orchestrator_core::runner::task_key;
+use orchestrator_core::task::{TaskError, TaskPhase};
+use orchestrator_core::{MemorySystem, Task, TaskRunner};
+use serde_json::json;
+
+#[tokio::test(start_paused = true)]
+async fn overall_timeout_fails_and_persists_the_task() {
+    let logic = MockLogic::new().with_delay(Duration::from_secs(10));
+    let runner = TaskRunner::new(logic, InMemoryStore::new())
+        .with_overall_timeout(Duration::from_secs(1));
+
+    let mut task = Task::new(
+        TaskMeta::new("test", "query", "a query that outlasts the task"),
+        json!({ "prompt": "take your time" }),
+    );
+    let err = runner.run(&mut task).await.unwrap_err();
+    assert!(matches!(err, TaskError::OverallTimeout));
+    assert_eq!(task.phase, TaskPhase::Failed);
+    assert_eq!(task.failure_reason.as_deref(), Some("overall timeout"));
+
+    let record = runner.memory().load(&task_key(task.id)).await.unwrap();
+    assert_eq!(record.value["task"]["phase"], "failed");
+    assert_eq!(record.value["task"]["failure_reason"], "overall timeout");
+
+    // The provider call was dropped, not left running in the background.
+    tokio::time::sleep(Duration::from_secs(20)).await;
+    assert_eq!(runner.logic().calls(), 1);
+    assert_eq!(runner.logic().answered(), 0);
+}
@@ -0,0 +1,8 @@
+use orchestrator_core::phase_guard::PhaseGuard;
+use orchestrator_core::protocol::TaskMeta;
+
+fn main() {
+    let meta = TaskMeta::new("ui", "query", "out of order");
+    // Cannot execute before initializing.
+    let _ = PhaseGuard::new(meta, serde_json::json!({})).begin_execution();
+}
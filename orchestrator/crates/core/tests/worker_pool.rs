@@ -0,0 +1,79 @@
+//! End-to-end: an orchestrator-supervised `WorkerPool` draining a queue that
+//! holds more tasks than it has workers.
+
+use std::time::Duration;
+
+use orchestrator_core::memory::InMemoryStore;
+use orchestrator_core::mock::MockLogic;
+use orchestrator_core::protocol::TaskMeta;
+use orchestrator_core::{Metrics, Orchestrator, Task, TaskQueue, TaskRunner, WorkerPool};
+use serde_json::json;
+
+#[tokio::test(start_paused = true)]
+async fn pool_completes_every_task_with_bounded_concurrency() {
+    const TASKS: u64 = 10;
+    const WORKERS: usize = 3;
+
+    let logic = MockLogic::new().with_delay(Duration::from_millis(250));
+    let probe = logic.clone();
+    let queue = TaskQueue::new();
+    for n in 0..TASKS {
+        queue.push(Task::new(
+            TaskMeta::new("test", "query", format!("pooled query {n}")),
+            json!({ "prompt": format!("q{n}") }),
+        ));
+    }
+
+    let pool = WorkerPool::new("workers", TaskRunner::new(logic, InMemoryStore::new()), queue)
+        .with_workers(WORKERS);
+    let stats = pool.stats();
+    let mut orch = Orchestrator::new();
+    orch.register_agent(Box::new(pool));
+
+    let shutdown = orch.shutdown_handle();
+    let running = tokio::spawn(async move { orch.run().await });
+    while stats.completed() < TASKS {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let snapshot = stats.snapshot();
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+
+    assert_eq!(probe.peak_in_flight(), WORKERS);
+    assert_eq!(
+        snapshot,
+        json!({
+            "workers": WORKERS,
+            "busy": 0,
+            "idle": WORKERS,
+            "queued": 0,
+            "completed": TASKS,
+            "failed": 0,
+            "restarts": 0,
+        })
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn idle_timeout_waits_for_the_pool_to_drain() {
+    let logic = MockLogic::new().with_delay(Duration::from_millis(250));
+    let queue = TaskQueue::new();
+    for n in 0..6 {
+        queue.push(Task::new(
+            TaskMeta::new("test", "query", format!("pooled query {n}")),
+            json!({ "prompt": format!("q{n}") }),
+        ));
+    }
+    let pool = WorkerPool::new("workers", TaskRunner::new(logic, InMemoryStore::new()), queue)
+        .with_workers(2);
+    let stats = pool.stats();
+    // The pool's ticks return no messages; only its running tasks keep the
+    // orchestrator from idling out long before they finish.
+    let mut orch = Orchestrator::new().with_idle_timeout(Duration::from_millis(100));
+    orch.register_agent(Box::new(pool));
+
+    let report = orch.run().await.unwrap();
+    assert!(report.idle);
+    assert_eq!(stats.completed(), 6);
+    assert!(report.uptime >= Duration::from_millis(750), "{:?}", report.uptime);
+}
@@ -1,8 +1,22 @@
 //! TUI application state and rendering.
 
-use orchestrator_core::adapter::Provider;
-use orchestrator_core::protocol::{LogEntry, LogLevel, MemoryLogSink, LogSink};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use orchestrator_core::adapter::{HealthStatus, Provider};
+use futures::StreamExt;
+use orchestrator_core::logic::{CoreLogic, DefaultLogic, Query};
+use orchestrator_core::memory::{InMemoryStore, MemoryError, MemorySystem, Record};
+use orchestrator_core::protocol::{LogEntry, LogLevel, MemoryLogSink, LogSink, TaskMeta};
+use orchestrator_core::registry::ProviderInfo;
 use orchestrator_core::task::{Task, TaskPhase};
+use orchestrator_core::text::{preview, truncate_chars};
+use ratatui::text::Line;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 /// Which panel has keyboard focus.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,6 +25,7 @@ pub enum FocusPanel {
     Tasks,
     Braid,
     Logs,
+    Memory,
 }
 
 impl FocusPanel {
@@ -19,17 +34,46 @@ impl FocusPanel {
             Self::Providers => Self::Tasks,
             Self::Tasks => Self::Braid,
             Self::Braid => Self::Logs,
-            Self::Logs => Self::Providers,
+            Self::Logs => Self::Memory,
+            Self::Memory => Self::Providers,
         }
     }
 }
 
 /// Braid state representing the tri-weavon resonance.
+///
+/// Serializable, so a reading can be stored in a [`MemorySystem`] and shown
+/// again in a later session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BraidStatus {
     pub alpha: f64,
     pub omega: f64,
     pub phi: f64,
-    pub status: &'static str,
+    pub status: String,
+}
+
+impl BraidStatus {
+    pub fn new(alpha: f64, omega: f64, phi: f64, status: impl Into<String>) -> Self {
+        Self { alpha, omega, phi, status: status.into() }
+    }
+}
+
+/// Scores the braid shown in the braid panel from the app's current state.
+///
+/// [`App`] uses [`DefaultCoherence`] unless another metric is supplied with
+/// [`App::with_metric`].
+pub trait CoherenceMetric {
+    fn compute(&self, app: &App) -> BraidStatus;
+}
+
+/// The built-in fixed tri-weavon reading.
+#[derive(Debug, Default)]
+pub struct DefaultCoherence;
+
+impl CoherenceMetric for DefaultCoherence {
+    fn compute(&self, _: &App) -> BraidStatus {
+        BraidStatus::new(8.0, 7.0, 0.82, "RESONANT")
+    }
 }
 
 /// Top-level application state shared between the event loop and the renderer.
@@ -38,14 +82,398 @@ pub struct App {
     pub focus: FocusPanel,
     pub providers: Vec<ProviderStatus>,
     pub tasks: Vec<TaskEntry>,
-    pub braid: BraidStatus,
+    pub metric: Box<dyn CoherenceMetric>,
     pub log_sink: MemoryLogSink,
+    pub logs: LogPanel,
+    pub memory: MemoryPanel,
+    pub watch: WatchPanel,
+    pub latency: LatencyWindow,
+    pub result: ResultPanel,
+    /// Shown in a banner across the top while set, e.g. when no providers
+    /// are registered.
+    pub warning: Option<String>,
+    /// Text typed at the open prompt, while input mode is on.
+    pub input: Option<String>,
+    /// What the open prompt is for.
+    pub prompt: Prompt,
+}
+
+/// The prompts that take typed input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Prompt {
+    /// The key or key prefix to follow in the watch panel.
+    #[default]
+    Watch,
+    /// A query to ask, answered in the result panel.
+    Query,
+}
+
+/// Entries shown in the log panel, newest first.
+pub const LOG_PANEL_ENTRIES: usize = 100;
+
+/// Selection and detail view of the log panel.
+///
+/// The list itself stays compact — one line per entry. When the panel is
+/// focused and the detail view is open, the selected entry's `data` payload
+/// is shown beneath its message line.
+///
+/// The panel follows the sink incrementally: each frame copies only the
+/// entries emitted since the last one, and the lines are laid out again
+/// only when those arrive or the selection or view changes.
+#[derive(Debug, Default)]
+pub struct LogPanel {
+    /// Index into the newest-first list of entries.
+    pub selected: usize,
+    /// Show the selected entry's data payload.
+    pub expanded: bool,
+    /// Pretty-print the payload instead of keeping it on one line.
+    pub pretty: bool,
+    cache: RefCell<LogCache>,
+}
+
+/// What the log panel last read from its sink and drew.
+#[derive(Debug, Default)]
+struct LogCache {
+    /// Sink entries read so far.
+    seen: usize,
+    /// The newest [`LOG_PANEL_ENTRIES`] entries, newest first.
+    recent: VecDeque<LogEntry>,
+    lines: Vec<Line<'static>>,
+    /// The view the lines were laid out for: `(selected, expanded, pretty)`
+    /// while focused, `None` otherwise. `None` once new entries arrive.
+    layout: Option<Option<(usize, bool, bool)>>,
+    layouts: usize,
+}
+
+impl LogPanel {
+    /// Read the entries `sink` received since the last sync.
+    fn sync(&self, sink: &MemoryLogSink) {
+        let mut cache = self.cache.borrow_mut();
+        let new = sink.entries_since(cache.seen);
+        if new.is_empty() {
+            return;
+        }
+        cache.seen += new.len();
+        for entry in new {
+            cache.recent.push_front(entry);
+        }
+        cache.recent.truncate(LOG_PANEL_ENTRIES);
+        cache.layout = None;
+    }
+
+    /// Number of entries listed.
+    pub fn visible(&self, sink: &MemoryLogSink) -> usize {
+        self.sync(sink);
+        self.cache.borrow().recent.len()
+    }
+
+    /// The panel's lines, laid out again only if something changed.
+    pub fn lines(&self, sink: &MemoryLogSink, focused: bool) -> Vec<Line<'static>> {
+        self.sync(sink);
+        let mut cache = self.cache.borrow_mut();
+        let view = focused.then_some((self.selected, self.expanded, self.pretty));
+        let layout = Some(view);
+        if cache.layout != layout {
+            let selected = view.map(|(selected, ..)| selected);
+            cache.lines = crate::ui::log_lines(&cache.recent, selected, |e| {
+                if self.expanded { self.detail(e) } else { Vec::new() }
+            });
+            cache.layout = layout;
+            cache.layouts += 1;
+        }
+        cache.lines.clone()
+    }
+
+    /// Times the lines have been laid out.
+    #[allow(dead_code)]
+    pub fn layouts(&self) -> usize {
+        self.cache.borrow().layouts
+    }
+
+    pub fn select_next(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
+        }
+    }
+
+    pub fn select_previous(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = self.selected.checked_sub(1).unwrap_or(len - 1);
+        }
+    }
+
+    pub fn toggle_expanded(&mut self) {
+        self.expanded = !self.expanded;
+    }
+
+    pub fn toggle_pretty(&mut self) {
+        self.pretty = !self.pretty;
+    }
+
+    /// Lines of `entry`'s data payload, pretty or compact; empty when it has
+    /// none.
+    pub fn detail(&self, entry: &LogEntry) -> Vec<String> {
+        let Some(data) = &entry.data else { return Vec::new() };
+        let json = if self.pretty {
+            serde_json::to_string_pretty(data)
+        } else {
+            serde_json::to_string(data)
+        };
+        json.map(|text| text.lines().map(str::to_owned).collect()).unwrap_or_default()
+    }
+}
+
+/// Latencies of the most recent queries, oldest first.
+pub struct LatencyWindow {
+    samples: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl LatencyWindow {
+    /// Samples kept by default — roughly one per column of the panel.
+    pub const DEFAULT_CAPACITY: usize = 60;
+
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Add a sample, dropping the oldest once the window is full.
+    pub fn push(&mut self, latency_ms: u64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        if self.capacity > 0 {
+            self.samples.push_back(latency_ms);
+        }
+    }
+
+    /// The window's samples, oldest first, as drawn by the sparkline.
+    pub fn data(&self) -> Vec<u64> {
+        self.samples.iter().copied().collect()
+    }
+
+    pub fn latest(&self) -> Option<u64> {
+        self.samples.back().copied()
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        self.samples.iter().copied().max()
+    }
+}
+
+impl Default for LatencyWindow {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// Cached snapshot of the memory store shown in the memory panel.
+#[derive(Default)]
+pub struct MemoryPanel {
+    /// Records sorted by key, as of the last refresh.
+    pub records: Vec<Record>,
+    pub selected: usize,
+    link: Option<MemoryLink>,
+}
+
+/// Channel pair connecting the UI to a background memory fetcher.
+pub struct MemoryLink {
+    requests: mpsc::UnboundedSender<()>,
+    snapshots: mpsc::UnboundedReceiver<Result<Vec<Record>, MemoryError>>,
+}
+
+/// Spawn a task that loads every record from `store` each time the returned
+/// link requests a refresh.
+pub fn spawn_memory_fetcher<M: MemorySystem + 'static>(store: Arc<M>) -> MemoryLink {
+    let (requests, mut pending) = mpsc::unbounded_channel::<()>();
+    let (publish, snapshots) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while pending.recv().await.is_some() {
+            if publish.send(snapshot(store.as_ref()).await).is_err() {
+                break;
+            }
+        }
+    });
+    MemoryLink { requests, snapshots }
+}
+
+async fn snapshot<M: MemorySystem>(store: &M) -> Result<Vec<Record>, MemoryError> {
+    let mut keys = store.keys().await?;
+    keys.sort();
+    let mut records = Vec::with_capacity(keys.len());
+    for key in keys {
+        match store.load(&key).await {
+            Ok(record) => records.push(record),
+            // Removed between listing and loading.
+            Err(MemoryError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(records)
+}
+
+impl MemoryPanel {
+    /// One summary row per record: key, version and last update time.
+    pub fn rows(&self) -> Vec<String> {
+        self.records
+            .iter()
+            .map(|r| {
+                format!("{} v{} {}", r.key, r.version, r.updated_at.format("%Y-%m-%d %H:%M:%S"))
+            })
+            .collect()
+    }
+
+    /// Record count and approximate size of the snapshot, e.g.
+    /// `"3 records, 1.2 KiB"`.
+    pub fn summary(&self) -> String {
+        let bytes: usize = self.records.iter().map(Record::approximate_bytes).sum();
+        let size = match bytes {
+            0..1024 => format!("{bytes} B"),
+            1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+            _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+        };
+        let plural = if self.records.len() == 1 { "" } else { "s" };
+        format!("{} record{plural}, {size}", self.records.len())
+    }
+
+    /// The selected record's value, pretty-printed.
+    pub fn selected_value(&self) -> Option<String> {
+        let record = self.records.get(self.selected)?;
+        serde_json::to_string_pretty(&record.value).ok()
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.records.is_empty() {
+            self.selected = (self.selected + 1) % self.records.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.records.is_empty() {
+            self.selected = self.selected.checked_sub(1).unwrap_or(self.records.len() - 1);
+        }
+    }
+
+    fn replace(&mut self, records: Vec<Record>) {
+        self.selected = self.selected.min(records.len().saturating_sub(1));
+        self.records = records;
+    }
+}
+
+/// Live view of the latest write to one key, or to any key under a prefix.
+///
+/// Writes arrive over an [`InMemoryStore::watch`] channel as they happen;
+/// [`App::poll_watch`] applies them without reading the store.
+#[derive(Default)]
+pub struct WatchPanel {
+    /// The watched key or prefix, once one has been picked.
+    pub prefix: Option<String>,
+    /// The most recent record written under the prefix.
+    pub latest: Option<Record>,
+    /// Writes seen since the watch began.
+    pub writes: u64,
+    store: Option<Arc<InMemoryStore>>,
+    updates: Option<mpsc::UnboundedReceiver<Record>>,
+}
+
+impl WatchPanel {
+    /// Stop following the current prefix and follow `prefix` instead.
+    fn watch(&mut self, prefix: String) {
+        let Some(store) = &self.store else { return };
+        self.updates = Some(store.watch(prefix.clone()));
+        self.prefix = Some(prefix);
+        self.latest = None;
+        self.writes = 0;
+    }
+
+    /// Apply every write received since the last call.
+    fn apply_pending(&mut self) {
+        let Some(updates) = &mut self.updates else { return };
+        while let Ok(record) = updates.try_recv() {
+            self.writes += 1;
+            self.latest = Some(record);
+        }
+    }
+
+    /// Key and version of the latest write, then its value pretty-printed.
+    pub fn lines(&self) -> Vec<String> {
+        let Some(record) = &self.latest else {
+            return match self.prefix {
+                Some(_) => vec!["waiting for writes…".to_owned()],
+                None => Vec::new(),
+            };
+        };
+        let header = format!("{} v{} ({} writes)", record.key, record.version, self.writes);
+        let mut lines = vec![header];
+        let value = serde_json::to_string_pretty(&record.value).unwrap_or_default();
+        lines.extend(value.lines().map(str::to_owned));
+        lines
+    }
+}
+
+/// An update from a query answer streaming in the background.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// The next piece of the answer.
+    Delta(String),
+    /// The answer is complete, `latency_ms` after the query was sent.
+    Done { latency_ms: u64 },
+    /// The stream broke off; what arrived before it stays in the panel.
+    Failed(String),
+}
+
+/// Spawn a task that streams the answer to `query` from `logic`, sending
+/// each chunk as it arrives and then how the stream ended.
+pub fn spawn_query_stream<L: CoreLogic + 'static>(
+    logic: Arc<L>,
+    query: Query,
+) -> mpsc::UnboundedReceiver<StreamEvent> {
+    let (events, received) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut chunks = logic.query_stream(query);
+        while let Some(chunk) = chunks.next().await {
+            let event = match chunk {
+                Ok(delta) => StreamEvent::Delta(delta),
+                Err(e) => StreamEvent::Failed(e.to_string()),
+            };
+            let failed = matches!(event, StreamEvent::Failed(_));
+            if events.send(event).is_err() || failed {
+                return;
+            }
+        }
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let _ = events.send(StreamEvent::Done { latency_ms });
+    });
+    received
+}
+
+/// The answer to the last submitted query, filled in as it streams.
+///
+/// [`App::poll_result`] appends the chunks received since the last frame
+/// and moves the query's task entry along as the stream ends or fails.
+#[derive(Default)]
+pub struct ResultPanel {
+    /// Id of the query's entry in the task panel.
+    pub task: Option<String>,
+    /// The answer received so far.
+    pub text: String,
+    /// Why the stream broke off, if it did.
+    pub error: Option<String>,
+    events: Option<mpsc::UnboundedReceiver<StreamEvent>>,
+}
+
+impl ResultPanel {
+    pub fn is_streaming(&self) -> bool {
+        self.events.is_some()
+    }
 }
 
 /// Connectivity status for a single provider.
 pub struct ProviderStatus {
+    #[allow(dead_code)]
     pub provider: Provider,
-    pub healthy: bool,
+    pub status: HealthStatus,
     pub label: &'static str,
 }
 
@@ -54,6 +482,7 @@ pub struct TaskEntry {
     pub id: String,
     pub kind: String,
     pub phase: TaskPhase,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl App {
@@ -71,21 +500,180 @@ impl App {
             running: true,
             focus: FocusPanel::Providers,
             providers: vec![
-                ProviderStatus { provider: Provider::Claude,     healthy: false, label: "Claude" },
-                ProviderStatus { provider: Provider::Gemini,     healthy: false, label: "Gemini" },
-                ProviderStatus { provider: Provider::Grok,       healthy: false, label: "Grok" },
-                ProviderStatus { provider: Provider::Manus,      healthy: false, label: "Manus" },
-                ProviderStatus { provider: Provider::OpenWeight, healthy: false, label: "OpenWeight" },
+                ProviderStatus { provider: Provider::Claude,     status: HealthStatus::Unreachable, label: "Claude" },
+                ProviderStatus { provider: Provider::Gemini,     status: HealthStatus::Unreachable, label: "Gemini" },
+                ProviderStatus { provider: Provider::Grok,       status: HealthStatus::Unreachable, label: "Grok" },
+                ProviderStatus { provider: Provider::Manus,      status: HealthStatus::Unreachable, label: "Manus" },
+                ProviderStatus { provider: Provider::OpenWeight, status: HealthStatus::Unreachable, label: "OpenWeight" },
             ],
             tasks: Vec::new(),
-            braid: BraidStatus {
-                alpha: 8.0,
-                omega: 7.0,
-                phi: 0.82,
-                status: "RESONANT",
-            },
+            metric: Box::new(DefaultCoherence),
             log_sink,
+            logs: LogPanel::default(),
+            memory: MemoryPanel::default(),
+            watch: WatchPanel::default(),
+            latency: LatencyWindow::default(),
+            result: ResultPanel::default(),
+            warning: None,
+            input: None,
+            prompt: Prompt::default(),
+        }
+    }
+
+    /// Score the braid panel with `metric` instead of [`DefaultCoherence`].
+    #[allow(dead_code)]
+    pub fn with_metric(mut self, metric: impl CoherenceMetric + 'static) -> Self {
+        self.metric = Box::new(metric);
+        self
+    }
+
+    /// The braid as scored by the app's [`CoherenceMetric`].
+    pub fn braid(&self) -> BraidStatus {
+        self.metric.compute(self)
+    }
+
+    /// Add a completed query's latency to the latency panel.
+    pub fn record_latency(&mut self, latency_ms: u64) {
+        self.latency.push(latency_ms);
+    }
+
+    /// Providers for the panel: healthy ones first, each group in its
+    /// original order.
+    pub fn providers_by_health(&self) -> Vec<&ProviderStatus> {
+        let mut sorted: Vec<&ProviderStatus> = self.providers.iter().collect();
+        sorted.sort_by_key(|p| p.status != HealthStatus::Healthy);
+        sorted
+    }
+
+    /// Number of providers currently healthy.
+    pub fn healthy_providers(&self) -> usize {
+        self.providers.iter().filter(|p| p.status == HealthStatus::Healthy).count()
+    }
+
+    /// Refresh the providers panel from
+    /// [`CoreLogic::available_providers`](orchestrator_core::CoreLogic::available_providers).
+    /// Each row shows its provider's [`ProviderInfo::status`]; providers
+    /// missing from `infos` are shown as unreachable.
+    pub fn update_providers(&mut self, infos: &[ProviderInfo]) {
+        for row in &mut self.providers {
+            row.status = infos
+                .iter()
+                .find(|i| i.provider == row.provider)
+                .map_or(HealthStatus::Unreachable, |info| info.status);
+        }
+    }
+
+    /// Run `logic`'s [startup check](DefaultLogic::validate), logging to the
+    /// log panel; while it fails, the banner shows why.
+    pub fn validate(&mut self, logic: &DefaultLogic) {
+        self.warning = logic
+            .validate(&self.log_sink)
+            .err()
+            .map(|e| format!("{e}: every query will fail"));
+    }
+
+    /// Show the contents of the store behind `link` in the memory panel.
+    pub fn with_memory(mut self, link: MemoryLink) -> Self {
+        self.memory.link = Some(link);
+        self.refresh_memory();
+        self
+    }
+
+    /// Ask the fetcher for a fresh snapshot; it arrives via [`poll_memory`](Self::poll_memory).
+    pub fn refresh_memory(&mut self) {
+        if let Some(link) = &self.memory.link {
+            let _ = link.requests.send(());
+        }
+    }
+
+    /// Apply any snapshots the fetcher has delivered since the last poll.
+    pub fn poll_memory(&mut self) {
+        let Some(link) = &mut self.memory.link else { return };
+        let mut latest = None;
+        while let Ok(snapshot) = link.snapshots.try_recv() {
+            latest = Some(snapshot);
+        }
+        match latest {
+            Some(Ok(records)) => self.memory.replace(records),
+            Some(Err(e)) => self.log_sink.emit(&LogEntry::new(
+                LogLevel::Error,
+                "memory",
+                format!("refresh failed: {e}"),
+            )),
+            None => {}
+        }
+    }
+
+    /// Let the watch panel follow keys of `store`.
+    pub fn with_watch_store(mut self, store: Arc<InMemoryStore>) -> Self {
+        self.watch.store = Some(store);
+        self
+    }
+
+    /// Open the watch prompt, starting from the current prefix.
+    pub fn begin_input(&mut self) {
+        self.input = Some(self.watch.prefix.clone().unwrap_or_default());
+        self.prompt = Prompt::Watch;
+    }
+
+    /// Open an empty query prompt.
+    pub fn begin_query(&mut self) {
+        self.input = Some(String::new());
+        self.prompt = Prompt::Query;
+    }
+
+    /// The text typed so far, if `prompt` is the one open.
+    pub fn prompt_text(&self, prompt: Prompt) -> Option<&str> {
+        self.input.as_deref().filter(|_| self.prompt == prompt)
+    }
+
+    /// Close the query prompt, returning the query typed into it unless it
+    /// is blank. `None`, leaving any other prompt open, if the query prompt
+    /// is not the one open.
+    pub fn take_query(&mut self) -> Option<String> {
+        if self.prompt != Prompt::Query {
+            return None;
         }
+        let content = self.input.take()?;
+        (!content.trim().is_empty()).then_some(content)
+    }
+
+    pub fn input_char(&mut self, c: char) {
+        if let Some(input) = &mut self.input {
+            input.push(c);
+        }
+    }
+
+    pub fn input_backspace(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.pop();
+        }
+    }
+
+    /// Close the prompt without changing the watch.
+    pub fn cancel_input(&mut self) {
+        self.input = None;
+    }
+
+    /// Close the prompt and watch the key or prefix typed into it.
+    pub fn submit_input(&mut self) {
+        let Some(prefix) = self.input.take() else { return };
+        self.log_sink.emit(&LogEntry::new(
+            LogLevel::Info,
+            "watch",
+            format!("watching `{prefix}`"),
+        ));
+        self.watch.watch(prefix);
+    }
+
+    /// Apply the writes the watch panel has received since the last poll.
+    pub fn poll_watch(&mut self) {
+        self.watch.apply_pending();
+    }
+
+    /// Number of entries listed in the log panel.
+    pub fn visible_logs(&self) -> usize {
+        self.logs.visible(&self.log_sink)
     }
 
     /// Cycle focus to the next panel.
@@ -94,19 +682,105 @@ impl App {
     }
 
     /// Register a task so it appears in the task panel.
+    ///
+    /// The panel keeps active tasks on top, most recently updated first.
+    #[allow(dead_code)]
     pub fn push_task(&mut self, task: &Task) {
+        let id = task.id.to_string();
+        let short_id = truncate_chars(&id, 8);
         self.tasks.push(TaskEntry {
-            id: task.id.to_string()[..8].to_owned(),
+            id: short_id.to_owned(),
             kind: task.meta.kind.clone(),
             phase: task.phase,
+            updated_at: task.updated_at,
         });
+        self.tasks
+            .sort_by_key(|t| (t.phase.priority(), std::cmp::Reverse(t.updated_at)));
         self.log_sink.emit(&LogEntry::new(
             LogLevel::Info,
             "task",
-            format!("task {} registered ({})", &task.id.to_string()[..8], task.meta.kind),
+            format!("task {short_id} registered ({})", task.meta.kind),
         ));
     }
 
+    /// Move the task entry `id` to `phase`.
+    pub fn set_task_phase(&mut self, id: &str, phase: TaskPhase) {
+        let Some(entry) = self.tasks.iter_mut().find(|t| t.id == id) else { return };
+        entry.phase = phase;
+        entry.updated_at = Utc::now();
+        self.tasks
+            .sort_by_key(|t| (t.phase.priority(), std::cmp::Reverse(t.updated_at)));
+    }
+
+    /// Ask `logic` for `content` and stream the answer into the result
+    /// panel, tracked as a `query` task.
+    pub fn submit_query<L: CoreLogic + 'static>(&mut self, logic: Arc<L>, content: &str) {
+        let query = Query::new(content);
+        let meta = TaskMeta::new("tui", "query", preview(content, 40));
+        let task = Task::new(meta, serde_json::json!({ "query_id": query.id }));
+        self.stream_result(&task, spawn_query_stream(logic, query));
+    }
+
+    /// Show `events` in the result panel, registering `task` as executing
+    /// until they end. A query still streaming into the panel is abandoned
+    /// and its task marked aborted.
+    pub fn stream_result(&mut self, task: &Task, events: mpsc::UnboundedReceiver<StreamEvent>) {
+        if self.result.is_streaming()
+            && let Some(previous) = self.result.task.clone()
+        {
+            self.set_task_phase(&previous, TaskPhase::Aborted);
+            self.log_sink.emit(&LogEntry::new(
+                LogLevel::Info,
+                "query",
+                format!("query {previous} aborted by a newer query"),
+            ));
+        }
+        self.push_task(task);
+        let id = truncate_chars(&task.id.to_string(), 8).to_owned();
+        self.set_task_phase(&id, TaskPhase::Executing);
+        self.result = ResultPanel { task: Some(id), events: Some(events), ..Default::default() };
+    }
+
+    /// Append the chunks streamed since the last poll, finishing the
+    /// query's task once the stream ends and recording its latency if it
+    /// completed.
+    pub fn poll_result(&mut self) {
+        let result = &mut self.result;
+        let Some(events) = &mut result.events else { return };
+        let mut latency = None;
+        let phase = loop {
+            match events.try_recv() {
+                Ok(StreamEvent::Delta(delta)) => result.text.push_str(&delta),
+                Ok(StreamEvent::Done { latency_ms }) => {
+                    latency = Some(latency_ms);
+                    break TaskPhase::Completed;
+                }
+                Ok(StreamEvent::Failed(e)) => {
+                    result.error = Some(e);
+                    break TaskPhase::Failed;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => return,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    result.error = Some("stream ended unexpectedly".to_owned());
+                    break TaskPhase::Failed;
+                }
+            }
+        };
+        result.events = None;
+        let id = result.task.clone().unwrap_or_default();
+        if let Some(e) = &result.error {
+            self.log_sink.emit(&LogEntry::new(
+                LogLevel::Error,
+                "query",
+                format!("query {id} failed: {e}"),
+            ));
+        }
+        if let Some(latency_ms) = latency {
+            self.record_latency(latency_ms);
+        }
+        self.set_task_phase(&id, phase);
+    }
+
     /// Request a graceful shutdown.
     pub fn quit(&mut self) {
         self.running = false;
@@ -128,11 +802,279 @@ impl Default for App {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn braid_status_survives_a_memory_round_trip() {
+        let braid = App::new().braid();
+        assert_eq!(braid, BraidStatus::new(8.0, 7.0, 0.82, "RESONANT"));
+
+        let store = InMemoryStore::new();
+        store.store("braid", serde_json::to_value(&braid).unwrap()).await.unwrap();
+        let value = store.load("braid").await.unwrap().value;
+        assert_eq!(value["status"], "RESONANT");
+        let restored: BraidStatus = serde_json::from_value(value).unwrap();
+        assert_eq!(restored, braid);
+
+        let dynamic = BraidStatus::new(1.0, 2.0, 0.5, format!("DRIFT {}", 3));
+        let json = serde_json::to_string(&dynamic).unwrap();
+        assert_eq!(serde_json::from_str::<BraidStatus>(&json).unwrap(), dynamic);
+    }
+
+    #[tokio::test]
+    async fn submitted_query_streams_into_the_result_panel() {
+        use orchestrator_core::logic::DefaultLogic;
+        use orchestrator_core::mock::MockAdapter;
+        use orchestrator_core::registry::AdapterRegistry;
+
+        let mut registry = AdapterRegistry::new();
+        registry.register(Arc::new(
+            MockAdapter::new(Provider::Claude).with_reply("a streamed answer").with_chunk_size(3),
+        ));
+        let mut app = App::new();
+        app.begin_query();
+        assert_eq!(app.prompt_text(Prompt::Watch), None);
+        "ask something".chars().for_each(|c| app.input_char(c));
+        let query = app.take_query().unwrap();
+        assert_eq!(app.input, None);
+        app.submit_query(Arc::new(DefaultLogic::new(registry)), &query);
+        assert!(app.result.is_streaming());
+        assert_eq!(app.tasks[0].phase, TaskPhase::Executing);
+        assert_eq!(app.tasks[0].kind, "query");
+
+        while app.result.is_streaming() {
+            tokio::task::yield_now().await;
+            app.poll_result();
+        }
+        assert_eq!(app.result.text, "a streamed answer");
+        assert_eq!(app.result.error, None);
+        assert_eq!(app.tasks[0].phase, TaskPhase::Completed);
+        assert_eq!(app.latency.data().len(), 1);
+    }
+
+    #[test]
+    fn result_panel_keeps_partial_answer_when_the_stream_fails() {
+        use orchestrator_core::protocol::TaskMeta;
+
+        let mut app = App::new();
+        let task = Task::new(TaskMeta::new("test", "query", ""), serde_json::json!({}));
+        let (events, received) = mpsc::unbounded_channel();
+        app.stream_result(&task, received);
+        let id = app.result.task.clone().unwrap();
+
+        events.send(StreamEvent::Delta("Hel".into())).unwrap();
+        app.poll_result();
+        assert_eq!(app.result.text, "Hel");
+        assert!(app.result.is_streaming());
+        assert_eq!(app.tasks[0].phase, TaskPhase::Executing);
+
+        events.send(StreamEvent::Delta("lo, wor".into())).unwrap();
+        events.send(StreamEvent::Failed("provider unavailable: reset".into())).unwrap();
+        // Sent after the failure; never shown.
+        events.send(StreamEvent::Delta("ld".into())).unwrap();
+        app.poll_result();
+        assert_eq!(app.result.text, "Hello, wor");
+        assert_eq!(app.result.error.as_deref(), Some("provider unavailable: reset"));
+        assert!(!app.result.is_streaming());
+        assert_eq!(app.tasks[0].id, id);
+        assert_eq!(app.tasks[0].phase, TaskPhase::Failed);
+        let logged = app.log_sink.entries();
+        assert_eq!(logged.last().unwrap().level, LogLevel::Error);
+
+        // A stream whose sender goes away without finishing also fails.
+        let (events, received) = mpsc::unbounded_channel();
+        app.stream_result(&task.fork(), received);
+        events.send(StreamEvent::Delta("partial".into())).unwrap();
+        drop(events);
+        app.poll_result();
+        assert_eq!(app.result.text, "partial");
+        assert_eq!(app.result.error.as_deref(), Some("stream ended unexpectedly"));
+    }
+
+    #[test]
+    fn new_query_aborts_the_one_still_streaming() {
+        use orchestrator_core::protocol::TaskMeta;
+
+        let mut app = App::new();
+        let task = Task::new(TaskMeta::new("test", "query", ""), serde_json::json!({}));
+        let (first, received) = mpsc::unbounded_channel();
+        app.stream_result(&task, received);
+        let first_id = app.result.task.clone().unwrap();
+        first.send(StreamEvent::Delta("old".into())).unwrap();
+        app.poll_result();
+
+        let (second, received) = mpsc::unbounded_channel();
+        app.stream_result(&task.fork(), received);
+        let phase = |app: &App, id: &str| app.tasks.iter().find(|t| t.id == id).unwrap().phase;
+        assert_eq!(phase(&app, &first_id), TaskPhase::Aborted);
+        // The abandoned stream is disconnected, so its producer stops.
+        assert!(first.send(StreamEvent::Delta("stale".into())).is_err());
+        assert_eq!(app.result.text, "");
+
+        second.send(StreamEvent::Done { latency_ms: 5 }).unwrap();
+        app.poll_result();
+        let second_id = app.result.task.clone().unwrap();
+        assert_eq!(phase(&app, &second_id), TaskPhase::Completed);
+        assert_eq!(phase(&app, &first_id), TaskPhase::Aborted);
+    }
+
     #[test]
     fn focus_cycles() {
         assert_eq!(FocusPanel::Providers.next(), FocusPanel::Tasks);
-        assert_eq!(FocusPanel::Tasks.next(), FocusPanel::Logs);
-        assert_eq!(FocusPanel::Logs.next(), FocusPanel::Providers);
+        assert_eq!(FocusPanel::Tasks.next(), FocusPanel::Braid);
+        assert_eq!(FocusPanel::Braid.next(), FocusPanel::Logs);
+        assert_eq!(FocusPanel::Logs.next(), FocusPanel::Memory);
+        assert_eq!(FocusPanel::Memory.next(), FocusPanel::Providers);
+    }
+
+    #[tokio::test]
+    async fn memory_snapshot_renders_rows_and_selection() {
+        use orchestrator_core::memory::InMemoryStore;
+        use serde_json::json;
+
+        let store = Arc::new(InMemoryStore::new());
+        store.store("task:b", json!({"phase": "completed"})).await.unwrap();
+        store.store("task:a", json!(1)).await.unwrap();
+        store.store("task:a", json!(2)).await.unwrap();
+
+        let mut app = App::new().with_memory(spawn_memory_fetcher(store.clone()));
+        // Wait for the fetcher to answer the initial refresh.
+        while app.memory.records.is_empty() {
+            tokio::task::yield_now().await;
+            app.poll_memory();
+        }
+
+        let rows = app.memory.rows();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("task:a v2 "), "{}", rows[0]);
+        assert!(rows[1].starts_with("task:b v1 "), "{}", rows[1]);
+        assert_eq!(app.memory.selected_value().as_deref(), Some("2"));
+        assert_eq!(
+            app.memory.summary(),
+            format!("{} records, {} B", store.len().await, store.approximate_bytes().await)
+        );
+
+        app.memory.select_next();
+        assert_eq!(
+            app.memory.selected_value().as_deref(),
+            Some("{\n  \"phase\": \"completed\"\n}")
+        );
+        app.memory.select_next();
+        assert_eq!(app.memory.selected, 0);
+        app.memory.select_previous();
+        assert_eq!(app.memory.selected, 1);
+
+        // A refresh after removal clamps the selection.
+        store.remove("task:b").await.unwrap();
+        app.refresh_memory();
+        while app.memory.records.len() != 1 {
+            tokio::task::yield_now().await;
+            app.poll_memory();
+        }
+        assert_eq!(app.memory.selected, 0);
+    }
+
+    #[tokio::test]
+    async fn watch_panel_follows_the_key_picked_at_the_prompt() {
+        use serde_json::json;
+
+        let store = Arc::new(InMemoryStore::new());
+        let mut app = App::new().with_watch_store(store.clone());
+        assert!(app.watch.lines().is_empty());
+
+        app.begin_input();
+        for c in "task:x".chars() {
+            app.input_char(c);
+        }
+        app.input_backspace();
+        app.input_char('a');
+        app.submit_input();
+        assert_eq!(app.input, None);
+        assert_eq!(app.watch.prefix.as_deref(), Some("task:a"));
+        assert_eq!(app.watch.lines(), ["waiting for writes…"]);
+
+        store.store("task:a", json!({"phase": "executing"})).await.unwrap();
+        store.store("other", json!(0)).await.unwrap();
+        store.store("task:a", json!({"phase": "completed"})).await.unwrap();
+        app.poll_watch();
+        let latest = app.watch.latest.as_ref().unwrap();
+        assert_eq!((latest.key.as_str(), latest.version), ("task:a", 2));
+        assert_eq!(latest.value, json!({"phase": "completed"}));
+        assert_eq!(app.watch.writes, 2);
+        assert_eq!(app.watch.lines()[0], "task:a v2 (2 writes)");
+
+        // Cancelling the prompt keeps the current watch.
+        app.begin_input();
+        assert_eq!(app.input.as_deref(), Some("task:a"));
+        app.input_char('!');
+        app.cancel_input();
+        assert_eq!(app.watch.prefix.as_deref(), Some("task:a"));
+    }
+
+    #[test]
+    fn provider_statuses_follow_available_providers() {
+        let info = |provider, status| ProviderInfo {
+            provider,
+            model: None,
+            tier: Default::default(),
+            healthy: status == HealthStatus::Healthy,
+            rate_limited: status == HealthStatus::RateLimited,
+            status,
+            features: Vec::new(),
+        };
+        let mut app = App::new();
+        app.update_providers(&[
+            info(Provider::Claude, HealthStatus::Healthy),
+            info(Provider::Gemini, HealthStatus::AuthFailed),
+            info(Provider::Grok, HealthStatus::RateLimited),
+        ]);
+        let statuses: Vec<HealthStatus> = app.providers.iter().map(|p| p.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                HealthStatus::Healthy,
+                HealthStatus::AuthFailed,
+                HealthStatus::RateLimited,
+                HealthStatus::Unreachable,
+                HealthStatus::Unreachable,
+            ]
+        );
+    }
+
+    #[test]
+    fn latency_window_keeps_most_recent_samples() {
+        let mut app = App::new();
+        app.latency = LatencyWindow::new(4);
+        for ms in [120, 80, 300, 95, 210, 40] {
+            app.record_latency(ms);
+        }
+        assert_eq!(app.latency.data(), [300, 95, 210, 40]);
+        assert_eq!(app.latency.latest(), Some(40));
+        assert_eq!(app.latency.max(), Some(300));
+    }
+
+    #[test]
+    fn log_detail_shows_data_payload() {
+        let mut app = App::new();
+        app.log_sink.emit(
+            &LogEntry::new(LogLevel::Info, "task", "answered")
+                .with_data(serde_json::json!({"provider": "claude", "ms": 12})),
+        );
+        let entries = app.log_sink.entries();
+        let newest = entries.last().unwrap();
+
+        assert_eq!(app.logs.detail(newest), [r#"{"ms":12,"provider":"claude"}"#]);
+        app.logs.toggle_pretty();
+        assert_eq!(
+            app.logs.detail(newest),
+            ["{", r#"  "ms": 12,"#, r#"  "provider": "claude""#, "}"]
+        );
+        // The startup entry has no payload.
+        assert!(app.logs.detail(&entries[0]).is_empty());
+
+        assert_eq!(app.visible_logs(), 2);
+        app.logs.select_next(app.visible_logs());
+        assert_eq!(app.logs.selected, 1);
+        app.logs.select_next(app.visible_logs());
+        assert_eq!(app.logs.selected, 0);
     }
 
     #[test]
@@ -145,6 +1087,33 @@ mod tests {
         assert!(!app.log_sink.entries().is_empty());
     }
 
+    #[test]
+    fn task_panel_lists_active_tasks_first() {
+        use orchestrator_core::protocol::TaskMeta;
+        use serde_json::json;
+
+        let mut app = App::new();
+        let task = |kind: &str| {
+            let meta = TaskMeta::new("test", kind, "");
+            Task::new(meta, json!({}))
+        };
+        let mut done = task("done");
+        done.initialize().unwrap();
+        done.begin_execution().unwrap();
+        done.validate(json!({})).unwrap();
+        done.complete().unwrap();
+        let queued = task("queued");
+        let mut running = task("running");
+        running.initialize().unwrap();
+        running.begin_execution().unwrap();
+
+        for t in [&done, &queued, &running] {
+            app.push_task(t);
+        }
+        let kinds: Vec<&str> = app.tasks.iter().map(|t| t.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["running", "queued", "done"]);
+    }
+
     #[test]
     fn quit_sets_flag() {
         let mut app = App::new();
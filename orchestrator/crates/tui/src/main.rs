@@ -2,48 +2,92 @@
 //!
 //! Keybindings:
 //! - `Tab`  — cycle panel focus
+//! - `r`    — refresh the memory panel
+//! - `↑`/`↓` — select a record (memory panel) or entry (logs panel)
+//! - `Enter` — show the selected log entry's data (logs panel)
+//! - `p`    — toggle pretty-printed log data (logs panel)
+//! - `w`    — pick the key (or key prefix) shown in the watch panel
+//! - `a`    — ask a query; the answer streams into the result panel
 //! - `q`    — quit
 
 mod app;
+mod terminal;
 mod ui;
 
 use std::io;
+use std::sync::Arc;
 
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use orchestrator_core::logic::DefaultLogic;
+use orchestrator_core::memory::InMemoryStore;
+use orchestrator_core::registry::AdapterRegistry;
 use ratatui::prelude::*;
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
-    // Terminal setup
-    enable_raw_mode()?;
-    io::stdout().execute(EnterAlternateScreen)?;
+    // Terminal setup — restored by the guard on return or panic
+    terminal::install_panic_hook();
+    let _guard = terminal::TerminalGuard::new(terminal::Crossterm)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
 
-    let mut app = app::App::new();
+    let store = Arc::new(InMemoryStore::new());
+    let mut app = app::App::new()
+        .with_memory(app::spawn_memory_fetcher(store.clone()))
+        .with_watch_store(store);
+    // No adapters are configured yet; the startup check flags that rather
+    // than showing every provider as merely unreachable.
+    let logic = Arc::new(DefaultLogic::new(AdapterRegistry::new()));
+    app.validate(&logic);
+    app.update_providers(&logic.registry().provider_info());
 
     // Main event loop
     while app.running {
+        app.poll_memory();
+        app.poll_watch();
+        app.poll_result();
         terminal.draw(|frame| ui::draw(frame, &app))?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') => app.quit(),
-                        KeyCode::Tab => app.cycle_focus(),
-                        _ => {}
-                    }
+        if event::poll(std::time::Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            if app.input.is_some() {
+                match key.code {
+                    KeyCode::Enter => match app.take_query() {
+                        Some(query) => app.submit_query(logic.clone(), &query),
+                        None => app.submit_input(),
+                    },
+                    KeyCode::Esc => app.cancel_input(),
+                    KeyCode::Backspace => app.input_backspace(),
+                    KeyCode::Char(c) => app.input_char(c),
+                    _ => {}
                 }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => app.quit(),
+                KeyCode::Char('w') => app.begin_input(),
+                KeyCode::Char('a') => app.begin_query(),
+                KeyCode::Tab => app.cycle_focus(),
+                KeyCode::Char('r') => app.refresh_memory(),
+                KeyCode::Down if app.focus == app::FocusPanel::Memory => app.memory.select_next(),
+                KeyCode::Up if app.focus == app::FocusPanel::Memory => {
+                    app.memory.select_previous()
+                }
+                KeyCode::Down if app.focus == app::FocusPanel::Logs => {
+                    app.logs.select_next(app.visible_logs())
+                }
+                KeyCode::Up if app.focus == app::FocusPanel::Logs => {
+                    app.logs.select_previous(app.visible_logs())
+                }
+                KeyCode::Enter if app.focus == app::FocusPanel::Logs => app.logs.toggle_expanded(),
+                KeyCode::Char('p') if app.focus == app::FocusPanel::Logs => {
+                    app.logs.toggle_pretty()
+                }
+                _ => {}
             }
         }
     }
 
-    // Terminal teardown
-    disable_raw_mode()?;
-    io::stdout().execute(LeaveAlternateScreen)?;
     Ok(())
 }
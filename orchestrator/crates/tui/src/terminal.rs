@@ -0,0 +1,117 @@
+//! Terminal setup and teardown.
+//!
+//! [`TerminalGuard`] enters raw mode and the alternate screen on creation and
+//! restores the terminal when dropped, so the user's shell is left usable even
+//! if the event loop returns early or panics.
+
+use std::io;
+
+use crossterm::{
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+
+/// The terminal state changes the TUI needs to make and undo.
+pub trait TerminalControl {
+    /// Enable raw mode and switch to the alternate screen.
+    fn enter(&mut self) -> io::Result<()>;
+
+    /// Disable raw mode and leave the alternate screen.
+    fn restore(&mut self) -> io::Result<()>;
+}
+
+/// [`TerminalControl`] backed by crossterm on stdout.
+pub struct Crossterm;
+
+impl TerminalControl for Crossterm {
+    fn enter(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        io::stdout().execute(EnterAlternateScreen)?;
+        Ok(())
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        restore_terminal()
+    }
+}
+
+/// Restore the real terminal. Safe to call more than once.
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    io::stdout().execute(LeaveAlternateScreen)?;
+    Ok(())
+}
+
+/// Restores the terminal when dropped.
+pub struct TerminalGuard<C: TerminalControl> {
+    control: C,
+}
+
+impl<C: TerminalControl> TerminalGuard<C> {
+    /// Enter the TUI terminal state; it is undone when the guard drops.
+    pub fn new(mut control: C) -> io::Result<Self> {
+        control.enter()?;
+        Ok(Self { control })
+    }
+}
+
+impl<C: TerminalControl> Drop for TerminalGuard<C> {
+    fn drop(&mut self) {
+        // Nothing useful can be done with a failure while unwinding.
+        let _ = self.control.restore();
+    }
+}
+
+/// Install a panic hook that restores the terminal before the panic message
+/// is printed, so it is readable instead of garbled by raw mode.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the calls made instead of touching the real terminal.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<&'static str>>>);
+
+    impl TerminalControl for Recorder {
+        fn enter(&mut self) -> io::Result<()> {
+            self.0.lock().unwrap().push("enter");
+            Ok(())
+        }
+
+        fn restore(&mut self) -> io::Result<()> {
+            self.0.lock().unwrap().push("restore");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn guard_restores_on_drop() {
+        let rec = Recorder::default();
+        {
+            let _guard = TerminalGuard::new(rec.clone()).unwrap();
+            assert_eq!(*rec.0.lock().unwrap(), vec!["enter"]);
+        }
+        assert_eq!(*rec.0.lock().unwrap(), vec!["enter", "restore"]);
+    }
+
+    #[test]
+    fn guard_restores_on_panic() {
+        let rec = Recorder::default();
+        let inner = rec.clone();
+        let outcome = std::panic::catch_unwind(move || {
+            let _guard = TerminalGuard::new(inner).unwrap();
+            panic!("boom");
+        });
+        assert!(outcome.is_err());
+        assert_eq!(*rec.0.lock().unwrap(), vec!["enter", "restore"]);
+    }
+}
@@ -4,33 +4,48 @@ use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Sparkline, Wrap},
     Frame,
 };
 
-use crate::app::{App, FocusPanel};
+use crate::app::{App, FocusPanel, Prompt};
+use orchestrator_core::adapter::HealthStatus;
+use orchestrator_core::protocol::{LogEntry, LogLevel};
 use orchestrator_core::task::TaskPhase;
 
 /// Draw the full UI for a single frame.
 pub fn draw(frame: &mut Frame, app: &App) {
-    // Four-panel layout: providers | tasks | braid | logs
+    let mut area = frame.area();
+    if let Some(warning) = &app.warning {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(area);
+        let banner = Paragraph::new(format!(" ⚠ {warning} ")).style(
+            Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+        );
+        frame.render_widget(banner, rows[0]);
+        area = rows[1];
+    }
+
+    // Five-panel layout: providers | tasks | braid + latency | logs | memory
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(20),
             Constraint::Percentage(20),
             Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(30),
+            Constraint::Percentage(20),
         ])
-        .split(frame.area());
+        .split(area);
 
     // ---- Provider panel ----
     let provider_items: Vec<ListItem> = app
-        .providers
-        .iter()
+        .providers_by_health()
+        .into_iter()
         .map(|p| {
-            let icon = if p.healthy { "●" } else { "○" };
-            let color = if p.healthy { Color::Green } else { Color::DarkGray };
+            let (icon, color) = health_style(p.status);
             ListItem::new(Line::from(vec![
                 Span::styled(format!("{icon} "), Style::default().fg(color)),
                 Span::raw(p.label),
@@ -39,7 +54,11 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .collect();
 
     let providers_block = Block::default()
-        .title(" Providers ")
+        .title(format!(
+            " Providers ({}/{} healthy) ",
+            app.healthy_providers(),
+            app.providers.len()
+        ))
         .borders(Borders::ALL)
         .border_style(border_style(app.focus == FocusPanel::Providers));
     let providers_list = List::new(provider_items).block(providers_block);
@@ -57,6 +76,7 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 TaskPhase::Validated => Color::Blue,
                 TaskPhase::Completed => Color::Green,
                 TaskPhase::Failed => Color::Red,
+                TaskPhase::Aborted => Color::Magenta,
             };
             ListItem::new(Line::from(vec![
                 Span::styled(format!("{:?} ", t.phase), Style::default().fg(phase_color)),
@@ -70,25 +90,58 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .borders(Borders::ALL)
         .border_style(border_style(app.focus == FocusPanel::Tasks));
     let tasks_list = List::new(task_items).block(tasks_block);
-    frame.render_widget(tasks_list, chunks[1]);
+    let query_prompt = app.prompt_text(Prompt::Query);
+    if app.result.task.is_none() && query_prompt.is_none() {
+        frame.render_widget(tasks_list, chunks[1]);
+    } else {
+        let task_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        frame.render_widget(tasks_list, task_chunks[0]);
+
+        // ---- Result panel ----
+        let result = &app.result;
+        let mut result_text: Vec<Line> = result.text.lines().map(Line::raw).collect();
+        if let Some(e) = &result.error {
+            result_text.push(Line::styled(format!("error: {e}"), Style::default().fg(Color::Red)));
+        }
+        let result_title =
+            if result.is_streaming() { " Result (streaming…) " } else { " Result " };
+        let (result_title, result_text) = match query_prompt {
+            Some(input) => (
+                " Query (enter: ask, esc: cancel) ",
+                vec![Line::from(format!("> {input}_"))],
+            ),
+            None => (result_title, result_text),
+        };
+        let result_widget = Paragraph::new(result_text).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(result_title)
+                .borders(Borders::ALL)
+                .border_style(border_style(query_prompt.is_some())),
+        );
+        frame.render_widget(result_widget, task_chunks[1]);
+    }
 
     // ---- Braid panel ----
+    let braid = app.braid();
     let braid_text = vec![
         Line::from(vec![
             Span::styled("STATUS: ", Style::default().fg(Color::DarkGray)),
-            Span::styled(app.braid.status, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled(braid.status.as_str(), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
         ]),
         Line::from(vec![
             Span::styled("ALPHA:  ", Style::default().fg(Color::DarkGray)),
-            Span::styled(format!("{:.1}", app.braid.alpha), Style::default().fg(Color::Cyan)),
+            Span::styled(format!("{:.1}", braid.alpha), Style::default().fg(Color::Cyan)),
         ]),
         Line::from(vec![
             Span::styled("OMEGA:  ", Style::default().fg(Color::DarkGray)),
-            Span::styled(format!("{:.1}", app.braid.omega), Style::default().fg(Color::Magenta)),
+            Span::styled(format!("{:.1}", braid.omega), Style::default().fg(Color::Magenta)),
         ]),
         Line::from(vec![
             Span::styled("PHI Φ:  ", Style::default().fg(Color::DarkGray)),
-            Span::styled(format!("{:.2}", app.braid.phi), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:.2}", braid.phi), Style::default().fg(Color::Yellow)),
         ]),
         Line::from(""),
         Line::from(Span::styled("  /\\  /\\  /\\", Style::default().fg(Color::Cyan))),
@@ -101,40 +154,149 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .title(" Braid Resonance ")
         .borders(Borders::ALL)
         .border_style(border_style(app.focus == FocusPanel::Braid));
+    let braid_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[2]);
     let braid_widget = Paragraph::new(braid_text).block(braid_block);
-    frame.render_widget(braid_widget, chunks[2]);
+    frame.render_widget(braid_widget, braid_chunks[0]);
+
+    // ---- Latency sparkline ----
+    let latency_title = match (app.latency.latest(), app.latency.max()) {
+        (Some(latest), Some(max)) => format!(" Latency: {latest} ms (max {max}) "),
+        _ => " Latency ".to_owned(),
+    };
+    let latency_block = Block::default()
+        .title(latency_title)
+        .borders(Borders::ALL)
+        .border_style(border_style(app.focus == FocusPanel::Braid));
+    let latency_data = app.latency.data();
+    let sparkline = Sparkline::default()
+        .block(latency_block)
+        .data(&latency_data)
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(sparkline, braid_chunks[1]);
 
     // ---- Log panel ----
-    let entries = app.log_sink.entries();
-    let log_lines: Vec<Line> = entries
-        .iter()
-        .rev()
-        .take(100)
-        .map(|e| {
+    let logs_focused = app.focus == FocusPanel::Logs;
+    let log_lines = app.logs.lines(&app.log_sink, logs_focused);
+
+    let logs_title = if logs_focused { " Logs (enter: data, p: pretty) " } else { " Logs " };
+    let logs_block = Block::default()
+        .title(logs_title)
+        .borders(Borders::ALL)
+        .border_style(border_style(app.focus == FocusPanel::Logs));
+    let logs_widget = Paragraph::new(log_lines).block(logs_block);
+    frame.render_widget(logs_widget, chunks[3]);
+
+    // ---- Memory panel ----
+    let memory_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+        ])
+        .split(chunks[4]);
+    let focused = app.focus == FocusPanel::Memory;
+    let record_items: Vec<ListItem> = app
+        .memory
+        .rows()
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let style = if i == app.memory.selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(Span::styled(row, style)))
+        })
+        .collect();
+
+    let memory_block = Block::default()
+        .title(format!(" Memory: {} (r: refresh) ", app.memory.summary()))
+        .borders(Borders::ALL)
+        .border_style(border_style(focused));
+    frame.render_widget(List::new(record_items).block(memory_block), memory_chunks[0]);
+
+    let value_block = Block::default()
+        .title(" Value ")
+        .borders(Borders::ALL)
+        .border_style(border_style(focused));
+    let value = app.memory.selected_value().unwrap_or_default();
+    frame.render_widget(Paragraph::new(value).block(value_block), memory_chunks[1]);
+
+    // ---- Watch panel ----
+    let watch_prompt = app.prompt_text(Prompt::Watch);
+    let (watch_title, watch_lines) = match (watch_prompt, &app.watch.prefix) {
+        (Some(input), _) => (
+            " Watch key (enter: watch, esc: cancel) ".to_owned(),
+            vec![Line::from(format!("> {input}_"))],
+        ),
+        (None, prefix) => (
+            match prefix {
+                Some(prefix) => format!(" Watch: {prefix} (w: change) "),
+                None => " Watch (w: pick key) ".to_owned(),
+            },
+            app.watch.lines().into_iter().map(Line::from).collect(),
+        ),
+    };
+    let watch_block = Block::default()
+        .title(watch_title)
+        .borders(Borders::ALL)
+        .border_style(border_style(watch_prompt.is_some()));
+    frame.render_widget(Paragraph::new(watch_lines).block(watch_block), memory_chunks[2]);
+}
+
+/// Lines of the log panel for `entries`, newest first. The entry at
+/// `selected` is highlighted, with its `detail` lines beneath it.
+pub fn log_lines<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    selected: Option<usize>,
+    detail: impl Fn(&LogEntry) -> Vec<String>,
+) -> Vec<Line<'static>> {
+    entries
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, e)| {
             let level_color = match e.level {
-                orchestrator_core::protocol::LogLevel::Trace => Color::DarkGray,
-                orchestrator_core::protocol::LogLevel::Debug => Color::Gray,
-                orchestrator_core::protocol::LogLevel::Info => Color::Cyan,
-                orchestrator_core::protocol::LogLevel::Warn => Color::Yellow,
-                orchestrator_core::protocol::LogLevel::Error => Color::Red,
+                LogLevel::Trace => Color::DarkGray,
+                LogLevel::Debug => Color::Gray,
+                LogLevel::Info => Color::Cyan,
+                LogLevel::Warn => Color::Yellow,
+                LogLevel::Error => Color::Red,
             };
-            Line::from(vec![
+            let selected = selected == Some(i);
+            let line = Line::from(vec![
                 Span::styled(
                     format!("{} ", e.level),
                     Style::default().fg(level_color).add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(format!("[{}] ", e.source), Style::default().fg(Color::DarkGray)),
-                Span::raw(&e.message),
-            ])
+                Span::raw(e.message.clone()),
+            ]);
+            let line = if selected {
+                line.style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                line
+            };
+            let detail = if selected { detail(e) } else { Vec::new() };
+            std::iter::once(line).chain(detail.into_iter().map(|text| {
+                Line::from(Span::styled(format!("  {text}"), Style::default().fg(Color::Gray)))
+            }))
         })
-        .collect();
+        .collect()
+}
 
-    let logs_block = Block::default()
-        .title(" Logs ")
-        .borders(Borders::ALL)
-        .border_style(border_style(app.focus == FocusPanel::Logs));
-    let logs_widget = Paragraph::new(log_lines).block(logs_block);
-    frame.render_widget(logs_widget, chunks[2]);
+/// Icon and color of a provider in the provider panel.
+fn health_style(status: HealthStatus) -> (&'static str, Color) {
+    match status {
+        HealthStatus::Healthy => ("●", Color::Green),
+        HealthStatus::AuthFailed => ("✗", Color::Red),
+        HealthStatus::RateLimited => ("◐", Color::Yellow),
+        HealthStatus::Unreachable => ("○", Color::DarkGray),
+    }
 }
 
 fn border_style(focused: bool) -> Style {
@@ -144,3 +306,181 @@ fn border_style(focused: bool) -> Style {
         Style::default().fg(Color::DarkGray)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{BraidStatus, CoherenceMetric};
+    use orchestrator_core::protocol::{LogEntry, LogLevel, LogSink};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn render(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(200, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn selected_log_entry_shows_its_data() {
+        let mut app = App::new();
+        app.log_sink.emit(
+            &LogEntry::new(LogLevel::Info, "task", "done").with_data(serde_json::json!({"n": 7})),
+        );
+        app.focus = FocusPanel::Logs;
+        assert!(!render(&app).contains(r#"{"n":7}"#));
+
+        app.logs.toggle_expanded();
+        assert!(render(&app).contains(r#"{"n":7}"#));
+    }
+
+    #[test]
+    fn empty_registry_shows_a_warning_banner() {
+        use orchestrator_core::logic::DefaultLogic;
+        use orchestrator_core::registry::AdapterRegistry;
+
+        let mut app = App::new();
+        assert!(!render(&app).contains('⚠'));
+
+        app.validate(&DefaultLogic::new(AdapterRegistry::new()));
+        let screen = render(&app);
+        assert!(screen.contains("⚠ no providers registered: every query will fail"), "{screen}");
+        let warnings: Vec<_> =
+            app.log_sink.entries().into_iter().filter(|e| e.level == LogLevel::Warn).collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].source, "logic");
+
+        let mut registry = AdapterRegistry::new();
+        registry.register(std::sync::Arc::new(orchestrator_core::mock::MockAdapter::new(
+            orchestrator_core::adapter::Provider::Claude,
+        )));
+        app.validate(&DefaultLogic::new(registry));
+        assert_eq!(app.warning, None);
+        // The banner is gone; the logged warning stays in the log panel.
+        assert!(!render(&app).contains('⚠'));
+    }
+
+    #[test]
+    fn log_panel_lays_out_only_when_entries_arrive_or_the_view_changes() {
+        let mut app = App::new();
+        for n in 0..1000 {
+            app.log_sink.emit(&LogEntry::new(LogLevel::Debug, "load", format!("entry {n}")));
+        }
+        let screen = render(&app);
+        assert!(screen.contains("entry 999"));
+        assert_eq!(app.logs.layouts(), 1);
+        assert_eq!(app.visible_logs(), crate::app::LOG_PANEL_ENTRIES);
+
+        // Frames with nothing new reuse the cached lines.
+        for _ in 0..50 {
+            assert_eq!(render(&app), screen);
+        }
+        assert_eq!(app.logs.layouts(), 1);
+
+        app.log_sink.emit(&LogEntry::new(LogLevel::Warn, "load", "entry 1000"));
+        assert!(render(&app).contains("entry 1000"));
+        assert_eq!(app.logs.layouts(), 2);
+
+        // Scrolling only counts while the panel is focused.
+        app.logs.select_next(app.visible_logs());
+        render(&app);
+        assert_eq!(app.logs.layouts(), 2);
+        app.focus = FocusPanel::Logs;
+        render(&app);
+        render(&app);
+        assert_eq!(app.logs.layouts(), 3);
+    }
+
+    /// Scores the braid by how many tasks are registered.
+    struct TaskCount;
+
+    impl CoherenceMetric for TaskCount {
+        fn compute(&self, app: &App) -> BraidStatus {
+            let n = app.tasks.len() as f64;
+            BraidStatus::new(n, n * 2.0, 0.5, "COUNTED")
+        }
+    }
+
+    #[test]
+    fn braid_panel_renders_custom_metric() {
+        let mut app = App::new().with_metric(TaskCount);
+        app.push_task(&orchestrator_core::task::Task::new(
+            orchestrator_core::protocol::TaskMeta::new("test", "probe", ""),
+            serde_json::json!({}),
+        ));
+
+        let screen = render(&app);
+        assert!(screen.contains("STATUS: COUNTED"), "{screen}");
+        assert!(screen.contains("ALPHA:  1.0"));
+        assert!(screen.contains("OMEGA:  2.0"));
+        assert!(screen.contains("0.50"));
+        assert!(!screen.contains("RESONANT"));
+    }
+
+    #[test]
+    fn aborted_task_has_its_own_color() {
+        let mut app = App::new();
+        let mut task = orchestrator_core::task::Task::new(
+            orchestrator_core::protocol::TaskMeta::new("test", "probe", ""),
+            serde_json::json!({}),
+        );
+        task.abort("user cancelled");
+        app.push_task(&task);
+
+        let mut terminal = Terminal::new(TestBackend::new(200, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        let row: String =
+            buffer.content()[width..2 * width].iter().map(|cell| cell.symbol()).collect();
+        let at = row.find("Aborted").expect("aborted task is listed");
+        let column = row[..at].chars().count();
+        assert_eq!(buffer.content()[width + column].fg, Color::Magenta);
+    }
+
+    #[test]
+    fn provider_panel_shows_each_health_status() {
+        let mut app = App::new();
+        app.providers[0].status = HealthStatus::Healthy;
+        app.providers[1].status = HealthStatus::AuthFailed;
+        app.providers[2].status = HealthStatus::RateLimited;
+
+        let mut terminal = Terminal::new(TestBackend::new(200, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        let expected = [
+            ("●", Color::Green),
+            ("✗", Color::Red),
+            ("◐", Color::Yellow),
+            ("○", Color::DarkGray),
+            ("○", Color::DarkGray),
+        ];
+        for (row, (icon, color)) in expected.into_iter().enumerate() {
+            // Inside the panel border: one row down, one column in.
+            let cell = &buffer.content()[(row + 1) * width + 1];
+            assert_eq!((cell.symbol(), cell.fg), (icon, color), "provider {row}");
+        }
+    }
+
+    #[test]
+    fn provider_panel_lists_healthy_providers_first() {
+        let mut app = App::new();
+        app.providers[1].status = HealthStatus::Healthy;
+        app.providers[3].status = HealthStatus::Healthy;
+        app.providers[4].status = HealthStatus::RateLimited;
+
+        let mut terminal = Terminal::new(TestBackend::new(200, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        let line = |row: usize| -> String {
+            buffer.content()[row * width..(row + 1) * width].iter().map(|c| c.symbol()).collect()
+        };
+        assert!(line(0).contains(" Providers (2/5 healthy) "), "{}", line(0));
+        let order: Vec<String> = (1..=5)
+            .map(|row| line(row).chars().skip(3).take_while(|c| *c != ' ').collect())
+            .collect();
+        assert_eq!(order, ["Gemini", "Manus", "Claude", "Grok", "OpenWeight"]);
+    }
+}